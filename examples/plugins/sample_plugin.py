@@ -0,0 +1,112 @@
+#!/usr/bin/env python3
+"""Sample external plugin for ssdocs.
+
+ssdocs starts this script once per build and talks to it over stdin/stdout,
+one JSON object per line. Every request carries an `id` and a `hook`; the
+reply echoes the `id` with either a `result` or an `error`. Log to stderr,
+never to stdout.
+
+Configure it in config.yaml:
+
+    plugins:
+      sample:
+        command: ["python3", "examples/plugins/sample_plugin.py"]
+        options:
+          tag: external
+
+It is also the conformance test for the protocol (see src/plugins/external.rs).
+"""
+
+import json
+import sys
+import time
+
+options = {}
+posts_seen = []
+
+
+def init(request):
+    options.update(request.get("options") or {})
+    return {
+        "hooks": [
+            "on_build_start",
+            "on_post_parsed",
+            "on_post_rendered",
+            "template_context_post",
+            "template_context_index",
+            "on_page_generated",
+            "on_build_finished",
+        ],
+        "shortcodes": ["upper"],
+    }
+
+
+def on_build_start(request):
+    posts_seen.clear()
+    return None
+
+
+def on_post_parsed(request):
+    frontmatter = request["post"]["frontmatter"]
+    tag = options.get("tag")
+    if tag and tag not in frontmatter["tags"]:
+        frontmatter["tags"].append(tag)
+    posts_seen.append(request["post"]["slug"])
+    return {"frontmatter": frontmatter}
+
+
+def on_post_rendered(request):
+    if options.get("hang_on") == "on_post_rendered":
+        time.sleep(60)
+    return {"html": request["html"] + "\n<!-- rendered by sample plugin -->"}
+
+
+def template_context_post(request):
+    return {"title_length": len(request["post"]["frontmatter"]["title"])}
+
+
+def template_context_index(request):
+    return {"sample_plugin": True}
+
+
+def on_page_generated(request):
+    return {"html": request["html"].replace("</body>", "<!-- sample --></body>")}
+
+
+def on_build_finished(request):
+    return {
+        "files": [
+            {"path": "sample-plugin.txt", "contents": "\n".join(sorted(posts_seen)) + "\n"}
+        ]
+    }
+
+
+def shortcode(request):
+    if request["name"] == "upper":
+        return {"html": (request.get("content") or "").upper()}
+    raise ValueError("unknown shortcode " + request["name"])
+
+
+HOOKS = {
+    "init": init,
+    "on_build_start": on_build_start,
+    "on_post_parsed": on_post_parsed,
+    "on_post_rendered": on_post_rendered,
+    "template_context_post": template_context_post,
+    "template_context_index": template_context_index,
+    "on_page_generated": on_page_generated,
+    "on_build_finished": on_build_finished,
+    "shortcode": shortcode,
+}
+
+for line in sys.stdin:
+    request = json.loads(line)
+    try:
+        reply = {"id": request["id"], "result": HOOKS[request["hook"]](request)}
+    except Exception as error:
+        reply = {"id": request["id"], "error": str(error)}
+    if options.get("wrong_id_on") == request["hook"]:
+        # Lets the conformance test check how a broken reply is handled
+        reply["id"] += 1000
+    sys.stdout.write(json.dumps(reply) + "\n")
+    sys.stdout.flush()
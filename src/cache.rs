@@ -1,8 +1,11 @@
+use crate::config::site_path;
+use crate::metadata::MetadataCache;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::Value as JsonValue;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BuildCache {
@@ -13,17 +16,97 @@ pub struct BuildCache {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CacheEntry {
     pub file_hash: String,
-    pub template_hash: String,
     pub output_path: String,
     pub built_at: String,
+    /// Everything the output read while it was built
+    #[serde(default)]
+    pub dependencies: Dependencies,
+}
+
+/// Dependency record for a single output.
+///
+/// Every map stores a hash of the value that was read, so an output only
+/// needs rebuilding when one of the things it actually used has changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dependencies {
+    /// Template name -> hash of the resolved template file
+    #[serde(default)]
+    pub templates: BTreeMap<String, String>,
+    /// Shortcodes invoked by the source file
+    #[serde(default)]
+    pub shortcodes: BTreeSet<String>,
+    /// Site-level metadata field -> hash of its value
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// Sibling post `category/slug` -> fingerprint of its metadata
+    #[serde(default)]
+    pub siblings: BTreeMap<String, String>,
+}
+
+impl Dependencies {
+    /// Record every post referenced from a template context as a sibling.
+    ///
+    /// Navigation links and plugin data (e.g. related posts) are serialized
+    /// with `slug` and `category` keys, so any such object naming a known post
+    /// is treated as a read of that post's metadata. `current` is the post's
+    /// own [`Dependencies::sibling_key`].
+    pub fn record_siblings(
+        &mut self,
+        current: &str,
+        context: &HashMap<String, JsonValue>,
+        metadata: &MetadataCache,
+    ) {
+        for value in context.values() {
+            self.collect_siblings(current, value, metadata);
+        }
+    }
+
+    /// How a post is named in `siblings`; slugs repeat across categories
+    pub fn sibling_key(category: &str, slug: &str) -> String {
+        format!("{}/{}", category, slug)
+    }
+
+    fn collect_siblings(&mut self, current: &str, value: &JsonValue, metadata: &MetadataCache) {
+        match value {
+            JsonValue::Array(items) => {
+                for item in items {
+                    self.collect_siblings(current, item, metadata);
+                }
+            }
+            JsonValue::Object(map) => {
+                let slug = map.get("slug").and_then(|v| v.as_str());
+                let category = map.get("category").and_then(|v| v.as_str());
+
+                if let (Some(slug), Some(category)) = (slug, category) {
+                    let key = Self::sibling_key(category, slug);
+                    if key != current {
+                        if let Some(post) = metadata
+                            .posts
+                            .iter()
+                            .find(|p| p.slug == slug && p.category == category)
+                        {
+                            // The referencing object may add to the post, e.g. a related score
+                            self.siblings.insert(key, fingerprint(&(post, map)));
+                        }
+                    }
+                }
+
+                for item in map.values() {
+                    self.collect_siblings(current, item, metadata);
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
 impl BuildCache {
-    pub fn load() -> Result<Self> {
-        let cache_path = Path::new(".build-cache/cache.json");
+    /// Load `cache.json` from the cache directory, or start empty
+    pub fn load(cache_dir: &Path) -> Result<Self> {
+        let cache_path = cache_dir.join("cache.json");
 
         if cache_path.exists() {
-            let content = fs::read_to_string(cache_path)?;
+            let content = fs::read_to_string(&cache_path)?;
             Ok(serde_json::from_str(&content)?)
         } else {
             Ok(Self::new())
@@ -37,20 +120,38 @@ impl BuildCache {
         }
     }
 
-    pub fn save(&self) -> Result<()> {
-        fs::create_dir_all(".build-cache")?;
+    pub fn save(&self, cache_dir: &Path) -> Result<()> {
+        fs::create_dir_all(cache_dir)?;
         let json = serde_json::to_string_pretty(self)?;
-        fs::write(".build-cache/cache.json", json)?;
+        fs::write(cache_dir.join("cache.json"), json)?;
         Ok(())
     }
 
-    pub fn needs_rebuild(&self, path: &Path, current_hash: &str, current_template_hash: &str) -> bool {
-        let path_str = path.to_string_lossy();
-
-        match self.entries.get(path_str.as_ref()) {
+    /// Check whether an output must be rebuilt.
+    ///
+    /// `current` holds the shortcodes, metadata and siblings the source reads
+    /// right now; `template_hashes` maps every loaded template to its hash.
+    pub fn needs_rebuild(
+        &self,
+        path: &Path,
+        current_hash: &str,
+        current: &Dependencies,
+        template_hashes: &HashMap<String, String>,
+    ) -> bool {
+        match self.entries.get(&Self::key(path)) {
             None => true,
             Some(entry) => {
-                entry.file_hash != current_hash || entry.template_hash != current_template_hash
+                let deps = &entry.dependencies;
+
+                entry.file_hash != current_hash
+                    || deps.templates.is_empty()
+                    || deps
+                        .templates
+                        .iter()
+                        .any(|(name, hash)| template_hashes.get(name) != Some(hash))
+                    || deps.shortcodes != current.shortcodes
+                    || deps.metadata != current.metadata
+                    || deps.siblings != current.siblings
             }
         }
     }
@@ -59,21 +160,30 @@ impl BuildCache {
         &mut self,
         path: &Path,
         hash: String,
-        template_hash: String,
         output: String,
+        dependencies: Dependencies,
     ) {
-        let path_str = path.to_string_lossy().to_string();
-
         self.entries.insert(
-            path_str,
+            Self::key(path),
             CacheEntry {
                 file_hash: hash,
-                template_hash,
                 output_path: output,
                 built_at: chrono::Utc::now().to_rfc3339(),
+                dependencies,
             },
         );
     }
+
+    /// Forget an output, so it is built again once its source is published
+    pub fn remove_entry(&mut self, path: &Path) {
+        self.entries.remove(&Self::key(path));
+    }
+
+    /// Sources are keyed relative to the site root, as watch mode reports
+    /// them, however `content_dir` is written
+    pub fn key(path: &Path) -> String {
+        site_path(path).to_string_lossy().to_string()
+    }
 }
 
 impl Default for BuildCache {
@@ -82,38 +192,94 @@ impl Default for BuildCache {
     }
 }
 
-pub fn hash_file(path: &Path) -> Result<String> {
-    let content = fs::read(path)?;
-    let hash = blake3::hash(&content);
-    Ok(hash.to_hex().to_string())
+/// The listing pages and feeds the last build wrote, so the next one can
+/// remove those it no longer writes without touching post output
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Listings {
+    output_dir: String,
+    /// Paths relative to `output_dir`
+    files: BTreeSet<PathBuf>,
 }
 
-pub fn hash_directory(dir: &Path) -> Result<String> {
-    use walkdir::WalkDir;
+impl Listings {
+    /// Load `listings.json` from the cache directory, or start empty
+    pub fn load(cache_dir: &Path) -> Result<Self> {
+        let path = cache_dir.join("listings.json");
+
+        if path.exists() {
+            let content = fs::read_to_string(&path)?;
+            Ok(serde_json::from_str(&content)?)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn save(&self, cache_dir: &Path) -> Result<()> {
+        fs::create_dir_all(cache_dir)?;
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(cache_dir.join("listings.json"), json)?;
+        Ok(())
+    }
+
+    /// Record the files written under `output_dir`
+    pub fn new(output_dir: &Path, written: impl IntoIterator<Item = PathBuf>) -> Self {
+        let files = written
+            .into_iter()
+            .map(|path| match path.strip_prefix(output_dir) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => path,
+            })
+            .collect();
+        Self {
+            output_dir: site_path(output_dir).to_string_lossy().to_string(),
+            files,
+        }
+    }
 
-    let mut hasher = blake3::Hasher::new();
-    let mut files: Vec<_> = WalkDir::new(dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().is_file())
-        .collect();
+    /// Delete the files this record holds that `current` does not, then the
+    /// directories that leaves empty. A record of another output directory
+    /// is ignored.
+    pub fn remove_stale(&self, current: &Listings, output_dir: &Path) -> Result<()> {
+        if self.output_dir != current.output_dir {
+            return Ok(());
+        }
 
-    files.sort_by_key(|e| e.path().to_path_buf());
+        for file in self.files.difference(&current.files) {
+            let path = output_dir.join(file);
+            if !path.is_file() {
+                continue;
+            }
+            fs::remove_file(&path)?;
 
-    for entry in files {
-        let path = entry.path();
-        if let Ok(content) = fs::read(path) {
-            hasher.update(path.to_string_lossy().as_bytes());
-            hasher.update(&content);
+            let mut dir = path.parent();
+            while let Some(parent) = dir.filter(|d| *d != output_dir) {
+                if fs::remove_dir(parent).is_err() {
+                    break;
+                }
+                dir = parent.parent();
+            }
         }
+
+        Ok(())
     }
+}
 
-    Ok(hasher.finalize().to_hex().to_string())
+pub fn hash_file(path: &Path) -> Result<String> {
+    let content = fs::read(path)?;
+    let hash = blake3::hash(&content);
+    Ok(hash.to_hex().to_string())
+}
+
+/// Stable hash of any serializable value
+pub fn fingerprint<T: Serialize + ?Sized>(value: &T) -> String {
+    let json = serde_json::to_vec(value).unwrap_or_default();
+    blake3::hash(&json).to_hex().to_string()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::reading::ReadingStats;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -129,28 +295,151 @@ mod tests {
         assert_eq!(hash1.len(), 64);
     }
 
+    fn create_test_dependencies() -> (Dependencies, HashMap<String, String>) {
+        let mut deps = Dependencies::default();
+        deps.templates
+            .insert("post.html".to_string(), "tpl1".to_string());
+        deps.templates
+            .insert("base.html".to_string(), "tpl2".to_string());
+        deps.metadata
+            .insert("site".to_string(), "site1".to_string());
+        deps.siblings
+            .insert("other-post".to_string(), "fp1".to_string());
+
+        let template_hashes = deps.templates.clone().into_iter().collect();
+        (deps, template_hashes)
+    }
+
     #[test]
     fn test_cache_needs_rebuild() {
         let cache = BuildCache::new();
         let path = Path::new("test.md");
+        let (deps, template_hashes) = create_test_dependencies();
 
-        assert!(cache.needs_rebuild(path, "abc123", "template_hash"));
+        assert!(cache.needs_rebuild(path, "abc123", &deps, &template_hashes));
     }
 
     #[test]
     fn test_cache_update_entry() {
         let mut cache = BuildCache::new();
         let path = Path::new("test.md");
+        let (deps, template_hashes) = create_test_dependencies();
 
         cache.update_entry(
             path,
             "abc123".to_string(),
-            "def456".to_string(),
             "dist/test/index.html".to_string(),
+            deps.clone(),
+        );
+
+        assert!(!cache.needs_rebuild(path, "abc123", &deps, &template_hashes));
+        assert!(cache.needs_rebuild(path, "different_hash", &deps, &template_hashes));
+    }
+
+    #[test]
+    fn test_entries_keyed_relative_to_site_root() {
+        let mut cache = BuildCache::new();
+        let (deps, template_hashes) = create_test_dependencies();
+        let cwd = std::env::current_dir().unwrap();
+
+        cache.update_entry(
+            &cwd.join("content/posts/dev/a.md"),
+            "abc123".to_string(),
+            String::new(),
+            deps.clone(),
+        );
+        cache.update_entry(
+            Path::new("./content/posts/dev/a.md"),
+            "abc123".to_string(),
+            String::new(),
+            deps.clone(),
+        );
+
+        assert_eq!(cache.entries.len(), 1);
+        let path = Path::new("content/posts/dev/a.md");
+        assert!(!cache.needs_rebuild(path, "abc123", &deps, &template_hashes));
+        cache.remove_entry(path);
+        assert!(cache.entries.is_empty());
+    }
+
+    #[test]
+    fn test_only_templates_read_trigger_rebuild() {
+        let mut cache = BuildCache::new();
+        let path = Path::new("test.md");
+        let (deps, mut template_hashes) = create_test_dependencies();
+
+        cache.update_entry(path, "abc123".to_string(), String::new(), deps.clone());
+
+        template_hashes.insert("tag.html".to_string(), "changed".to_string());
+        assert!(!cache.needs_rebuild(path, "abc123", &deps, &template_hashes));
+
+        template_hashes.insert("base.html".to_string(), "changed".to_string());
+        assert!(cache.needs_rebuild(path, "abc123", &deps, &template_hashes));
+    }
+
+    #[test]
+    fn test_sibling_change_triggers_rebuild() {
+        let mut cache = BuildCache::new();
+        let path = Path::new("test.md");
+        let (deps, template_hashes) = create_test_dependencies();
+
+        cache.update_entry(path, "abc123".to_string(), String::new(), deps.clone());
+
+        let mut current = deps.clone();
+        current
+            .siblings
+            .insert("new-post".to_string(), "fp2".to_string());
+        assert!(cache.needs_rebuild(path, "abc123", &current, &template_hashes));
+
+        let mut current = deps.clone();
+        current
+            .siblings
+            .insert("other-post".to_string(), "fp-renamed".to_string());
+        assert!(cache.needs_rebuild(path, "abc123", &current, &template_hashes));
+    }
+
+    #[test]
+    fn test_record_siblings_from_context() {
+        use crate::types::{Frontmatter, PostDate};
+
+        let mut metadata = MetadataCache::new();
+        for slug in ["a", "b", "c"] {
+            metadata.upsert_post(
+                slug.to_string(),
+                "dev".to_string(),
+                Frontmatter {
+                    title: slug.to_string(),
+                    date: PostDate::new(chrono::Utc::now()),
+                    tags: vec![],
+                    featured_image: None,
+                    description: None,
+                    draft: false,
+                    series: None,
+                    series_order: None,
+                    expires: None,
+                    lang: None,
+                    translation_key: None,
+                },
+                ReadingStats::default(),
+            );
+        }
+
+        let mut context = HashMap::new();
+        context.insert(
+            "prev_post".to_string(),
+            serde_json::json!({ "slug": "a", "category": "dev", "title": "a" }),
         );
+        context.insert(
+            "related_posts".to_string(),
+            serde_json::json!([{ "slug": "c", "category": "dev" }, { "slug": "b", "category": "dev" }]),
+        );
+
+        let mut deps = Dependencies::default();
+        deps.record_siblings("dev/b", &context, &metadata);
 
-        assert!(!cache.needs_rebuild(path, "abc123", "def456"));
-        assert!(cache.needs_rebuild(path, "different_hash", "def456"));
-        assert!(cache.needs_rebuild(path, "abc123", "different_template_hash"));
+        assert_eq!(
+            deps.siblings.keys().collect::<Vec<_>>(),
+            vec!["dev/a", "dev/c"]
+        );
     }
 }
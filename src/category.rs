@@ -2,6 +2,7 @@ use anyhow::{Context, Result};
 use std::fs;
 use std::path::Path;
 
+use crate::diagnostics::SourceError;
 use crate::types::Category;
 
 pub fn discover_categories(content_dir: &Path) -> Result<Vec<Category>> {
@@ -53,7 +54,7 @@ fn load_category_metadata(dir: &Path, slug: &str) -> Result<Category> {
             )
         })?;
         serde_yaml::from_str::<Category>(&content)
-            .with_context(|| format!("Failed to parse .category.yaml in '{}'", dir.display()))?
+            .map_err(|e| SourceError::yaml(&metadata_path, &e))?
     } else {
         Category {
             slug: slug.to_string(),
@@ -1,8 +1,11 @@
-use anyhow::{Context, Result};
+use crate::diagnostics::SourceError;
+use crate::i18n::Language;
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
 /// Site configuration from config.yaml
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +18,30 @@ pub struct SiteConfig {
     pub author: String,
     #[serde(default = "default_description")]
     pub description: String,
+    /// Code of the main language; its pages have no URL prefix (default: ko)
+    #[serde(default = "default_language")]
+    pub language: String,
+    /// Every language posts may be written in, keyed by code
+    #[serde(default = "default_languages")]
+    pub languages: BTreeMap<String, LanguageConfig>,
+}
+
+/// A site language from config.yaml
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LanguageConfig {
+    /// Display name, e.g. for a language switcher (default: the code)
+    #[serde(default)]
+    pub name: Option<String>,
+    /// BCP 47 tag used for `hreflang`, `<html lang>` and feeds (default: the code)
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Site title for this language (default: `site.title`)
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Site description for this language (default: `site.description`)
+    #[serde(default)]
+    pub description: Option<String>,
 }
 
 /// Theme configuration from config.yaml
@@ -34,12 +61,61 @@ pub struct SearchConfig {
     /// Enable search index generation (default: true)
     #[serde(default = "default_search_enabled")]
     pub enabled: bool,
+    /// Index the plain text of every post body (default: false)
+    #[serde(default)]
+    pub full_text: bool,
+    /// Split full-text terms into this many JSON files (default: 1, no sharding)
+    #[serde(default = "default_search_shards")]
+    pub shards: usize,
+    /// N-gram size used to tokenize CJK text (default: 2)
+    #[serde(default = "default_search_ngram")]
+    pub ngram: usize,
+    /// Field weights used when ranking query results
+    #[serde(default)]
+    pub boosts: SearchBoosts,
+}
+
+/// Weight of a match in each field relative to the body
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SearchBoosts {
+    #[serde(default = "default_title_boost")]
+    pub title: f64,
+    #[serde(default = "default_tags_boost")]
+    pub tags: f64,
+    #[serde(default = "default_body_boost")]
+    pub body: f64,
+}
+
+impl Default for SearchBoosts {
+    fn default() -> Self {
+        Self {
+            title: default_title_boost(),
+            tags: default_tags_boost(),
+            body: default_body_boost(),
+        }
+    }
+}
+
+fn default_title_boost() -> f64 {
+    3.0
+}
+
+fn default_tags_boost() -> f64 {
+    2.0
+}
+
+fn default_body_boost() -> f64 {
+    1.0
 }
 
 impl Default for SearchConfig {
     fn default() -> Self {
         Self {
             enabled: default_search_enabled(),
+            full_text: false,
+            shards: default_search_shards(),
+            ngram: default_search_ngram(),
+            boosts: SearchBoosts::default(),
         }
     }
 }
@@ -48,13 +124,208 @@ fn default_search_enabled() -> bool {
     true
 }
 
+fn default_search_shards() -> usize {
+    1
+}
+
+fn default_search_ngram() -> usize {
+    2
+}
+
+/// Syndication feed formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedFormat {
+    /// RSS 2.0 (`feed.xml`)
+    Rss,
+    /// Atom 1.0 (`atom.xml`)
+    Atom,
+    /// JSON Feed 1.1 (`feed.json`)
+    Json,
+}
+
+/// Feed configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedConfig {
+    /// Formats to generate for the site and every category (default: [rss])
+    #[serde(default = "default_feed_formats")]
+    pub formats: Vec<FeedFormat>,
+    /// Number of posts per feed (default: 10)
+    #[serde(default = "default_feed_limit")]
+    pub limit: usize,
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        Self {
+            formats: default_feed_formats(),
+            limit: default_feed_limit(),
+        }
+    }
+}
+
+fn default_feed_formats() -> Vec<FeedFormat> {
+    vec![FeedFormat::Rss]
+}
+
+fn default_feed_limit() -> usize {
+    10
+}
+
+/// Reading speed used to estimate reading time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadingConfig {
+    /// Words per minute for non-CJK text (default: 200)
+    #[serde(default = "default_words_per_minute")]
+    pub words_per_minute: usize,
+    /// Characters per minute for Korean, Chinese and Japanese text (default: 500)
+    #[serde(default = "default_cjk_chars_per_minute")]
+    pub cjk_chars_per_minute: usize,
+}
+
+impl Default for ReadingConfig {
+    fn default() -> Self {
+        Self {
+            words_per_minute: default_words_per_minute(),
+            cjk_chars_per_minute: default_cjk_chars_per_minute(),
+        }
+    }
+}
+
+fn default_words_per_minute() -> usize {
+    200
+}
+
+fn default_cjk_chars_per_minute() -> usize {
+    500
+}
+
+/// Excerpt configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcerptConfig {
+    /// Comment that ends the excerpt when present in a post (default: `<!-- more -->`)
+    #[serde(default = "default_excerpt_separator")]
+    pub separator: String,
+    /// Characters of text to keep when a post has no separator; 0 disables (default: 200)
+    #[serde(default = "default_excerpt_length")]
+    pub length: usize,
+}
+
+impl Default for ExcerptConfig {
+    fn default() -> Self {
+        Self {
+            separator: default_excerpt_separator(),
+            length: default_excerpt_length(),
+        }
+    }
+}
+
+fn default_excerpt_separator() -> String {
+    "<!-- more -->".to_string()
+}
+
+fn default_excerpt_length() -> usize {
+    200
+}
+
+/// Table of contents and heading anchor configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TocConfig {
+    /// Append a `#` self-link to every heading (default: false)
+    #[serde(default)]
+    pub anchors: bool,
+}
+
+/// Sitemap configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SitemapConfig {
+    /// Generate sitemap.xml (default: true)
+    #[serde(default = "default_sitemap_enabled")]
+    pub enabled: bool,
+}
+
+impl Default for SitemapConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_sitemap_enabled(),
+        }
+    }
+}
+
+fn default_sitemap_enabled() -> bool {
+    true
+}
+
+/// robots.txt configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RobotsConfig {
+    /// Generate robots.txt (default: true)
+    #[serde(default = "default_robots_enabled")]
+    pub enabled: bool,
+    /// One group per user agent (default: allow everything for `*`)
+    #[serde(default = "default_robots_rules")]
+    pub rules: Vec<RobotsRule>,
+}
+
+/// A `User-agent` group in robots.txt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RobotsRule {
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub disallow: Vec<String>,
+}
+
+impl Default for RobotsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_robots_enabled(),
+            rules: default_robots_rules(),
+        }
+    }
+}
+
+fn default_robots_enabled() -> bool {
+    true
+}
+
+fn default_robots_rules() -> Vec<RobotsRule> {
+    vec![RobotsRule {
+        user_agent: default_user_agent(),
+        allow: Vec::new(),
+        disallow: Vec::new(),
+    }]
+}
+
+fn default_user_agent() -> String {
+    "*".to_string()
+}
+
 /// Build configuration from config.yaml
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildConfig {
+    /// Posts, one directory per category (default: content/posts)
     #[serde(default = "default_content_dir")]
     pub content_dir: String,
     #[serde(default = "default_output_dir")]
     pub output_dir: String,
+    /// Standalone pages such as `about.md` (default: content/pages)
+    #[serde(default = "default_pages_dir")]
+    pub pages_dir: String,
+    /// Files copied to the output as they are (default: static)
+    #[serde(default = "default_static_dir")]
+    pub static_dir: String,
+    /// Directory holding one directory per theme (default: themes)
+    #[serde(default = "default_themes_dir")]
+    pub themes_dir: String,
+    /// Where incremental builds keep their state (default: .build-cache)
+    #[serde(default = "default_cache_dir")]
+    pub cache_dir: String,
+    /// Site translations, overriding the theme's (default: i18n)
+    #[serde(default = "default_i18n_dir")]
+    pub i18n_dir: String,
     #[serde(default = "default_posts_per_page")]
     pub posts_per_page: usize,
     /// Maximum number of page links to show in pagination (default: 5)
@@ -68,10 +339,95 @@ pub struct BuildConfig {
     /// Search index configuration
     #[serde(default)]
     pub search: SearchConfig,
+    /// Feed configuration
+    #[serde(default)]
+    pub feeds: FeedConfig,
+    /// Reading time configuration
+    #[serde(default)]
+    pub reading: ReadingConfig,
+    /// Excerpt configuration
+    #[serde(default)]
+    pub excerpt: ExcerptConfig,
+    /// Table of contents configuration
+    #[serde(default)]
+    pub toc: TocConfig,
+    /// Sitemap configuration
+    #[serde(default)]
+    pub sitemap: SitemapConfig,
+    /// robots.txt configuration
+    #[serde(default)]
+    pub robots: RobotsConfig,
 }
 
-/// Complete config.yaml structure
+/// Per-plugin configuration from config.yaml
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    /// Run the plugin (default: true)
+    #[serde(default = "default_plugin_enabled")]
+    pub enabled: bool,
+    /// Plugin-specific options, validated by the plugin itself
+    #[serde(default)]
+    pub options: serde_yaml::Value,
+    /// Program and arguments of an external plugin, e.g. `["python3", "plugins/tags.py"]`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+    /// How long an external plugin may take to answer one call, in milliseconds
+    /// (default: 10000)
+    #[serde(default = "default_plugin_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_plugin_enabled() -> bool {
+    true
+}
+
+fn default_plugin_timeout_ms() -> u64 {
+    10_000
+}
+
+impl Default for PluginConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_plugin_enabled(),
+            options: serde_yaml::Value::Null,
+            command: None,
+            timeout_ms: default_plugin_timeout_ms(),
+        }
+    }
+}
+
+/// Dev server configuration for `ssg watch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address to listen on; `0.0.0.0` serves other devices too (default: 127.0.0.1)
+    #[serde(default = "default_server_host")]
+    pub host: String,
+    /// Port to listen on (default: 8080)
+    #[serde(default = "default_server_port")]
+    pub port: u16,
+}
+
+fn default_server_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_server_port() -> u16 {
+    8080
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: default_server_host(),
+            port: default_server_port(),
+        }
+    }
+}
+
+/// Complete config.yaml structure
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SsgConfig {
     #[serde(default)]
     pub site: SiteConfig,
@@ -79,6 +435,115 @@ pub struct SsgConfig {
     pub theme: ThemeConfig,
     #[serde(default)]
     pub build: BuildConfig,
+    /// Plugin settings keyed by plugin name
+    #[serde(default)]
+    pub plugins: BTreeMap<String, PluginConfig>,
+    #[serde(default)]
+    pub server: ServerConfig,
+}
+
+impl SsgConfig {
+    /// Every configured language, the main one first
+    pub fn languages(&self) -> Vec<Language> {
+        let default = &self.site.language;
+        std::iter::once(default)
+            .chain(self.site.languages.keys().filter(|code| *code != default))
+            .map(|code| self.language_unchecked(code))
+            .collect()
+    }
+
+    /// A configured language by code
+    pub fn language(&self, code: &str) -> Option<Language> {
+        (code == self.site.language || self.site.languages.contains_key(code))
+            .then(|| self.language_unchecked(code))
+    }
+
+    /// The main language
+    pub fn default_language(&self) -> Language {
+        self.language_unchecked(&self.site.language)
+    }
+
+    fn language_unchecked(&self, code: &str) -> Language {
+        let language = self.site.languages.get(code).cloned().unwrap_or_default();
+        let is_default = code == self.site.language;
+        Language {
+            code: code.to_string(),
+            name: language.name.unwrap_or_else(|| code.to_string()),
+            locale: language.locale.unwrap_or_else(|| code.to_string()),
+            prefix: if is_default {
+                String::new()
+            } else {
+                format!("/{}", code)
+            },
+            is_default,
+        }
+    }
+
+    /// The config one language's listings and feeds are built with, as if the
+    /// language were a site of its own: it becomes `site.language`,
+    /// `site.url` and `build.output_dir` gain its prefix, and title and
+    /// description take its overrides
+    pub fn localized(&self, language: &Language) -> SsgConfig {
+        let mut config = self.clone();
+        config.site.language = language.code.clone();
+        config.site.url = format!("{}{}", self.site.url.trim_end_matches('/'), language.prefix);
+        config.build.output_dir = format!(
+            "{}{}",
+            self.build.output_dir.trim_end_matches('/'),
+            language.prefix
+        );
+        if let Some(overrides) = self.site.languages.get(&language.code) {
+            if let Some(title) = &overrides.title {
+                config.site.title = title.clone();
+            }
+            if let Some(description) = &overrides.description {
+                config.site.description = description.clone();
+            }
+        }
+        config
+    }
+
+    /// Check settings serde cannot check on its own
+    pub fn validate(&self) -> Result<()> {
+        if !self.site.languages.contains_key(&self.site.language) {
+            bail!(
+                "site.language '{}' is not listed under site.languages",
+                self.site.language
+            );
+        }
+        if let Some(code) = self.site.languages.keys().find(|code| {
+            code.is_empty()
+                || !code
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        }) {
+            bail!("Invalid language code '{}' in site.languages", code);
+        }
+        // Every markdown file under content_dir is a post
+        if Path::new(&self.build.pages_dir).starts_with(&self.build.content_dir) {
+            bail!(
+                "build.pages_dir '{}' must not be inside build.content_dir '{}'",
+                self.build.pages_dir,
+                self.build.content_dir
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the named plugin should run; plugins missing from the map are enabled
+    pub fn plugin_enabled(&self, name: &str) -> bool {
+        self.plugins.get(name).is_none_or(|plugin| plugin.enabled)
+    }
+
+    /// Deserialize the options block of the named plugin, falling back to
+    /// `T::default()` when the plugin has none
+    pub fn plugin_options<T: DeserializeOwned + Default>(&self, name: &str) -> Result<T> {
+        match self.plugins.get(name).map(|plugin| &plugin.options) {
+            None | Some(serde_yaml::Value::Null) => Ok(T::default()),
+            Some(options) => serde_yaml::from_value(options.clone())
+                .with_context(|| format!("Invalid options for plugin '{}'", name)),
+        }
+    }
 }
 
 impl Default for SiteConfig {
@@ -88,6 +553,8 @@ impl Default for SiteConfig {
             url: default_site_url(),
             author: default_author(),
             description: default_description(),
+            language: default_language(),
+            languages: default_languages(),
         }
     }
 }
@@ -107,20 +574,21 @@ impl Default for BuildConfig {
         Self {
             content_dir: default_content_dir(),
             output_dir: default_output_dir(),
+            pages_dir: default_pages_dir(),
+            static_dir: default_static_dir(),
+            themes_dir: default_themes_dir(),
+            cache_dir: default_cache_dir(),
+            i18n_dir: default_i18n_dir(),
             posts_per_page: default_posts_per_page(),
             pagination_window: default_pagination_window(),
             encode_filenames: false,
             search: SearchConfig::default(),
-        }
-    }
-}
-
-impl Default for SsgConfig {
-    fn default() -> Self {
-        Self {
-            site: SiteConfig::default(),
-            theme: ThemeConfig::default(),
-            build: BuildConfig::default(),
+            feeds: FeedConfig::default(),
+            reading: ReadingConfig::default(),
+            excerpt: ExcerptConfig::default(),
+            toc: TocConfig::default(),
+            sitemap: SitemapConfig::default(),
+            robots: RobotsConfig::default(),
         }
     }
 }
@@ -141,6 +609,21 @@ fn default_description() -> String {
     "marshallku blog".to_string()
 }
 
+fn default_language() -> String {
+    "ko".to_string()
+}
+
+fn default_languages() -> BTreeMap<String, LanguageConfig> {
+    BTreeMap::from([(
+        "ko".to_string(),
+        LanguageConfig {
+            name: Some("한국어".to_string()),
+            locale: Some("ko-KR".to_string()),
+            ..Default::default()
+        },
+    )])
+}
+
 fn default_theme_name() -> String {
     "default".to_string()
 }
@@ -153,6 +636,26 @@ fn default_output_dir() -> String {
     "dist".to_string()
 }
 
+fn default_pages_dir() -> String {
+    "content/pages".to_string()
+}
+
+fn default_static_dir() -> String {
+    "static".to_string()
+}
+
+fn default_themes_dir() -> String {
+    "themes".to_string()
+}
+
+fn default_cache_dir() -> String {
+    ".build-cache".to_string()
+}
+
+fn default_i18n_dir() -> String {
+    "i18n".to_string()
+}
+
 fn default_posts_per_page() -> usize {
     10
 }
@@ -171,11 +674,25 @@ pub fn load_config() -> Result<SsgConfig> {
     let content = fs::read_to_string(config_path).context("Failed to read config.yaml")?;
 
     let config: SsgConfig =
-        serde_yaml::from_str(&content).context("Failed to parse config.yaml")?;
+        serde_yaml::from_str(&content).map_err(|e| SourceError::yaml(config_path, &e))?;
+    config.validate()?;
 
     Ok(config)
 }
 
+/// A configured path relative to the site root, the working directory, so
+/// `./notes`, `notes` and its absolute path all compare equal
+pub fn site_path(dir: impl AsRef<Path>) -> PathBuf {
+    let path = dir.as_ref();
+    let path = match std::env::current_dir() {
+        Ok(root) if path.is_absolute() => path.strip_prefix(&root).unwrap_or(path),
+        _ => path,
+    };
+    path.components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -186,5 +703,144 @@ mod tests {
         assert_eq!(config.site.title, "marshallku blog");
         assert_eq!(config.theme.name, "default");
         assert_eq!(config.build.posts_per_page, 10);
+        assert_eq!(config.build.feeds.formats, vec![FeedFormat::Rss]);
+        assert_eq!(config.build.feeds.limit, 10);
+        assert_eq!(config.build.reading.words_per_minute, 200);
+        assert_eq!(config.build.reading.cjk_chars_per_minute, 500);
+        assert_eq!(config.build.excerpt.separator, "<!-- more -->");
+        assert_eq!(config.build.excerpt.length, 200);
+        assert_eq!(config.server.host, "127.0.0.1");
+        assert_eq!(config.server.port, 8080);
+        assert_eq!(config.build.pages_dir, "content/pages");
+        assert_eq!(config.build.cache_dir, ".build-cache");
+        assert_eq!(config.build.i18n_dir, "i18n");
+    }
+
+    #[test]
+    fn test_pages_dir_outside_content_dir() {
+        let yaml = "build:\n  content_dir: site\n  pages_dir: site/pages\n";
+        let config: SsgConfig = serde_yaml::from_str(yaml).unwrap();
+        assert!(config.validate().is_err());
+
+        let yaml = "build:\n  content_dir: site/posts\n  pages_dir: site/pages\n";
+        let config: SsgConfig = serde_yaml::from_str(yaml).unwrap();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_feed_config() {
+        let yaml = r#"
+build:
+  feeds:
+    formats: [rss, atom, json]
+    limit: 20
+"#;
+        let config: SsgConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            config.build.feeds.formats,
+            vec![FeedFormat::Rss, FeedFormat::Atom, FeedFormat::Json]
+        );
+        assert_eq!(config.build.feeds.limit, 20);
+    }
+
+    #[test]
+    fn test_robots_config() {
+        let yaml = r#"
+build:
+  robots:
+    rules:
+      - disallow: ["/private/"]
+      - user_agent: "GPTBot"
+        disallow: ["/"]
+"#;
+        let config: SsgConfig = serde_yaml::from_str(yaml).unwrap();
+        let rules = &config.build.robots.rules;
+        assert!(config.build.robots.enabled);
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].user_agent, "*");
+        assert_eq!(rules[0].disallow, vec!["/private/"]);
+        assert_eq!(rules[1].user_agent, "GPTBot");
+        assert_eq!(SsgConfig::default().build.robots.rules.len(), 1);
+    }
+
+    #[test]
+    fn test_plugin_config() {
+        #[derive(Debug, Default, Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Options {
+            limit: usize,
+        }
+
+        let yaml = r#"
+plugins:
+  related_posts:
+    options:
+      limit: 5
+  search_hints:
+    enabled: false
+  broken:
+    options:
+      limt: 5
+"#;
+        let config: SsgConfig = serde_yaml::from_str(yaml).unwrap();
+        assert!(config.plugin_enabled("related_posts"));
+        assert!(!config.plugin_enabled("search_hints"));
+        assert!(config.plugin_enabled("unconfigured"));
+
+        let options: Options = config.plugin_options("related_posts").unwrap();
+        assert_eq!(options.limit, 5);
+        let options: Options = config.plugin_options("search_hints").unwrap();
+        assert_eq!(options.limit, 0);
+
+        let error = config.plugin_options::<Options>("broken").unwrap_err();
+        assert_eq!(error.to_string(), "Invalid options for plugin 'broken'");
+        assert!(format!("{:#}", error).contains("unknown field `limt`"));
+    }
+
+    #[test]
+    fn test_languages() {
+        let config = SsgConfig::default();
+        config.validate().unwrap();
+        let languages = config.languages();
+        assert_eq!(languages.len(), 1);
+        assert_eq!(languages[0].locale, "ko-KR");
+        assert_eq!(languages[0].prefix, "");
+
+        let yaml = r#"
+site:
+  url: https://example.com/
+  language: en
+  languages:
+    en:
+      name: English
+      locale: en-US
+    ko:
+      title: 한국어 블로그
+"#;
+        let config: SsgConfig = serde_yaml::from_str(yaml).unwrap();
+        config.validate().unwrap();
+        let codes: Vec<_> = config.languages().into_iter().map(|l| l.code).collect();
+        assert_eq!(codes, vec!["en", "ko"]);
+
+        let ko = config.language("ko").unwrap();
+        assert_eq!((ko.prefix.as_str(), ko.locale.as_str()), ("/ko", "ko"));
+        assert!(config.language("fr").is_none());
+
+        let localized = config.localized(&ko);
+        assert_eq!(localized.site.url, "https://example.com/ko");
+        assert_eq!(localized.build.output_dir, "dist/ko");
+        assert_eq!(localized.site.title, "한국어 블로그");
+        assert_eq!(localized.default_language().locale, "ko");
+        assert_eq!(
+            config.localized(&config.default_language()).site.url,
+            "https://example.com"
+        );
+
+        let yaml = "site:\n  language: en\n";
+        let config: SsgConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "site.language 'en' is not listed under site.languages"
+        );
     }
 }
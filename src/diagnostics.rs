@@ -0,0 +1,243 @@
+//! Build errors traced back to the file, line and column that caused them.
+//!
+//! Parsers of site files wrap their errors in a [`SourceError`]; watch mode
+//! turns whatever error a build ends with into an [`overlay`] that the dev
+//! server lays over every page until the next successful build.
+
+use crate::shortcodes::escape_html;
+use regex::Regex;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Lines of source shown before and after the failing one
+const CONTEXT_LINES: usize = 2;
+
+/// An error in a site file, with its position when the parser reported one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceError {
+    pub path: PathBuf,
+    /// 1-based
+    pub line: Option<usize>,
+    /// 1-based
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl SourceError {
+    pub fn yaml(path: &Path, error: &serde_yaml::Error) -> Self {
+        static POSITION_RE: OnceLock<Regex> = OnceLock::new();
+        let re = POSITION_RE.get_or_init(|| Regex::new(r" at line \d+ column \d+").unwrap());
+
+        // The position is kept separately, and may be shifted by `offset_lines`
+        let location = error.location();
+        let message = re.replace_all(&error.to_string(), "").to_string();
+
+        Self {
+            path: path.to_path_buf(),
+            line: location.as_ref().map(|l| l.line()),
+            column: location.as_ref().map(|l| l.column()),
+            message,
+        }
+    }
+
+    /// A template that failed to parse. Tera reports these as text only,
+    /// naming the file and giving the position as `--> line:column`.
+    pub fn template(error: &tera::Error) -> Option<Self> {
+        static PARSE_RE: OnceLock<Regex> = OnceLock::new();
+        let re = PARSE_RE.get_or_init(|| {
+            Regex::new(r#"Failed to parse "([^"]+)"\s*-->\s*(\d+):(\d+)"#).unwrap()
+        });
+
+        let mut text = error.to_string();
+        let mut source = std::error::Error::source(error);
+        while let Some(cause) = source {
+            text.push('\n');
+            text.push_str(&cause.to_string());
+            source = cause.source();
+        }
+
+        let captures = re.captures(&text)?;
+        let path = PathBuf::from(&captures[1]);
+        let root = std::env::current_dir().unwrap_or_default();
+        let message = text
+            .lines()
+            .find_map(|line| line.trim_start().strip_prefix("= "))
+            .unwrap_or("invalid template syntax");
+
+        Some(Self {
+            path: path.strip_prefix(&root).unwrap_or(&path).to_path_buf(),
+            line: captures[2].parse().ok(),
+            column: captures[3].parse().ok(),
+            message: message.to_string(),
+        })
+    }
+
+    /// Shift the position, for YAML that starts partway into a file
+    pub fn offset_lines(mut self, lines: usize) -> Self {
+        self.line = self.line.map(|line| line + lines);
+        self
+    }
+
+    /// The first source error in an error's chain
+    pub fn find(error: &anyhow::Error) -> Option<&Self> {
+        error.chain().find_map(|cause| cause.downcast_ref::<Self>())
+    }
+
+    /// `path:line:column`, as far as it is known
+    pub fn location(&self) -> String {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => format!("{}:{}:{}", self.path.display(), line, column),
+            (Some(line), None) => format!("{}:{}", self.path.display(), line),
+            _ => self.path.display().to_string(),
+        }
+    }
+
+    /// Numbered source lines around the error
+    fn snippet(&self) -> Vec<(usize, String)> {
+        let (Some(line), Ok(content)) = (self.line, fs::read_to_string(&self.path)) else {
+            return Vec::new();
+        };
+        let first = line.saturating_sub(CONTEXT_LINES).max(1);
+
+        content
+            .lines()
+            .enumerate()
+            .map(|(i, text)| (i + 1, text.to_string()))
+            .skip(first - 1)
+            .take(line + CONTEXT_LINES + 1 - first)
+            .collect()
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location(), self.message)
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+/// HTML laid over every page while the last build is failing: the location
+/// and surrounding source when known, then the whole error chain
+pub fn overlay(error: &anyhow::Error) -> String {
+    let mut html = String::from(
+        r#"<div id="ssg-error-overlay" style="position:fixed;inset:0;z-index:2147483647;overflow:auto;background:rgba(24,24,27,.94);color:#f4f4f5;font:14px/1.5 ui-monospace,SFMono-Regular,Menlo,monospace;padding:2rem">
+<h1 style="color:#f87171;font-size:1.25rem;margin:0 0 1rem">Build failed</h1>
+"#,
+    );
+
+    if let Some(source) = SourceError::find(error) {
+        html.push_str(&format!(
+            "<p style=\"margin:0 0 .5rem;color:#fbbf24\">{}</p>\n",
+            escape_html(&source.location())
+        ));
+
+        let snippet = source.snippet();
+        if !snippet.is_empty() {
+            html.push_str("<pre style=\"background:#27272a;padding:1rem;overflow:auto\">");
+            let width = snippet
+                .last()
+                .map_or(1, |(number, _)| number.to_string().len());
+            for (number, text) in &snippet {
+                let is_error_line = Some(*number) == source.line;
+                let line = format!("{:>width$} | {}", number, text, width = width);
+                if is_error_line {
+                    html.push_str(&format!(
+                        "<span style=\"color:#f87171\">{}</span>\n",
+                        escape_html(&line)
+                    ));
+                    if let Some(column) = source.column {
+                        let caret =
+                            format!("{:>width$} | {}^", "", " ".repeat(column.saturating_sub(1)));
+                        html.push_str(&format!(
+                            "<span style=\"color:#f87171\">{}</span>\n",
+                            escape_html(&caret)
+                        ));
+                    }
+                } else {
+                    html.push_str(&escape_html(&line));
+                    html.push('\n');
+                }
+            }
+            html.push_str("</pre>\n");
+        }
+    }
+
+    let chain: Vec<String> = error.chain().map(|cause| cause.to_string()).collect();
+    html.push_str(&format!(
+        "<pre style=\"white-space:pre-wrap\">{}</pre>\n",
+        escape_html(&chain.join("\n"))
+    ));
+    html.push_str(
+        "<p style=\"color:#a1a1aa\">The page behind this is from the last successful build. It reloads once the site builds again.</p>\n</div>\n",
+    );
+
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_yaml_error_location() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("config.yaml");
+        let content = "site:\n  title: \"Blog\"\n  url: [\n";
+        fs::write(&path, content).unwrap();
+
+        let error = serde_yaml::from_str::<serde_yaml::Value>(content).unwrap_err();
+        let source = SourceError::yaml(&path, &error);
+        assert_eq!((source.line, source.column), (Some(4), Some(1)));
+        assert!(!source.message.contains("at line"));
+
+        let error = anyhow::Error::new(source).context("Failed to load config");
+        let found = SourceError::find(&error).unwrap();
+        assert_eq!(found.path, path);
+
+        let html = overlay(&error);
+        assert!(html.contains(&format!("{}:4:1", path.display())));
+        assert!(html.contains("3 |   url: ["));
+        assert!(html.contains("Failed to load config"));
+    }
+
+    #[test]
+    fn test_template_error_location() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("post.html");
+        fs::write(&path, "<h1>\n  {{ title }\n</h1>\n").unwrap();
+
+        let error = tera::Tera::new(&format!("{}/*.html", dir.path().display())).unwrap_err();
+        let source = SourceError::template(&error).unwrap();
+        assert!(source.path.ends_with("post.html"));
+        assert_eq!(source.line, Some(2));
+        assert!(source.column.is_some());
+
+        let render_error =
+            tera::Tera::one_off("{{ missing }}", &tera::Context::new(), false).unwrap_err();
+        assert_eq!(SourceError::template(&render_error), None);
+    }
+
+    #[test]
+    fn test_overlay_escapes_and_shifts() {
+        let source = SourceError {
+            path: PathBuf::from("content/posts/dev/missing.md"),
+            line: Some(2),
+            column: Some(7),
+            message: "invalid type: <map>".to_string(),
+        }
+        .offset_lines(1);
+        assert_eq!(source.location(), "content/posts/dev/missing.md:3:7");
+
+        let error = Err::<(), _>(source)
+            .context("1 posts failed to build")
+            .unwrap_err();
+        let html = overlay(&error);
+        assert!(html.contains("invalid type: &lt;map&gt;"));
+        assert!(!html.contains("<map>"));
+    }
+}
@@ -0,0 +1,287 @@
+use crate::config::ExcerptConfig;
+use crate::html::{decode_entities, parse as parse_html, Element, Node};
+use crate::search::tokenizer::is_cjk;
+use serde::{Deserialize, Serialize};
+
+/// Elements whose content is never part of an excerpt
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "template"];
+
+/// Elements whose text never runs into the text around them
+const BLOCK_ELEMENTS: &[&str] = &[
+    "blockquote",
+    "br",
+    "dd",
+    "div",
+    "dt",
+    "figcaption",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "li",
+    "p",
+    "pre",
+    "td",
+    "th",
+    "tr",
+];
+
+/// Leading part of a rendered post
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Excerpt {
+    /// HTML with every element that was cut off closed again
+    pub html: String,
+    /// Plain text of `html` with whitespace collapsed, for feeds and search
+    pub text: String,
+}
+
+impl Excerpt {
+    /// Everything before the separator comment, or the first `length`
+    /// characters of text when the post has no separator.
+    ///
+    /// Returns `None` when the excerpt would have no text.
+    pub fn from_html(html: &str, config: &ExcerptConfig) -> Option<Self> {
+        let nodes = parse_html(html);
+        let separator = normalize_comment(&config.separator);
+
+        let mut budget = if contains_separator(&nodes, &separator) {
+            None
+        } else if config.length > 0 {
+            Some(config.length)
+        } else {
+            return None;
+        };
+
+        let mut cut = Cut {
+            separator: &separator,
+            budget: &mut budget,
+            html: String::new(),
+            text: String::new(),
+        };
+        cut.nodes(&nodes);
+
+        let text = cut.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            html: cut.html.trim().to_string(),
+            text,
+        })
+    }
+}
+
+/// Plain text of a whole rendered post with whitespace collapsed, as excerpts
+/// read it; the search index is built from it
+pub fn plain_text(html: &str) -> String {
+    fn collect(nodes: &[Node], text: &mut String) {
+        for node in nodes {
+            match node {
+                Node::Text(raw) if raw.starts_with("<!") || raw.starts_with("<?") => {}
+                Node::Text(raw) => text.push_str(&decode_entities(raw)),
+                Node::Element(element) if is_skipped(element) => {}
+                Node::Element(element) => {
+                    collect(&element.children, text);
+                    if BLOCK_ELEMENTS.contains(&element.name.as_str()) {
+                        text.push('\n');
+                    }
+                }
+            }
+        }
+    }
+
+    let mut text = String::new();
+    collect(&parse_html(html), &mut text);
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Scripts, styles and decoration such as heading anchors, marked aria-hidden
+fn is_skipped(element: &Element) -> bool {
+    SKIPPED_ELEMENTS.contains(&element.name.as_str())
+        || element
+            .attributes
+            .iter()
+            .any(|(name, value)| name == "aria-hidden" && value == "true")
+}
+
+/// Serializes nodes until the separator or the character budget is reached
+struct Cut<'a> {
+    separator: &'a str,
+    /// Characters of text left; `None` when cutting at the separator
+    budget: &'a mut Option<usize>,
+    html: String,
+    text: String,
+}
+
+impl Cut<'_> {
+    /// Returns true once the excerpt is complete
+    fn nodes(&mut self, nodes: &[Node]) -> bool {
+        nodes.iter().any(|node| self.node(node))
+    }
+
+    fn node(&mut self, node: &Node) -> bool {
+        match node {
+            Node::Text(raw) if raw.starts_with("<!") || raw.starts_with("<?") => {
+                normalize_comment(raw) == self.separator
+            }
+            Node::Text(raw) => self.text_node(raw),
+            Node::Element(element) => {
+                if is_skipped(element) {
+                    return false;
+                }
+
+                self.html.push_str(element.start_tag());
+                let done = self.nodes(&element.children);
+                match element.end_tag() {
+                    Some(end) => self.html.push_str(end),
+                    None if !element.children.is_empty() => {
+                        self.html.push_str(&format!("</{}>", element.name));
+                    }
+                    None => {}
+                }
+                done
+            }
+        }
+    }
+
+    fn text_node(&mut self, raw: &str) -> bool {
+        let decoded = decode_entities(raw);
+        let length = decoded.chars().count();
+
+        let Some(remaining) = self.budget.as_mut() else {
+            self.html.push_str(raw);
+            self.text.push_str(&decoded);
+            return false;
+        };
+
+        if length <= *remaining {
+            *remaining -= length;
+            self.html.push_str(raw);
+            self.text.push_str(&decoded);
+            return false;
+        }
+
+        let kept = truncate_at_word(&decoded, *remaining);
+        *remaining = 0;
+        self.html.push_str(&escape_text(kept));
+        self.html.push('…');
+        self.text.push_str(kept);
+        self.text.push('…');
+        true
+    }
+}
+
+/// First `max` characters of `text`, backing off to the previous space
+/// rather than splitting a word. CJK text is cut at any character.
+fn truncate_at_word(text: &str, max: usize) -> &str {
+    let end = text.char_indices().nth(max).map_or(text.len(), |(i, _)| i);
+    let (head, tail) = text.split_at(end);
+
+    let splits_word = match (head.chars().next_back(), tail.chars().next()) {
+        (Some(before), Some(after)) => {
+            !before.is_whitespace() && !after.is_whitespace() && !is_cjk(before) && !is_cjk(after)
+        }
+        _ => false,
+    };
+
+    let head = match head.rfind(char::is_whitespace) {
+        Some(space) if splits_word => &head[..space],
+        _ => head,
+    };
+    head.trim_end()
+}
+
+fn contains_separator(nodes: &[Node], separator: &str) -> bool {
+    nodes.iter().any(|node| match node {
+        Node::Text(raw) => raw.starts_with("<!--") && normalize_comment(raw) == separator,
+        Node::Element(element) => contains_separator(&element.children, separator),
+    })
+}
+
+/// `<!--more-->` and `<!-- MORE -->` both match `<!-- more -->`
+fn normalize_comment(comment: &str) -> String {
+    comment
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(length: usize) -> ExcerptConfig {
+        ExcerptConfig {
+            separator: "<!-- more -->".to_string(),
+            length,
+        }
+    }
+
+    #[test]
+    fn test_separator() {
+        let html = "<p>Intro <em>text</em></p>\n<!--more-->\n<p>Rest</p>";
+        let excerpt = Excerpt::from_html(html, &config(3)).unwrap();
+
+        assert_eq!(excerpt.html, "<p>Intro <em>text</em></p>");
+        assert_eq!(excerpt.text, "Intro text");
+    }
+
+    #[test]
+    fn test_separator_inside_paragraph_closes_tags() {
+        let html = "<ul><li><p>One <!-- more --> two</p></li></ul>";
+        let excerpt = Excerpt::from_html(html, &config(200)).unwrap();
+
+        assert_eq!(excerpt.html, "<ul><li><p>One </p></li></ul>");
+    }
+
+    #[test]
+    fn test_length_balances_tags_and_keeps_words() {
+        let html = "<p>Hello <strong>brave new</strong> world</p><p>Second</p>";
+        let excerpt = Excerpt::from_html(html, &config(12)).unwrap();
+
+        assert_eq!(excerpt.html, "<p>Hello <strong>brave…</strong></p>");
+        assert_eq!(excerpt.text, "Hello brave…");
+    }
+
+    #[test]
+    fn test_length_counts_cjk_and_entities() {
+        let html = "<p>&lt;가나&gt; 다라마바사</p>";
+        let excerpt = Excerpt::from_html(html, &config(6)).unwrap();
+
+        assert_eq!(excerpt.html, "<p>&lt;가나&gt; 다…</p>");
+        assert_eq!(excerpt.text, "<가나> 다…");
+    }
+
+    #[test]
+    fn test_short_post_and_disabled() {
+        let html =
+            "<h2 id=\"a\">Short <a href=\"#a\" aria-hidden=\"true\">#</a></h2><script>x()</script>";
+
+        assert_eq!(
+            Excerpt::from_html(html, &config(200)).unwrap().html,
+            "<h2 id=\"a\">Short </h2>"
+        );
+        assert!(Excerpt::from_html(html, &config(0)).is_none());
+        assert!(Excerpt::from_html("<hr>", &config(200)).is_none());
+    }
+
+    #[test]
+    fn test_plain_text() {
+        let html = "<h2 id=\"a\">Title <a href=\"#a\" aria-hidden=\"true\">#</a></h2><p>One \
+                    &amp; <strong>two</strong></p><!-- more --><pre><code>x()</code></pre>\
+                    <script>hidden()</script>";
+
+        assert_eq!(plain_text(html), "Title One & two x()");
+    }
+}
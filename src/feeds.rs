@@ -1,256 +1,456 @@
-use crate::config::SsgConfig;
-use crate::metadata::MetadataCache;
+use crate::config::{FeedFormat, SsgConfig};
+use crate::metadata::{MetadataCache, PostMetadata};
 use crate::parser::Parser;
 use crate::renderer::Renderer;
 use crate::slug;
+use crate::types::PostDate;
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
 use std::fs;
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
+/// Format-independent feed data, rendered into every configured format
+pub struct Feed {
+    pub title: String,
+    pub description: String,
+    /// Page the feed belongs to (site root or category page)
+    pub link: String,
+    /// Base URL the feed files are written under, without trailing slash
+    pub feed_base_url: String,
+    /// BCP 47 tag of the site language, e.g. `ko-KR`
+    pub language: String,
+    pub author: String,
+    pub items: Vec<FeedItem>,
+}
+
+pub struct FeedItem {
+    pub title: String,
+    pub url: String,
+    pub author: String,
+    pub date: PostDate,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub description: String,
+    pub content_html: String,
+}
+
+/// Feed reference for `<link rel="alternate">` autodiscovery in templates
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedLink {
+    pub title: String,
+    pub url: String,
+    pub mime_type: &'static str,
+}
+
+impl Feed {
+    /// Most recent publish or modification date of any item
+    fn updated(&self) -> DateTime<Utc> {
+        self.items
+            .iter()
+            .map(|item| item.date.modified.unwrap_or(item.date.posted))
+            .max()
+            .unwrap_or_else(Utc::now)
+    }
+}
+
+impl FeedFormat {
+    pub fn filename(&self) -> &'static str {
+        match self {
+            FeedFormat::Rss => "feed.xml",
+            FeedFormat::Atom => "atom.xml",
+            FeedFormat::Json => "feed.json",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            FeedFormat::Rss => "application/rss+xml",
+            FeedFormat::Atom => "application/atom+xml",
+            FeedFormat::Json => "application/feed+json",
+        }
+    }
+
+    pub fn render(&self, feed: &Feed) -> Result<String> {
+        match self {
+            FeedFormat::Rss => Ok(FeedGenerator::render_rss(feed)),
+            FeedFormat::Atom => Ok(FeedGenerator::render_atom(feed)),
+            FeedFormat::Json => FeedGenerator::render_json(feed),
+        }
+    }
+}
+
 pub struct FeedGenerator;
 
 impl FeedGenerator {
+    /// Every feed of the site. Returns the paths of the files written.
     pub fn generate_all_feeds(
         config: &SsgConfig,
         metadata: &MetadataCache,
         content_dir: &Path,
         output_dir: &Path,
-    ) -> Result<()> {
-        Self::generate_global_feed(config, metadata, content_dir, output_dir)?;
-        Self::generate_category_feeds(config, metadata, content_dir, output_dir)?;
-        Ok(())
+    ) -> Result<Vec<PathBuf>> {
+        let mut written = Self::generate_global_feed(config, metadata, content_dir, output_dir)?;
+        written.extend(Self::generate_category_feeds(
+            config,
+            metadata,
+            content_dir,
+            output_dir,
+        )?);
+        written.extend(Self::generate_tag_feeds(
+            config,
+            metadata,
+            content_dir,
+            output_dir,
+        )?);
+        Ok(written)
     }
 
-    fn generate_global_feed(
+    /// The feeds a post appears in: the site feed and those of its category and tags
+    pub fn generate_post_feeds(
         config: &SsgConfig,
         metadata: &MetadataCache,
         content_dir: &Path,
         output_dir: &Path,
+        post: &PostMetadata,
     ) -> Result<()> {
-        let recent_posts = metadata.get_recent_posts(10);
-
-        if recent_posts.is_empty() {
-            return Ok(());
+        Self::generate_global_feed(config, metadata, content_dir, output_dir)?;
+        Self::generate_category_feed(config, metadata, content_dir, output_dir, &post.category)?;
+        for tag in &post.frontmatter.tags {
+            Self::generate_tag_feed(config, metadata, content_dir, output_dir, tag)?;
         }
+        Ok(())
+    }
 
-        let renderer = Renderer::new();
-        let last_build_date = chrono::Utc::now().to_rfc2822();
+    /// Feeds covering the whole site
+    pub fn site_feed_links(config: &SsgConfig) -> Vec<FeedLink> {
+        Self::feed_links(config, &config.site.title, &config.site.url)
+    }
 
-        let mut items = Vec::new();
+    /// Site feeds followed by the feeds of a single category
+    pub fn category_feed_links(config: &SsgConfig, slug: &str, name: &str) -> Vec<FeedLink> {
+        let mut links = Self::site_feed_links(config);
+        links.extend(Self::feed_links(
+            config,
+            &format!("{} - {}", config.site.title, name),
+            &Self::category_feed_base_url(config, slug),
+        ));
+        links
+    }
 
-        for post_meta in recent_posts {
-            if post_meta.frontmatter.draft {
-                continue;
-            }
+    /// Site feeds followed by the feeds of a single tag
+    pub fn tag_feed_links(config: &SsgConfig, tag: &str) -> Vec<FeedLink> {
+        let mut links = Self::site_feed_links(config);
+        links.extend(Self::feed_links(
+            config,
+            &format!("{} - #{}", config.site.title, tag),
+            &Self::tag_feed_base_url(config, tag),
+        ));
+        links
+    }
 
-            let post_path = Self::find_post_file(content_dir, &post_meta.slug)?;
-            let post = Parser::parse_file(&post_path)
-                .with_context(|| format!("Failed to parse post: {}", post_meta.slug))?;
+    fn feed_links(config: &SsgConfig, title: &str, base_url: &str) -> Vec<FeedLink> {
+        config
+            .build
+            .feeds
+            .formats
+            .iter()
+            .map(|format| FeedLink {
+                title: title.to_string(),
+                url: format!("{}/{}", base_url, format.filename()),
+                mime_type: format.mime_type(),
+            })
+            .collect()
+    }
 
-            let rendered_content = renderer.render_markdown(&post.content);
-            let url = format!("{}/{}/{}", config.site.url, post.category, post.slug);
+    fn category_feed_base_url(config: &SsgConfig, category: &str) -> String {
+        format!("{}/{}", config.site.url, slug::encode_for_url(category))
+    }
 
-            let category_name = metadata
-                .get_category_info()
-                .iter()
-                .find(|c| c.slug == post.category)
-                .map(|c| c.name.clone())
-                .unwrap_or_else(|| post.category.clone());
+    fn tag_feed_base_url(config: &SsgConfig, tag: &str) -> String {
+        format!("{}/tags/{}", config.site.url, slug::encode_for_url(tag))
+    }
 
-            let tags_xml = if !post.frontmatter.tags.is_empty() {
-                post.frontmatter
-                    .tags
-                    .iter()
-                    .map(|tag| format!("        <category><![CDATA[{}]]></category>", tag))
-                    .collect::<Vec<_>>()
-                    .join("\n")
-            } else {
-                String::new()
-            };
+    fn generate_global_feed(
+        config: &SsgConfig,
+        metadata: &MetadataCache,
+        content_dir: &Path,
+        output_dir: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        let recent_posts: Vec<_> = metadata
+            .get_recent_posts(usize::MAX)
+            .into_iter()
+            .take(config.build.feeds.limit)
+            .collect();
 
-            let description = post
-                .frontmatter
-                .description
-                .as_deref()
-                .unwrap_or(&post.frontmatter.title);
+        if recent_posts.is_empty() {
+            return Ok(Vec::new());
+        }
 
-            let pub_date = post.frontmatter.date.to_rfc2822();
+        let feed = Feed {
+            title: config.site.title.clone(),
+            description: config.site.description.clone(),
+            link: config.site.url.clone(),
+            feed_base_url: config.site.url.clone(),
+            language: config.default_language().locale,
+            author: config.site.author.clone(),
+            items: Self::build_items(config, metadata, content_dir, &recent_posts)?,
+        };
+
+        Self::write_feed(config, &feed, output_dir)
+    }
 
-            let item = format!(
-                r#"    <item>
-        <title>{}</title>
-        <link>{}</link>
-        <dc:creator><![CDATA[{}]]></dc:creator>
-        <pubDate>{}</pubDate>
-        <category><![CDATA[{}]]></category>{}{}
-        <guid isPermaLink="false">{}</guid>
-        <description><![CDATA[{}]]></description>
-        <content:encoded><![CDATA[{}]]></content:encoded>
-    </item>"#,
-                Self::escape_xml(&post.frontmatter.title),
-                url,
-                config.site.author,
-                pub_date,
-                category_name,
-                if tags_xml.is_empty() { "" } else { "\n" },
-                tags_xml,
-                url,
-                Self::escape_xml(description),
-                rendered_content
-            );
-
-            items.push(item);
+    fn generate_category_feeds(
+        config: &SsgConfig,
+        metadata: &MetadataCache,
+        content_dir: &Path,
+        output_dir: &Path,
+    ) -> Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        for category_slug in metadata.get_categories() {
+            written.extend(Self::generate_category_feed(
+                config,
+                metadata,
+                content_dir,
+                output_dir,
+                &category_slug,
+            )?);
         }
 
-        let feed_url = format!("{}/feed.xml", config.site.url);
+        Ok(written)
+    }
 
-        let rss_xml = format!(
-            r#"<?xml version="1.0" encoding="UTF-8"?>
-<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/" xmlns:wfw="http://wellformedweb.org/CommentAPI/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:sy="http://purl.org/rss/1.0/modules/syndication/" xmlns:slash="http://purl.org/rss/1.0/modules/slash/"
->
-<channel>
-    <title>{}</title>
-    <description>{}</description>
-    <language>ko-KR</language>
-    <atom:link href="{}" rel="self" type="application/rss+xml" />
-    <link>{}</link>
-    <lastBuildDate>{}</lastBuildDate>
-    <sy:updatePeriod>hourly</sy:updatePeriod>
-    <sy:updateFrequency>1</sy:updateFrequency>
-{}
-</channel>
-</rss>
-"#,
-            Self::escape_xml(&config.site.title),
-            Self::escape_xml(&config.site.description),
-            feed_url,
-            config.site.url,
-            last_build_date,
-            items.join("\n")
-        );
+    fn generate_category_feed(
+        config: &SsgConfig,
+        metadata: &MetadataCache,
+        content_dir: &Path,
+        output_dir: &Path,
+        category_slug: &str,
+    ) -> Result<Vec<PathBuf>> {
+        let mut category_posts: Vec<_> = metadata
+            .get_posts_by_category(category_slug)
+            .into_iter()
+            .collect();
 
-        fs::create_dir_all(output_dir)?;
-        let output_path = output_dir.join("feed.xml");
-        fs::write(&output_path, rss_xml)?;
+        category_posts.sort_by(|a, b| b.frontmatter.date.cmp(&a.frontmatter.date));
+        let category_posts: Vec<_> = category_posts
+            .into_iter()
+            .take(config.build.feeds.limit)
+            .collect();
 
-        Ok(())
+        if category_posts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let category_info = metadata
+            .get_category_info()
+            .iter()
+            .find(|c| c.slug == category_slug)
+            .cloned();
+
+        let category_name = category_info
+            .as_ref()
+            .map(|c| c.name.clone())
+            .unwrap_or_else(|| category_slug.to_string());
+
+        let feed_description = category_info
+            .as_ref()
+            .and_then(|c| {
+                if c.description.is_empty() {
+                    None
+                } else {
+                    Some(c.description.clone())
+                }
+            })
+            .unwrap_or_else(|| format!("{} posts from {}", category_name, config.site.title));
+
+        let feed = Feed {
+            title: format!("{} - {}", config.site.title, category_name),
+            description: feed_description,
+            link: format!(
+                "{}/{}/",
+                config.site.url,
+                slug::encode_for_url(category_slug)
+            ),
+            feed_base_url: Self::category_feed_base_url(config, category_slug),
+            language: config.default_language().locale,
+            author: config.site.author.clone(),
+            items: Self::build_items(config, metadata, content_dir, &category_posts)?,
+        };
+
+        let category_dir = if config.build.encode_filenames {
+            slug::encode_for_url(category_slug)
+        } else {
+            category_slug.to_string()
+        };
+
+        Self::write_feed(config, &feed, &output_dir.join(category_dir))
     }
 
-    fn generate_category_feeds(
+    fn generate_tag_feeds(
         config: &SsgConfig,
         metadata: &MetadataCache,
         content_dir: &Path,
         output_dir: &Path,
-    ) -> Result<()> {
-        let categories = metadata.get_categories();
+    ) -> Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        for tag in metadata.get_tags() {
+            written.extend(Self::generate_tag_feed(
+                config,
+                metadata,
+                content_dir,
+                output_dir,
+                &tag,
+            )?);
+        }
 
-        for category_slug in categories {
-            let mut category_posts: Vec<_> = metadata
-                .get_posts_by_category(&category_slug)
-                .into_iter()
-                .filter(|p| !p.frontmatter.draft)
-                .collect();
+        Ok(written)
+    }
 
-            category_posts.sort_by(|a, b| b.frontmatter.date.cmp(&a.frontmatter.date));
-            let category_posts: Vec<_> = category_posts.into_iter().take(10).collect();
+    fn generate_tag_feed(
+        config: &SsgConfig,
+        metadata: &MetadataCache,
+        content_dir: &Path,
+        output_dir: &Path,
+        tag: &str,
+    ) -> Result<Vec<PathBuf>> {
+        let mut tag_posts: Vec<_> = metadata.get_posts_by_tag(tag).into_iter().collect();
 
-            if category_posts.is_empty() {
-                continue;
-            }
+        tag_posts.sort_by(|a, b| b.frontmatter.date.cmp(&a.frontmatter.date));
+        let tag_posts: Vec<_> = tag_posts
+            .into_iter()
+            .take(config.build.feeds.limit)
+            .collect();
+
+        if tag_posts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let feed = Feed {
+            title: format!("{} - #{}", config.site.title, tag),
+            description: format!("Posts tagged with \"{}\" from {}", tag, config.site.title),
+            link: format!("{}/tag/{}/", config.site.url, slug::encode_for_url(tag)),
+            feed_base_url: Self::tag_feed_base_url(config, tag),
+            language: config.default_language().locale,
+            author: config.site.author.clone(),
+            items: Self::build_items(config, metadata, content_dir, &tag_posts)?,
+        };
+
+        let tag_dir = if config.build.encode_filenames {
+            slug::encode_for_url(tag)
+        } else {
+            tag.to_string()
+        };
+
+        Self::write_feed(config, &feed, &output_dir.join("tags").join(tag_dir))
+    }
+
+    fn build_items(
+        config: &SsgConfig,
+        metadata: &MetadataCache,
+        content_dir: &Path,
+        posts: &[&PostMetadata],
+    ) -> Result<Vec<FeedItem>> {
+        let renderer = Renderer::new();
+        let mut items = Vec::new();
+
+        for post_meta in posts {
+            let post_path =
+                Self::find_post_file(content_dir, &post_meta.category, &post_meta.slug)?;
+            let post = Parser::parse_file(&post_path, content_dir)
+                .with_context(|| format!("Failed to parse post: {}", post_meta.slug))?;
 
-            let category_info = metadata
+            let category_name = metadata
                 .get_category_info()
                 .iter()
-                .find(|c| c.slug == category_slug)
-                .cloned();
-
-            let category_name = category_info
-                .as_ref()
+                .find(|c| c.slug == post.category)
                 .map(|c| c.name.clone())
-                .unwrap_or_else(|| category_slug.clone());
-
-            let renderer = Renderer::new();
-            let last_build_date = chrono::Utc::now().to_rfc2822();
+                .unwrap_or_else(|| post.category.clone());
 
-            let mut items = Vec::new();
+            let description = post
+                .frontmatter
+                .description
+                .clone()
+                .or_else(|| post_meta.excerpt.as_ref().map(|e| e.text.clone()))
+                .unwrap_or_else(|| post.frontmatter.title.clone());
+
+            items.push(FeedItem {
+                url: format!("{}/{}/{}", config.site.url, post.category, post.slug),
+                title: post.frontmatter.title.clone(),
+                author: config.site.author.clone(),
+                date: post.frontmatter.date.clone(),
+                category: category_name,
+                tags: post.frontmatter.tags.clone(),
+                description,
+                content_html: renderer.render_markdown(&post.content),
+            });
+        }
 
-            for post_meta in category_posts {
-                let post_path = Self::find_post_file(content_dir, &post_meta.slug)?;
-                let post = Parser::parse_file(&post_path)
-                    .with_context(|| format!("Failed to parse post: {}", post_meta.slug))?;
+        Ok(items)
+    }
 
-                let rendered_content = renderer.render_markdown(&post.content);
-                let url = format!("{}/{}/{}", config.site.url, post.category, post.slug);
+    /// Write the feed in every configured format; returns the paths written
+    fn write_feed(config: &SsgConfig, feed: &Feed, dir: &Path) -> Result<Vec<PathBuf>> {
+        fs::create_dir_all(dir)?;
 
-                let tags_xml = if !post.frontmatter.tags.is_empty() {
-                    post.frontmatter
-                        .tags
-                        .iter()
-                        .map(|tag| format!("        <category><![CDATA[{}]]></category>", tag))
-                        .collect::<Vec<_>>()
-                        .join("\n")
-                } else {
-                    String::new()
-                };
+        let mut written = Vec::new();
+        for format in &config.build.feeds.formats {
+            let output = format.render(feed)?;
+            let path = dir.join(format.filename());
+            fs::write(&path, output)?;
+            written.push(path);
+        }
 
-                let description = post
-                    .frontmatter
-                    .description
-                    .as_deref()
-                    .unwrap_or(&post.frontmatter.title);
+        Ok(written)
+    }
 
-                let pub_date = post.frontmatter.date.to_rfc2822();
+    fn render_rss(feed: &Feed) -> String {
+        let items: Vec<String> = feed
+            .items
+            .iter()
+            .map(|item| {
+                let tags_xml = item
+                    .tags
+                    .iter()
+                    .map(|tag| format!("        <category>{}</category>", Self::cdata(tag)))
+                    .collect::<Vec<_>>()
+                    .join("\n");
 
-                let item = format!(
+                format!(
                     r#"    <item>
         <title>{}</title>
         <link>{}</link>
-        <dc:creator><![CDATA[{}]]></dc:creator>
+        <dc:creator>{}</dc:creator>
         <pubDate>{}</pubDate>
-        <category><![CDATA[{}]]></category>{}{}
+        <category>{}</category>{}{}
         <guid isPermaLink="false">{}</guid>
-        <description><![CDATA[{}]]></description>
-        <content:encoded><![CDATA[{}]]></content:encoded>
+        <description>{}</description>
+        <content:encoded>{}</content:encoded>
     </item>"#,
-                    Self::escape_xml(&post.frontmatter.title),
-                    url,
-                    config.site.author,
-                    pub_date,
-                    category_name,
+                    Self::escape_xml(&item.title),
+                    item.url,
+                    Self::cdata(&item.author),
+                    item.date.to_rfc2822(),
+                    Self::cdata(&item.category),
                     if tags_xml.is_empty() { "" } else { "\n" },
                     tags_xml,
-                    url,
-                    Self::escape_xml(description),
-                    rendered_content
-                );
-
-                items.push(item);
-            }
-
-            let feed_url = format!("{}/{}/feed.xml", config.site.url, category_slug);
-            let category_url = format!("{}/{}/", config.site.url, category_slug);
-            let feed_title = format!("{} - {}", config.site.title, category_name);
-            let feed_description = category_info
-                .as_ref()
-                .and_then(|c| {
-                    if c.description.is_empty() {
-                        None
-                    } else {
-                        Some(c.description.clone())
-                    }
-                })
-                .unwrap_or_else(|| format!("{} posts from {}", category_name, config.site.title));
-
-            let rss_xml = format!(
-                r#"<?xml version="1.0" encoding="UTF-8"?>
+                    item.url,
+                    Self::cdata(&Self::escape_xml(&item.description)),
+                    Self::cdata(&item.content_html)
+                )
+            })
+            .collect();
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
 <rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/" xmlns:wfw="http://wellformedweb.org/CommentAPI/" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:sy="http://purl.org/rss/1.0/modules/syndication/" xmlns:slash="http://purl.org/rss/1.0/modules/slash/"
 >
 <channel>
     <title>{}</title>
     <description>{}</description>
-    <language>ko-KR</language>
-    <atom:link href="{}" rel="self" type="application/rss+xml" />
+    <language>{}</language>
+    <atom:link href="{}/{}" rel="self" type="{}" />
     <link>{}</link>
     <lastBuildDate>{}</lastBuildDate>
     <sy:updatePeriod>hourly</sy:updatePeriod>
@@ -259,29 +459,154 @@ impl FeedGenerator {
 </channel>
 </rss>
 "#,
-                Self::escape_xml(&feed_title),
-                Self::escape_xml(&feed_description),
-                feed_url,
-                category_url,
-                last_build_date,
-                items.join("\n")
-            );
-
-            let category_dir = output_dir.join(&category_slug);
-            fs::create_dir_all(&category_dir)?;
-            let output_path = category_dir.join("feed.xml");
-            fs::write(&output_path, rss_xml)?;
+            Self::escape_xml(&feed.title),
+            Self::escape_xml(&feed.description),
+            feed.language,
+            feed.feed_base_url,
+            FeedFormat::Rss.filename(),
+            FeedFormat::Rss.mime_type(),
+            feed.link,
+            feed.updated().to_rfc2822(),
+            items.join("\n")
+        )
+    }
+
+    fn render_atom(feed: &Feed) -> String {
+        let entries: Vec<String> = feed
+            .items
+            .iter()
+            .map(|item| {
+                let categories = std::iter::once(&item.category)
+                    .chain(item.tags.iter())
+                    .map(|term| {
+                        format!(r#"        <category term="{}" />"#, Self::escape_xml(term))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                format!(
+                    r#"    <entry>
+        <title>{}</title>
+        <link href="{}" rel="alternate" type="text/html" />
+        <id>{}</id>
+        <published>{}</published>
+        <updated>{}</updated>
+        <author>
+            <name>{}</name>
+        </author>
+{}
+        <summary type="html">{}</summary>
+        <content type="html">{}</content>
+    </entry>"#,
+                    Self::escape_xml(&item.title),
+                    item.url,
+                    item.url,
+                    item.date.posted.to_rfc3339(),
+                    item.date.modified.unwrap_or(item.date.posted).to_rfc3339(),
+                    Self::escape_xml(&item.author),
+                    categories,
+                    Self::cdata(&Self::escape_xml(&item.description)),
+                    Self::cdata(&item.content_html)
+                )
+            })
+            .collect();
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="{}">
+    <title>{}</title>
+    <subtitle>{}</subtitle>
+    <link href="{}/{}" rel="self" type="{}" />
+    <link href="{}" rel="alternate" type="text/html" />
+    <id>{}</id>
+    <updated>{}</updated>
+    <author>
+        <name>{}</name>
+    </author>
+{}
+</feed>
+"#,
+            feed.language,
+            Self::escape_xml(&feed.title),
+            Self::escape_xml(&feed.description),
+            feed.feed_base_url,
+            FeedFormat::Atom.filename(),
+            FeedFormat::Atom.mime_type(),
+            feed.link,
+            feed.link,
+            feed.updated().to_rfc3339(),
+            Self::escape_xml(&feed.author),
+            entries.join("\n")
+        )
+    }
+
+    fn render_json(feed: &Feed) -> Result<String> {
+        #[derive(Serialize)]
+        struct JsonAuthor<'a> {
+            name: &'a str,
         }
 
-        Ok(())
+        #[derive(Serialize)]
+        struct JsonItem<'a> {
+            id: &'a str,
+            url: &'a str,
+            title: &'a str,
+            content_html: &'a str,
+            summary: &'a str,
+            date_published: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            date_modified: Option<String>,
+            authors: Vec<JsonAuthor<'a>>,
+            tags: &'a [String],
+        }
+
+        #[derive(Serialize)]
+        struct JsonFeed<'a> {
+            version: &'static str,
+            title: &'a str,
+            description: &'a str,
+            home_page_url: &'a str,
+            feed_url: String,
+            language: &'a str,
+            authors: Vec<JsonAuthor<'a>>,
+            items: Vec<JsonItem<'a>>,
+        }
+
+        let json_feed = JsonFeed {
+            version: "https://jsonfeed.org/version/1.1",
+            title: &feed.title,
+            description: &feed.description,
+            home_page_url: &feed.link,
+            feed_url: format!("{}/{}", feed.feed_base_url, FeedFormat::Json.filename()),
+            language: &feed.language,
+            authors: vec![JsonAuthor { name: &feed.author }],
+            items: feed
+                .items
+                .iter()
+                .map(|item| JsonItem {
+                    id: &item.url,
+                    url: &item.url,
+                    title: &item.title,
+                    content_html: &item.content_html,
+                    summary: &item.description,
+                    date_published: item.date.posted.to_rfc3339(),
+                    date_modified: item.date.modified.map(|d| d.to_rfc3339()),
+                    authors: vec![JsonAuthor { name: &item.author }],
+                    tags: &item.tags,
+                })
+                .collect(),
+        };
+
+        Ok(serde_json::to_string_pretty(&json_feed)?)
     }
 
-    fn find_post_file(content_dir: &Path, slug: &str) -> Result<PathBuf> {
+    /// The source of a post; other categories may have a post with the same slug
+    fn find_post_file(content_dir: &Path, category: &str, slug: &str) -> Result<PathBuf> {
         // Decode the slug back to original filename for searching
         let decoded = slug::decode_from_url(slug);
         let filename = format!("{}.md", decoded);
 
-        for entry in WalkDir::new(content_dir)
+        for entry in WalkDir::new(content_dir.join(category))
             .follow_links(true)
             .into_iter()
             .filter_map(|e| e.ok())
@@ -294,7 +619,12 @@ impl FeedGenerator {
         anyhow::bail!("Post file not found: {} (decoded: {})", slug, decoded)
     }
 
-    fn escape_xml(s: &str) -> String {
+    /// Wrap text in a CDATA section, splitting any embedded terminator
+    fn cdata(s: &str) -> String {
+        format!("<![CDATA[{}]]>", s.replace("]]>", "]]]]><![CDATA[>"))
+    }
+
+    pub(crate) fn escape_xml(s: &str) -> String {
         s.replace('&', "&amp;")
             .replace('<', "&lt;")
             .replace('>', "&gt;")
@@ -306,6 +636,31 @@ impl FeedGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
+
+    fn create_test_feed() -> Feed {
+        Feed {
+            title: "Blog & Co".to_string(),
+            description: "Posts".to_string(),
+            link: "https://example.com".to_string(),
+            feed_base_url: "https://example.com/dev".to_string(),
+            language: "ko-KR".to_string(),
+            author: "Author".to_string(),
+            items: vec![FeedItem {
+                title: "Hello <World>".to_string(),
+                url: "https://example.com/dev/hello".to_string(),
+                author: "Author".to_string(),
+                date: PostDate {
+                    posted: Utc.with_ymd_and_hms(2025, 11, 11, 10, 0, 0).unwrap(),
+                    modified: Some(Utc.with_ymd_and_hms(2025, 11, 12, 10, 0, 0).unwrap()),
+                },
+                category: "Dev".to_string(),
+                tags: vec!["rust".to_string()],
+                description: "Desc".to_string(),
+                content_html: "<p>Body ]]> end</p>".to_string(),
+            }],
+        }
+    }
 
     #[test]
     fn test_escape_xml() {
@@ -313,4 +668,56 @@ mod tests {
         let expected = r#"Hello &amp; &lt;world&gt; &quot;test&quot;"#;
         assert_eq!(FeedGenerator::escape_xml(input), expected);
     }
+
+    #[test]
+    fn test_cdata_splits_terminator() {
+        assert_eq!(
+            FeedGenerator::cdata("a]]>b"),
+            "<![CDATA[a]]]]><![CDATA[>b]]>"
+        );
+    }
+
+    #[test]
+    fn test_render_rss() {
+        let rss = FeedFormat::Rss.render(&create_test_feed()).unwrap();
+        assert!(rss.contains("<rss version=\"2.0\""));
+        assert!(rss.contains("<title>Hello &lt;World&gt;</title>"));
+        assert!(rss.contains(r#"<atom:link href="https://example.com/dev/feed.xml""#));
+        assert!(rss.contains("<category><![CDATA[rust]]></category>"));
+    }
+
+    #[test]
+    fn test_render_atom() {
+        let atom = FeedFormat::Atom.render(&create_test_feed()).unwrap();
+        assert!(atom.contains(r#"<feed xmlns="http://www.w3.org/2005/Atom" xml:lang="ko-KR">"#));
+        assert!(atom.contains(r#"<link href="https://example.com/dev/atom.xml" rel="self""#));
+        assert!(atom.contains("<updated>2025-11-12T10:00:00+00:00</updated>"));
+        assert!(atom.contains(r#"<category term="rust" />"#));
+    }
+
+    #[test]
+    fn test_tag_feed_links() {
+        let mut config = SsgConfig::default();
+        config.site.url = "https://example.com".to_string();
+        config.build.feeds.formats = vec![FeedFormat::Rss, FeedFormat::Atom];
+
+        let links = FeedGenerator::tag_feed_links(&config, "한글");
+        assert_eq!(links.len(), 4);
+        assert_eq!(links[0].url, "https://example.com/feed.xml");
+        assert_eq!(
+            links[2].url,
+            "https://example.com/tags/%ED%95%9C%EA%B8%80/feed.xml"
+        );
+        assert_eq!(links[3].mime_type, "application/atom+xml");
+    }
+
+    #[test]
+    fn test_render_json() {
+        let json = FeedFormat::Json.render(&create_test_feed()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], "https://jsonfeed.org/version/1.1");
+        assert_eq!(value["feed_url"], "https://example.com/dev/feed.json");
+        assert_eq!(value["items"][0]["id"], "https://example.com/dev/hello");
+        assert_eq!(value["items"][0]["tags"][0], "rust");
+    }
 }
@@ -1,11 +1,16 @@
-use crate::config::SsgConfig;
+use crate::cache::{fingerprint, hash_file};
+use crate::category;
+use crate::config::{site_path, SsgConfig};
+use crate::feeds::FeedGenerator;
+use crate::i18n::{LanguageContext, Translations};
+use crate::plugin::{PluginContext, PluginManager};
 use crate::slug;
-use crate::theme::ThemeEngine;
+use crate::theme::{hash_templates, template_dependencies, ThemeEngine};
 use crate::types::{Page, Post};
 use anyhow::{Context, Result};
 use serde::Serialize;
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
 use tera::{Context as TeraContext, Tera};
@@ -24,6 +29,10 @@ pub struct Generator {
     theme_engine: ThemeEngine,
     theme_variables: HashMap<String, serde_yaml::Value>,
     theme_info: HashMap<String, String>,
+    template_hashes: HashMap<String, String>,
+    translations: Translations,
+    /// Hash of every file named in an external plugin command
+    plugin_programs: BTreeMap<String, String>,
 }
 
 impl Generator {
@@ -32,6 +41,9 @@ impl Generator {
         let tera = theme_engine.create_tera_engine()?;
         let theme_variables = theme_engine.get_template_variables();
         let theme_info = theme_engine.get_theme_info();
+        let template_hashes = hash_templates(&tera);
+        let translations = Translations::load(&theme_engine, &config)?;
+        let plugin_programs = hash_plugin_programs(&config);
 
         Ok(Self {
             tera,
@@ -39,6 +51,9 @@ impl Generator {
             theme_engine,
             theme_variables,
             theme_info,
+            template_hashes,
+            translations,
+            plugin_programs,
         })
     }
 
@@ -46,16 +61,22 @@ impl Generator {
         &self,
         post: &Post,
         plugin_data: &HashMap<String, JsonValue>,
+        plugin_manager: &PluginManager,
+        plugin_ctx: &PluginContext,
     ) -> Result<PathBuf> {
         let html = post
             .rendered_html
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("Post not rendered: {}", post.slug))?;
 
+        // Translated posts read like pages of a site in their own language
+        let language = self.post_language(post);
+        let config = self.config.localized(&language.lang);
+
         let template_config = TemplateConfig {
-            site_title: &self.config.site.title,
-            site_url: &self.config.site.url,
-            author: &self.config.site.author,
+            site_title: &config.site.title,
+            site_url: &config.site.url,
+            author: &config.site.author,
         };
 
         let mut context = TeraContext::new();
@@ -63,7 +84,16 @@ impl Generator {
         context.insert("slug", &post.slug);
         context.insert("category", &post.category);
         context.insert("content", html);
+        context.insert("toc", &post.toc);
         context.insert("config", &template_config);
+        let category_name =
+            category::get_category_by_slug(&post.category, plugin_ctx.metadata.get_category_info())
+                .map_or(post.category.as_str(), |c| c.name.as_str());
+        context.insert(
+            "feeds",
+            &FeedGenerator::category_feed_links(&config, &post.category, category_name),
+        );
+        language.insert_into(&mut context);
 
         // Add theme context
         context.insert("theme_variables", &self.theme_variables);
@@ -77,8 +107,7 @@ impl Generator {
         let output = self.tera.render("post.html", &context)?;
 
         let output_path = self.get_post_path(post);
-        fs::create_dir_all(output_path.parent().unwrap())?;
-        fs::write(&output_path, output)?;
+        plugin_manager.write_page(&output_path, output, plugin_ctx)?;
 
         Ok(output_path)
     }
@@ -87,6 +116,8 @@ impl Generator {
         &self,
         page: &Page,
         plugin_data: &HashMap<String, JsonValue>,
+        plugin_manager: &PluginManager,
+        plugin_ctx: &PluginContext,
     ) -> Result<PathBuf> {
         let html = page
             .rendered_html
@@ -103,7 +134,11 @@ impl Generator {
         context.insert("page", &page.frontmatter);
         context.insert("slug", &page.slug);
         context.insert("content", html);
+        context.insert("toc", &page.toc);
         context.insert("config", &template_config);
+        context.insert("feeds", &FeedGenerator::site_feed_links(&self.config));
+        self.language_context(&self.config.site.language)
+            .insert_into(&mut context);
 
         context.insert("theme_variables", &self.theme_variables);
         context.insert("theme_info", &self.theme_info);
@@ -115,8 +150,7 @@ impl Generator {
         let output = self.tera.render("page.html", &context)?;
 
         let output_path = self.get_page_path(page);
-        fs::create_dir_all(output_path.parent().unwrap())?;
-        fs::write(&output_path, output)?;
+        plugin_manager.write_page(&output_path, output, plugin_ctx)?;
 
         Ok(output_path)
     }
@@ -125,11 +159,110 @@ impl Generator {
         &self.tera
     }
 
+    /// UI strings of every site language
+    pub fn translations(&self) -> &Translations {
+        &self.translations
+    }
+
+    /// Language, site languages and UI strings for templates in one language
+    pub fn language_context(&self, code: &str) -> LanguageContext {
+        LanguageContext::new(&self.config, &self.translations, code)
+    }
+
+    fn post_language(&self, post: &Post) -> LanguageContext {
+        let code = post
+            .frontmatter
+            .lang
+            .as_deref()
+            .unwrap_or(&self.config.site.language);
+        self.language_context(code)
+    }
+
+    /// Hash of every loaded template, keyed by template name
+    pub fn template_hashes(&self) -> &HashMap<String, String> {
+        &self.template_hashes
+    }
+
+    /// Templates read when rendering `template` with the given components applied
+    pub fn template_dependencies(
+        &self,
+        template: &str,
+        components: &[String],
+    ) -> BTreeMap<String, String> {
+        std::iter::once(template)
+            .chain(components.iter().map(|c| c.as_str()))
+            .flat_map(|name| template_dependencies(&self.tera, name))
+            .filter_map(|name| {
+                let hash = self.template_hashes.get(&name)?.clone();
+                Some((name, hash))
+            })
+            .collect()
+    }
+
+    /// Site-level values inserted into every post and page context
+    pub fn metadata_dependencies(&self) -> BTreeMap<String, String> {
+        let template_config = TemplateConfig {
+            site_title: &self.config.site.title,
+            site_url: &self.config.site.url,
+            author: &self.config.site.author,
+        };
+        let theme_variables: BTreeMap<_, _> = self.theme_variables.iter().collect();
+        let theme_info: BTreeMap<_, _> = self.theme_info.iter().collect();
+
+        let mut deps = BTreeMap::new();
+        deps.insert("config".to_string(), fingerprint(&template_config));
+        deps.insert("theme_variables".to_string(), fingerprint(&theme_variables));
+        deps.insert("theme_info".to_string(), fingerprint(&theme_info));
+        deps.insert("feeds".to_string(), fingerprint(&self.config.build.feeds));
+        deps.insert("toc".to_string(), fingerprint(&self.config.build.toc));
+        deps.insert(
+            "reading".to_string(),
+            fingerprint(&self.config.build.reading),
+        );
+        deps.insert(
+            "excerpt".to_string(),
+            fingerprint(&self.config.build.excerpt),
+        );
+        // Posts only keep their plain text while full-text search needs it
+        deps.insert(
+            "full_text".to_string(),
+            fingerprint(&self.config.build.search.full_text),
+        );
+        deps.insert(
+            "i18n".to_string(),
+            fingerprint(&(&self.config.site, &self.translations)),
+        );
+        deps.insert(
+            "plugins".to_string(),
+            fingerprint(&(&self.config.plugins, &self.plugin_programs)),
+        );
+
+        // Adding or removing a component template changes which tags get replaced
+        let components: BTreeSet<&String> = self
+            .template_hashes
+            .keys()
+            .filter(|name| name.starts_with("components/"))
+            .collect();
+        deps.insert("components".to_string(), fingerprint(&components));
+        deps
+    }
+
+    /// Delete a post's output left over from an earlier build
+    pub fn remove_post(&self, post: &Post) -> Result<()> {
+        remove_output(&self.get_post_path(post))
+    }
+
+    /// Delete a page's output left over from an earlier build
+    pub fn remove_page(&self, page: &Page) -> Result<()> {
+        remove_output(&self.get_page_path(page))
+    }
+
     fn get_post_path(&self, post: &Post) -> PathBuf {
         let category = self.maybe_encode(&post.category);
         let slug = self.maybe_encode(&post.slug);
+        let language = self.post_language(post).lang;
 
-        PathBuf::from(&self.config.build.output_dir)
+        PathBuf::from(&self.config.localized(&language).build.output_dir)
             .join(category)
             .join(slug)
             .join("index.html")
@@ -159,7 +292,7 @@ impl Generator {
             println!("📦 Copied theme static assets");
         }
 
-        let src = Path::new("static");
+        let src = Path::new(&self.config.build.static_dir);
         if src.exists() {
             Self::copy_dir_all(src, dst)?;
             println!("📦 Copied static assets");
@@ -168,6 +301,50 @@ impl Generator {
         Ok(())
     }
 
+    /// Copy one changed file from a theme's or the site's `static/` directory.
+    /// The copy that wins a full build wins here too: the site over the
+    /// active theme over its parent. A file deleted everywhere is removed.
+    /// `path` is relative to the site root, as watch mode reports it.
+    pub fn copy_static_file(&self, path: &Path) -> Result<()> {
+        let roots: Vec<PathBuf> = self
+            .theme_engine
+            .static_paths
+            .iter()
+            .map(site_path)
+            .chain([site_path(&self.config.build.static_dir)])
+            .collect();
+
+        let Some(relative) = roots.iter().find_map(|root| path.strip_prefix(root).ok()) else {
+            // Not part of the active theme
+            return Ok(());
+        };
+
+        let output_path = Path::new(&self.config.build.output_dir).join(relative);
+        match roots
+            .iter()
+            .rev()
+            .map(|root| root.join(relative))
+            .find(|p| p.is_file())
+        {
+            Some(source) => {
+                if let Some(parent) = output_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(&source, &output_path).with_context(|| {
+                    format!(
+                        "Failed to copy {} to {}",
+                        source.display(),
+                        output_path.display()
+                    )
+                })?;
+            }
+            None if output_path.is_file() => fs::remove_file(&output_path)?,
+            None => {}
+        }
+
+        Ok(())
+    }
+
     pub fn copy_content_assets(&self) -> Result<()> {
         let content_dir = Path::new(&self.config.build.content_dir);
         let output_dir = Path::new(&self.config.build.output_dir);
@@ -184,46 +361,28 @@ impl Generator {
         {
             let path = entry.path();
 
-            if !path.is_file() {
+            if !path.is_file() || !Self::is_content_asset(path) {
                 continue;
             }
 
-            if let Some(ext) = path.extension() {
-                let ext_str = ext.to_string_lossy().to_lowercase();
-                if ext_str == "md" {
-                    continue;
-                }
+            let relative_path = path.strip_prefix(content_dir)?;
+            let encoded_path = Self::encode_asset_path(relative_path);
+            let full_output_path = output_dir.join(&encoded_path);
 
-                let is_image = matches!(
-                    ext_str.as_str(),
-                    "png" | "jpg" | "jpeg" | "gif" | "webp" | "svg" | "ico" | "bmp"
-                );
-
-                let is_media = matches!(ext_str.as_str(), "mp4" | "webm" | "mp3" | "wav");
-
-                let is_document = matches!(ext_str.as_str(), "pdf" | "zip" | "tar" | "gz");
-
-                if is_image || is_media || is_document {
-                    let relative_path = path.strip_prefix(content_dir)?;
-                    let encoded_path = Self::encode_asset_path(relative_path);
-                    let full_output_path = output_dir.join(&encoded_path);
-
-                    if let Some(parent) = full_output_path.parent() {
-                        fs::create_dir_all(parent).with_context(|| {
-                            format!("Failed to create directory for: {}", encoded_path.display())
-                        })?;
-                    }
-
-                    fs::copy(path, &full_output_path).with_context(|| {
-                        format!(
-                            "Failed to copy {} to {}",
-                            path.display(),
-                            full_output_path.display()
-                        )
-                    })?;
-                    copied_count += 1;
-                }
+            if let Some(parent) = full_output_path.parent() {
+                fs::create_dir_all(parent).with_context(|| {
+                    format!("Failed to create directory for: {}", encoded_path.display())
+                })?;
             }
+
+            fs::copy(path, &full_output_path).with_context(|| {
+                format!(
+                    "Failed to copy {} to {}",
+                    path.display(),
+                    full_output_path.display()
+                )
+            })?;
+            copied_count += 1;
         }
 
         if copied_count > 0 {
@@ -233,6 +392,56 @@ impl Generator {
         Ok(())
     }
 
+    /// Copy one changed file from the content directory, or remove the copy
+    /// of a deleted one. `path` is relative to the site root.
+    pub fn copy_content_asset(&self, path: &Path) -> Result<()> {
+        let content_dir = site_path(&self.config.build.content_dir);
+        let Ok(relative_path) = path.strip_prefix(&content_dir) else {
+            return Ok(());
+        };
+        if !Self::is_content_asset(path) {
+            return Ok(());
+        }
+
+        let output_path =
+            Path::new(&self.config.build.output_dir).join(Self::encode_asset_path(relative_path));
+        if path.is_file() {
+            if let Some(parent) = output_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(path, &output_path).with_context(|| {
+                format!(
+                    "Failed to copy {} to {}",
+                    path.display(),
+                    output_path.display()
+                )
+            })?;
+        } else if output_path.is_file() {
+            fs::remove_file(&output_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Images, media and documents are published next to the posts that use them
+    fn is_content_asset(path: &Path) -> bool {
+        let Some(ext) = path.extension() else {
+            return false;
+        };
+        let ext_str = ext.to_string_lossy().to_lowercase();
+
+        let is_image = matches!(
+            ext_str.as_str(),
+            "png" | "jpg" | "jpeg" | "gif" | "webp" | "svg" | "ico" | "bmp"
+        );
+
+        let is_media = matches!(ext_str.as_str(), "mp4" | "webm" | "mp3" | "wav");
+
+        let is_document = matches!(ext_str.as_str(), "pdf" | "zip" | "tar" | "gz");
+
+        is_image || is_media || is_document
+    }
+
     fn encode_asset_path(path: &Path) -> PathBuf {
         // No encoding needed - keep UTF-8 filenames as-is
         // Web servers (Nginx) decode URLs before filesystem lookup
@@ -258,3 +467,26 @@ impl Generator {
         Ok(())
     }
 }
+
+/// Remove an `index.html` output and its directory, unless something else lives there
+fn remove_output(output_path: &Path) -> Result<()> {
+    if output_path.exists() {
+        fs::remove_file(output_path)?;
+        if let Some(dir) = output_path.parent() {
+            // Only succeeds when the directory is empty, e.g. no co-located assets
+            let _ = fs::remove_dir(dir);
+        }
+    }
+    Ok(())
+}
+
+/// External plugin scripts can change without config.yaml changing
+fn hash_plugin_programs(config: &SsgConfig) -> BTreeMap<String, String> {
+    config
+        .plugins
+        .values()
+        .filter_map(|plugin| plugin.command.as_ref())
+        .flatten()
+        .filter_map(|arg| Some((arg.clone(), hash_file(Path::new(arg)).ok()?)))
+        .collect()
+}
@@ -0,0 +1,464 @@
+//! Lenient HTML tokenizer and tree used to apply component templates.
+//!
+//! Every node keeps its source text, so serializing an unmodified tree gives
+//! back the input byte for byte, even for malformed markup.
+
+/// Elements that never have content or an end tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Elements whose content is raw text, not markup
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    /// Text, comments, doctypes and anything else passed through verbatim
+    Text(String),
+    Element(Element),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    /// Lowercase tag name
+    pub name: String,
+    /// Attributes in source order with entities decoded; valueless attributes are `"true"`
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+    start_tag: String,
+    /// `None` for void, self-closing and unclosed elements
+    end_tag: Option<String>,
+}
+
+impl Element {
+    pub fn start_tag(&self) -> &str {
+        &self.start_tag
+    }
+
+    pub fn end_tag(&self) -> Option<&str> {
+        self.end_tag.as_deref()
+    }
+}
+
+type Attributes = Vec<(String, String)>;
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Text(String),
+    StartTag {
+        name: String,
+        attributes: Attributes,
+        self_closing: bool,
+        raw: String,
+    },
+    EndTag {
+        name: String,
+        raw: String,
+    },
+}
+
+/// Parse an HTML fragment into a tree.
+///
+/// End tags close the nearest open element of the same name; stray end tags
+/// are kept as text and elements left open at the end are closed implicitly.
+pub fn parse(html: &str) -> Vec<Node> {
+    let mut root: Vec<Node> = Vec::new();
+    let mut stack: Vec<Element> = Vec::new();
+
+    fn append(stack: &mut [Element], root: &mut Vec<Node>, node: Node) {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => root.push(node),
+        }
+    }
+
+    for token in tokenize(html) {
+        match token {
+            Token::Text(text) => append(&mut stack, &mut root, Node::Text(text)),
+            Token::StartTag {
+                name,
+                attributes,
+                self_closing,
+                raw,
+            } => {
+                let element = Element {
+                    attributes,
+                    children: Vec::new(),
+                    start_tag: raw,
+                    end_tag: None,
+                    name,
+                };
+                if self_closing || VOID_ELEMENTS.contains(&element.name.as_str()) {
+                    append(&mut stack, &mut root, Node::Element(element));
+                } else {
+                    stack.push(element);
+                }
+            }
+            Token::EndTag { name, raw } => {
+                let Some(open) = stack.iter().rposition(|e| e.name == name) else {
+                    append(&mut stack, &mut root, Node::Text(raw));
+                    continue;
+                };
+
+                while stack.len() > open + 1 {
+                    let unclosed = stack.pop().unwrap();
+                    append(&mut stack, &mut root, Node::Element(unclosed));
+                }
+
+                let mut element = stack.pop().unwrap();
+                element.end_tag = Some(raw);
+                append(&mut stack, &mut root, Node::Element(element));
+            }
+        }
+    }
+
+    while let Some(unclosed) = stack.pop() {
+        append(&mut stack, &mut root, Node::Element(unclosed));
+    }
+
+    root
+}
+
+fn tokenize(html: &str) -> Vec<Token> {
+    let bytes = html.as_bytes();
+    let mut tokens = Vec::new();
+    let mut text_start = 0;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        if bytes[pos] != b'<' {
+            pos += 1;
+            continue;
+        }
+
+        let Some((token, end)) = read_markup(html, pos) else {
+            pos += 1;
+            continue;
+        };
+
+        if text_start < pos {
+            tokens.push(Token::Text(html[text_start..pos].to_string()));
+        }
+
+        let raw_text_end = match &token {
+            Token::StartTag {
+                name, self_closing, ..
+            } if !self_closing && RAW_TEXT_ELEMENTS.contains(&name.as_str()) => {
+                Some(find_end_tag(html, end, name))
+            }
+            _ => None,
+        };
+
+        tokens.push(token);
+        pos = end;
+
+        if let Some(raw_end) = raw_text_end {
+            if end < raw_end {
+                tokens.push(Token::Text(html[end..raw_end].to_string()));
+            }
+            pos = raw_end;
+        }
+
+        text_start = pos;
+    }
+
+    if text_start < bytes.len() {
+        tokens.push(Token::Text(html[text_start..].to_string()));
+    }
+
+    tokens
+}
+
+/// Read a tag, comment or declaration starting at `<`; `None` if it is plain text
+fn read_markup(html: &str, start: usize) -> Option<(Token, usize)> {
+    let rest = &html[start..];
+
+    if let Some(comment) = rest.strip_prefix("<!--") {
+        let end = comment
+            .find("-->")
+            .map_or(html.len(), |i| start + 4 + i + 3);
+        return Some((Token::Text(html[start..end].to_string()), end));
+    }
+
+    if rest.starts_with("<!") || rest.starts_with("<?") {
+        let end = start + rest.find('>')? + 1;
+        return Some((Token::Text(html[start..end].to_string()), end));
+    }
+
+    if let Some(after) = rest.strip_prefix("</") {
+        if !after.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        let end = start + rest.find('>')? + 1;
+        let name = read_name(after).to_ascii_lowercase();
+        return Some((
+            Token::EndTag {
+                name,
+                raw: html[start..end].to_string(),
+            },
+            end,
+        ));
+    }
+
+    let after = &rest[1..];
+    if !after.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let name = read_name(after);
+    let (attributes, self_closing, len) = read_attributes(&after[name.len()..])?;
+    let end = start + 1 + name.len() + len;
+
+    Some((
+        Token::StartTag {
+            name: name.to_ascii_lowercase(),
+            attributes,
+            self_closing,
+            raw: html[start..end].to_string(),
+        },
+        end,
+    ))
+}
+
+fn read_name(s: &str) -> &str {
+    let len = s
+        .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+        .unwrap_or(s.len());
+    &s[..len]
+}
+
+/// Parse attributes up to and including the closing `>`.
+/// Returns the attributes, whether the tag ended with `/>`, and the bytes consumed.
+fn read_attributes(s: &str) -> Option<(Attributes, bool, usize)> {
+    let bytes = s.as_bytes();
+    let mut attributes = Attributes::new();
+    let mut pos = 0;
+
+    loop {
+        while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'/') {
+            if bytes[pos] == b'/' && bytes.get(pos + 1) == Some(&b'>') {
+                return Some((attributes, true, pos + 2));
+            }
+            pos += 1;
+        }
+
+        if *bytes.get(pos)? == b'>' {
+            return Some((attributes, false, pos + 1));
+        }
+
+        let name_start = pos;
+        while pos < bytes.len()
+            && !bytes[pos].is_ascii_whitespace()
+            && !matches!(bytes[pos], b'=' | b'>' | b'/')
+        {
+            pos += 1;
+        }
+        if pos == name_start {
+            // A lone `=`; skip it so the loop always advances
+            pos += 1;
+            continue;
+        }
+        let name = s[name_start..pos].to_ascii_lowercase();
+
+        let mut value_pos = pos;
+        while value_pos < bytes.len() && bytes[value_pos].is_ascii_whitespace() {
+            value_pos += 1;
+        }
+
+        if bytes.get(value_pos) != Some(&b'=') {
+            push_attribute(&mut attributes, name, "true".to_string());
+            continue;
+        }
+
+        pos = value_pos + 1;
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+
+        let value = match bytes.get(pos)? {
+            quote @ (b'"' | b'\'') => {
+                let value_start = pos + 1;
+                let len = s[value_start..].find(*quote as char)?;
+                pos = value_start + len + 1;
+                &s[value_start..value_start + len]
+            }
+            _ => {
+                let value_start = pos;
+                while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && bytes[pos] != b'>' {
+                    pos += 1;
+                }
+                &s[value_start..pos]
+            }
+        };
+
+        push_attribute(&mut attributes, name, decode_entities(value));
+    }
+}
+
+/// Keep the first occurrence of a duplicated attribute, as browsers do
+fn push_attribute(attributes: &mut Attributes, name: String, value: String) {
+    if !attributes.iter().any(|(existing, _)| *existing == name) {
+        attributes.push((name, value));
+    }
+}
+
+/// Byte offset of the `</name` that closes a raw text element, or the end of input
+fn find_end_tag(html: &str, from: usize, name: &str) -> usize {
+    let lower = html[from..].to_ascii_lowercase();
+    let needle = format!("</{}", name);
+    lower.find(&needle).map_or(html.len(), |i| from + i)
+}
+
+/// Decode character references in an attribute value
+pub fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest.find(';').and_then(|semi| {
+            let entity = &rest[1..semi];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some('\u{a0}'),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, semi + 1))
+        });
+
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serialize nodes back to their source text
+    fn to_html(nodes: &[Node]) -> String {
+        let mut html = String::new();
+        for node in nodes {
+            write_node(node, &mut html);
+        }
+        html
+    }
+
+    fn write_node(node: &Node, out: &mut String) {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Element(element) => {
+                out.push_str(&element.start_tag);
+                for child in &element.children {
+                    write_node(child, out);
+                }
+                if let Some(end) = &element.end_tag {
+                    out.push_str(end);
+                }
+            }
+        }
+    }
+
+    fn element(nodes: &[Node], index: usize) -> &Element {
+        match &nodes[index] {
+            Node::Element(e) => e,
+            other => panic!("expected element, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_round_trip_preserves_source() {
+        let inputs = [
+            "<p>Hello <strong>world</strong></p>\n",
+            "<div class=\"a\"><p>unclosed<span>x</div> tail",
+            "stray </em> end and a < b > c",
+            "<!-- <p> comment --><!DOCTYPE html><br><img src=a.png/>",
+            "<script>if (a < b) { document.write('<p>') }</script>",
+            "<a href='x'",
+        ];
+
+        for input in inputs {
+            assert_eq!(to_html(&parse(input)), input);
+        }
+    }
+
+    #[test]
+    fn test_attributes() {
+        let nodes = parse(
+            r#"<video controls data-id=7 title="a > b" alt='it&#39;s' src="/v?a=1&amp;b=2" CLASS=x class=y>"#,
+        );
+        let video = element(&nodes, 0);
+
+        assert_eq!(video.name, "video");
+        assert_eq!(
+            video.attributes,
+            vec![
+                ("controls".to_string(), "true".to_string()),
+                ("data-id".to_string(), "7".to_string()),
+                ("title".to_string(), "a > b".to_string()),
+                ("alt".to_string(), "it's".to_string()),
+                ("src".to_string(), "/v?a=1&b=2".to_string()),
+                ("class".to_string(), "x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nesting_same_tag() {
+        let nodes = parse("<div><div title=\"<div>\">inner</div>after</div>");
+        let outer = element(&nodes, 0);
+
+        assert_eq!(outer.children.len(), 2);
+        let inner = element(&outer.children, 0);
+        assert_eq!(inner.attributes[0].1, "<div>");
+        assert_eq!(to_html(&inner.children), "inner");
+        assert_eq!(outer.children[1], Node::Text("after".to_string()));
+    }
+
+    #[test]
+    fn test_void_and_self_closing() {
+        let nodes = parse("<p><img src=\"a.png\"><br/>text</p>");
+        let p = element(&nodes, 0);
+
+        assert_eq!(p.children.len(), 3);
+        assert_eq!(element(&p.children, 0).end_tag(), None);
+        assert_eq!(element(&p.children, 1).name, "br");
+        assert_eq!(p.end_tag(), Some("</p>"));
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(decode_entities("a &amp;lt; b"), "a &lt; b");
+        assert_eq!(
+            decode_entities("&#x1F600;&#65;&unknown; &"),
+            "😀A&unknown; &"
+        );
+    }
+}
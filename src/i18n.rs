@@ -0,0 +1,155 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use tera::Context as TeraContext;
+
+use crate::config::SsgConfig;
+use crate::diagnostics::SourceError;
+use crate::theme::ThemeEngine;
+
+/// Directory holding a theme's `<code>.yaml` translation files
+const TRANSLATIONS_DIR: &str = "i18n";
+
+/// Themes ship this language complete; it fills keys no site language translates
+const FALLBACK_LANGUAGE: &str = "en";
+
+/// A configured site language, resolved from config.yaml
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Language {
+    pub code: String,
+    pub name: String,
+    /// BCP 47 tag, e.g. `en-US`
+    pub locale: String,
+    /// URL prefix: empty for the main language, `/<code>` otherwise
+    pub prefix: String,
+    pub is_default: bool,
+}
+
+/// UI strings for every site language.
+///
+/// Each language reads `i18n/<code>.yaml` from the parent theme and the active
+/// theme, then `<code>.yaml` from `build.i18n_dir`, later files overriding
+/// earlier ones. Keys a language does not translate fall back to the main
+/// language, then English.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Translations {
+    strings: BTreeMap<String, BTreeMap<String, String>>,
+    default_language: String,
+}
+
+impl Translations {
+    pub fn load(theme: &ThemeEngine, config: &SsgConfig) -> Result<Self> {
+        let mut dirs: Vec<_> = theme
+            .template_paths
+            .iter()
+            .rev()
+            .map(|path| path.join(TRANSLATIONS_DIR))
+            .collect();
+        dirs.push(Path::new(&config.build.i18n_dir).to_path_buf());
+
+        let mut codes: Vec<_> = config.languages().into_iter().map(|l| l.code).collect();
+        codes.push(FALLBACK_LANGUAGE.to_string());
+
+        let mut strings = BTreeMap::new();
+        for code in codes {
+            let mut merged = BTreeMap::new();
+            for dir in &dirs {
+                let path = dir.join(format!("{}.yaml", code));
+                if !path.exists() {
+                    continue;
+                }
+                let content = fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let file: BTreeMap<String, String> =
+                    serde_yaml::from_str(&content).map_err(|e| SourceError::yaml(&path, &e))?;
+                merged.extend(file);
+            }
+            strings.insert(code, merged);
+        }
+
+        Ok(Self {
+            strings,
+            default_language: config.site.language.clone(),
+        })
+    }
+
+    /// Every UI string for a language, with the fallbacks filling the gaps
+    pub fn for_language(&self, code: &str) -> BTreeMap<String, String> {
+        let mut merged = BTreeMap::new();
+        for code in [FALLBACK_LANGUAGE, &self.default_language, code] {
+            if let Some(strings) = self.strings.get(code) {
+                merged.extend(strings.clone());
+            }
+        }
+        merged
+    }
+}
+
+/// What every template learns about the language it renders
+#[derive(Debug, Clone, Serialize)]
+pub struct LanguageContext {
+    pub lang: Language,
+    /// Every site language, the main one first, e.g. for a language switcher
+    pub languages: Vec<Language>,
+    /// UI strings, e.g. `{{ t.recent_posts }}`
+    pub t: BTreeMap<String, String>,
+}
+
+impl LanguageContext {
+    /// Context for a language code; unknown codes get the main language
+    pub fn new(config: &SsgConfig, translations: &Translations, code: &str) -> Self {
+        let lang = config
+            .language(code)
+            .unwrap_or_else(|| config.default_language());
+        Self {
+            t: translations.for_language(&lang.code),
+            languages: config.languages(),
+            lang,
+        }
+    }
+
+    pub fn insert_into(&self, context: &mut TeraContext) {
+        context.insert("lang", &self.lang);
+        context.insert("languages", &self.languages);
+        context.insert("t", &self.t);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_keys_fall_back() {
+        let mut translations = Translations {
+            default_language: "ko".to_string(),
+            ..Default::default()
+        };
+        translations.strings.insert(
+            "ko".to_string(),
+            BTreeMap::from([
+                ("next".to_string(), "다음".to_string()),
+                ("previous".to_string(), "이전".to_string()),
+            ]),
+        );
+        translations.strings.insert(
+            "ja".to_string(),
+            BTreeMap::from([("next".to_string(), "次へ".to_string())]),
+        );
+        translations.strings.insert(
+            "en".to_string(),
+            BTreeMap::from([
+                ("next".to_string(), "Next".to_string()),
+                ("tags".to_string(), "Tags".to_string()),
+            ]),
+        );
+
+        let ja = translations.for_language("ja");
+        assert_eq!(ja["next"], "次へ");
+        assert_eq!(ja["previous"], "이전");
+        assert_eq!(ja["tags"], "Tags");
+        assert_eq!(translations.for_language("fr")["next"], "다음");
+    }
+}
@@ -1,13 +1,16 @@
 use crate::config::SsgConfig;
-use crate::metadata::MetadataCache;
+use crate::feeds::FeedGenerator;
+use crate::i18n::{LanguageContext, Translations};
+use crate::metadata::{MetadataCache, PostMetadata};
+use crate::navigation;
 use crate::plugin::{PluginContext, PluginManager};
 use crate::slug;
 use crate::theme::ThemeEngine;
 use anyhow::Result;
 use serde::Serialize;
 use serde_json::Value as JsonValue;
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::fs;
 use std::path::PathBuf;
 use tera::{Context as TeraContext, Tera};
 
@@ -47,11 +50,23 @@ struct TemplateConfig<'a> {
     author: &'a str,
 }
 
+/// Series overview context for templates
+#[derive(Debug, Serialize)]
+struct SeriesContext<'a> {
+    name: &'a str,
+    slug: &'a str,
+    url: String,
+    posts: Vec<&'a PostMetadata>,
+}
+
 pub struct IndexGenerator {
     tera: Tera,
     config: SsgConfig,
     theme_variables: HashMap<String, serde_yaml::Value>,
     theme_info: HashMap<String, String>,
+    language: LanguageContext,
+    /// Every page written so far
+    written: RefCell<Vec<PathBuf>>,
 }
 
 impl IndexGenerator {
@@ -60,16 +75,31 @@ impl IndexGenerator {
         let tera = theme_engine.create_tera_engine()?;
         let theme_variables = theme_engine.get_template_variables();
         let theme_info = theme_engine.get_theme_info();
+        let translations = Translations::load(&theme_engine, &config)?;
+        let language = LanguageContext::new(&config, &translations, &config.site.language);
 
         Ok(Self {
             tera,
             config,
             theme_variables,
             theme_info,
+            language,
+            written: RefCell::default(),
         })
     }
 
-    pub fn generate_all(&self, metadata: &MetadataCache, plugin_manager: &PluginManager) -> Result<()> {
+    /// Generate the listings of one language; `config` should be localized to it
+    pub fn with_language(mut self, language: LanguageContext) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Every listing of the site. Returns the paths of the pages written.
+    pub fn generate_all(
+        &self,
+        metadata: &MetadataCache,
+        plugin_manager: &PluginManager,
+    ) -> Result<Vec<PathBuf>> {
         println!("\n📑 Generating indices...");
 
         // Create plugin context for index pages
@@ -79,27 +109,111 @@ impl IndexGenerator {
         };
         let plugin_data = plugin_manager.template_context_index(&plugin_ctx)?;
 
-        self.generate_homepage(metadata, &plugin_data)?;
+        self.generate_homepage(metadata, &plugin_data, plugin_manager, &plugin_ctx)?;
 
         let category_count = metadata.get_category_info().len();
         for category in metadata.get_category_info() {
-            self.generate_category_page(category, metadata, &plugin_data)?;
+            self.generate_category_page(
+                category,
+                metadata,
+                &plugin_data,
+                plugin_manager,
+                &plugin_ctx,
+            )?;
         }
 
         for tag in metadata.get_tags() {
-            self.generate_tag_page(&tag, metadata, &plugin_data)?;
+            self.generate_tag_page(&tag, metadata, &plugin_data, plugin_manager, &plugin_ctx)?;
+        }
+
+        self.generate_tags_overview(metadata, &plugin_data, plugin_manager, &plugin_ctx)?;
+
+        let series = metadata.get_series();
+        if !series.is_empty() && !self.has_template("series.html") {
+            eprintln!("   ⚠ Theme has no series.html, skipping series pages");
+        } else {
+            for name in &series {
+                self.generate_series_page(
+                    name,
+                    metadata,
+                    &plugin_data,
+                    plugin_manager,
+                    &plugin_ctx,
+                )?;
+            }
         }
 
-        self.generate_tags_overview(metadata, &plugin_data)?;
+        if self.has_template("404.html") {
+            self.generate_not_found(metadata, &plugin_data, plugin_manager, &plugin_ctx)?;
+        }
 
         println!("   ✓ Homepage");
         println!("   ✓ {} category pages", category_count);
         println!("   ✓ {} tag pages", metadata.get_tags().len());
+        if !series.is_empty() {
+            println!("   ✓ {} series pages", series.len());
+        }
+
+        Ok(self.written.take())
+    }
+
+    /// Regenerate the listings one post appears in: the homepage and the pages
+    /// of its category, tags and series. Enough after an edit that leaves the
+    /// post's place in every listing alone, e.g. a new excerpt.
+    pub fn generate_for_post(
+        &self,
+        metadata: &MetadataCache,
+        plugin_manager: &PluginManager,
+        post: &PostMetadata,
+    ) -> Result<()> {
+        let plugin_ctx = PluginContext {
+            config: &self.config,
+            metadata,
+        };
+        let plugin_data = plugin_manager.template_context_index(&plugin_ctx)?;
+
+        self.generate_homepage(metadata, &plugin_data, plugin_manager, &plugin_ctx)?;
+
+        if let Some(category) = metadata
+            .get_category_info()
+            .iter()
+            .find(|c| c.slug == post.category)
+        {
+            self.generate_category_page(
+                category,
+                metadata,
+                &plugin_data,
+                plugin_manager,
+                &plugin_ctx,
+            )?;
+        }
+
+        for tag in &post.frontmatter.tags {
+            self.generate_tag_page(tag, metadata, &plugin_data, plugin_manager, &plugin_ctx)?;
+        }
+
+        if let Some(series) = &post.frontmatter.series {
+            if self.has_template("series.html") {
+                self.generate_series_page(
+                    series,
+                    metadata,
+                    &plugin_data,
+                    plugin_manager,
+                    &plugin_ctx,
+                )?;
+            }
+        }
 
         Ok(())
     }
 
-    fn generate_homepage(&self, metadata: &MetadataCache, plugin_data: &HashMap<String, JsonValue>) -> Result<()> {
+    fn generate_homepage(
+        &self,
+        metadata: &MetadataCache,
+        plugin_data: &HashMap<String, JsonValue>,
+        plugin_manager: &PluginManager,
+        plugin_ctx: &PluginContext,
+    ) -> Result<()> {
         let recent_posts = metadata.get_recent_posts(10);
 
         let visible_categories: Vec<_> = metadata
@@ -118,6 +232,8 @@ impl IndexGenerator {
         context.insert("posts", &recent_posts);
         context.insert("categories", &visible_categories);
         context.insert("config", &template_config);
+        self.language.insert_into(&mut context);
+        context.insert("feeds", &FeedGenerator::site_feed_links(&self.config));
 
         // Add theme context
         context.insert("theme_variables", &self.theme_variables);
@@ -131,7 +247,7 @@ impl IndexGenerator {
         let output = self.tera.render("index.html", &context)?;
         let output_path = PathBuf::from(&self.config.build.output_dir).join("index.html");
 
-        fs::write(&output_path, output)?;
+        self.write_page(output_path, output, plugin_manager, plugin_ctx)?;
 
         Ok(())
     }
@@ -141,6 +257,8 @@ impl IndexGenerator {
         category_info: &crate::types::Category,
         metadata: &MetadataCache,
         plugin_data: &HashMap<String, JsonValue>,
+        plugin_manager: &PluginManager,
+        plugin_ctx: &PluginContext,
     ) -> Result<()> {
         let mut posts = metadata.get_posts_by_category(&category_info.slug);
 
@@ -151,10 +269,10 @@ impl IndexGenerator {
         let total_pages = if total_posts == 0 {
             1
         } else {
-            (total_posts + posts_per_page - 1) / posts_per_page
+            total_posts.div_ceil(posts_per_page)
         };
 
-        let base_url = format!("/{}/", category_info.slug);
+        let base_url = format!("{}/{}/", self.language.lang.prefix, category_info.slug);
 
         let visible_categories: Vec<_> = metadata
             .get_category_info()
@@ -180,6 +298,15 @@ impl IndexGenerator {
             context.insert("post_count", &total_posts);
             context.insert("categories", &visible_categories);
             context.insert("config", &template_config);
+            self.language.insert_into(&mut context);
+            context.insert(
+                "feeds",
+                &FeedGenerator::category_feed_links(
+                    &self.config,
+                    &category_info.slug,
+                    &category_info.name,
+                ),
+            );
 
             if total_pages > 1 {
                 let pagination = self.build_pagination_context(page_num, total_posts, &base_url);
@@ -210,14 +337,20 @@ impl IndexGenerator {
                     .join("index.html")
             };
 
-            fs::create_dir_all(output_path.parent().unwrap())?;
-            fs::write(&output_path, output)?;
+            self.write_page(output_path, output, plugin_manager, plugin_ctx)?;
         }
 
         Ok(())
     }
 
-    fn generate_tag_page(&self, tag: &str, metadata: &MetadataCache, plugin_data: &HashMap<String, JsonValue>) -> Result<()> {
+    fn generate_tag_page(
+        &self,
+        tag: &str,
+        metadata: &MetadataCache,
+        plugin_data: &HashMap<String, JsonValue>,
+        plugin_manager: &PluginManager,
+        plugin_ctx: &PluginContext,
+    ) -> Result<()> {
         let mut posts = metadata.get_posts_by_tag(tag);
 
         posts.sort_by(|a, b| b.frontmatter.date.cmp(&a.frontmatter.date));
@@ -227,10 +360,10 @@ impl IndexGenerator {
         let total_pages = if total_posts == 0 {
             1
         } else {
-            (total_posts + posts_per_page - 1) / posts_per_page
+            total_posts.div_ceil(posts_per_page)
         };
 
-        let base_url = format!("/tag/{}/", tag);
+        let base_url = format!("{}/tag/{}/", self.language.lang.prefix, tag);
 
         let visible_categories: Vec<_> = metadata
             .get_category_info()
@@ -256,6 +389,8 @@ impl IndexGenerator {
             context.insert("post_count", &total_posts);
             context.insert("categories", &visible_categories);
             context.insert("config", &template_config);
+            self.language.insert_into(&mut context);
+            context.insert("feeds", &FeedGenerator::tag_feed_links(&self.config, tag));
 
             if total_pages > 1 {
                 let pagination = self.build_pagination_context(page_num, total_posts, &base_url);
@@ -288,16 +423,21 @@ impl IndexGenerator {
                     .join("index.html")
             };
 
-            fs::create_dir_all(output_path.parent().unwrap())?;
-            fs::write(&output_path, output)?;
+            self.write_page(output_path, output, plugin_manager, plugin_ctx)?;
         }
 
         Ok(())
     }
 
-    fn generate_tags_overview(&self, metadata: &MetadataCache, plugin_data: &HashMap<String, JsonValue>) -> Result<()> {
+    fn generate_tags_overview(
+        &self,
+        metadata: &MetadataCache,
+        plugin_data: &HashMap<String, JsonValue>,
+        plugin_manager: &PluginManager,
+        plugin_ctx: &PluginContext,
+    ) -> Result<()> {
         let mut tags_with_counts: Vec<_> = metadata.tags.iter().collect();
-        tags_with_counts.sort_by(|a, b| b.1.cmp(a.1));
+        tags_with_counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
 
         let visible_categories: Vec<_> = metadata
             .get_category_info()
@@ -315,6 +455,8 @@ impl IndexGenerator {
         context.insert("tags", &tags_with_counts);
         context.insert("categories", &visible_categories);
         context.insert("config", &template_config);
+        self.language.insert_into(&mut context);
+        context.insert("feeds", &FeedGenerator::site_feed_links(&self.config));
 
         // Add theme context
         context.insert("theme_variables", &self.theme_variables);
@@ -330,12 +472,118 @@ impl IndexGenerator {
             .join("tags")
             .join("index.html");
 
-        fs::create_dir_all(output_path.parent().unwrap())?;
-        fs::write(&output_path, output)?;
+        self.write_page(output_path, output, plugin_manager, plugin_ctx)?;
+
+        Ok(())
+    }
+
+    /// Overview page listing every part of a series in reading order
+    fn generate_series_page(
+        &self,
+        name: &str,
+        metadata: &MetadataCache,
+        plugin_data: &HashMap<String, JsonValue>,
+        plugin_manager: &PluginManager,
+        plugin_ctx: &PluginContext,
+    ) -> Result<()> {
+        let series_slug = navigation::series_slug(name);
+        let series = SeriesContext {
+            name,
+            slug: &series_slug,
+            url: format!(
+                "{}{}",
+                self.language.lang.prefix,
+                navigation::series_url(name)
+            ),
+            posts: metadata.get_series_posts(name),
+        };
+
+        let visible_categories: Vec<_> = metadata
+            .get_category_info()
+            .iter()
+            .filter(|c| !c.hidden)
+            .collect();
+
+        let template_config = TemplateConfig {
+            site_title: &self.config.site.title,
+            site_url: &self.config.site.url,
+            author: &self.config.site.author,
+        };
+
+        let mut context = TeraContext::new();
+        context.insert("series", &series);
+        context.insert("categories", &visible_categories);
+        context.insert("config", &template_config);
+        self.language.insert_into(&mut context);
+        context.insert("feeds", &FeedGenerator::site_feed_links(&self.config));
+
+        // Add theme context
+        context.insert("theme_variables", &self.theme_variables);
+        context.insert("theme_info", &self.theme_info);
+
+        // Add plugin data
+        for (key, value) in plugin_data {
+            context.insert(key, value);
+        }
+
+        let output = self.tera.render("series.html", &context)?;
+        let output_path = PathBuf::from(&self.config.build.output_dir)
+            .join("series")
+            .join(self.maybe_encode(&series_slug))
+            .join("index.html");
+
+        self.write_page(output_path, output, plugin_manager, plugin_ctx)?;
 
         Ok(())
     }
 
+    /// `404.html`, which the dev server and most static hosts send for unknown paths
+    fn generate_not_found(
+        &self,
+        metadata: &MetadataCache,
+        plugin_data: &HashMap<String, JsonValue>,
+        plugin_manager: &PluginManager,
+        plugin_ctx: &PluginContext,
+    ) -> Result<()> {
+        let visible_categories: Vec<_> = metadata
+            .get_category_info()
+            .iter()
+            .filter(|c| !c.hidden)
+            .collect();
+
+        let template_config = TemplateConfig {
+            site_title: &self.config.site.title,
+            site_url: &self.config.site.url,
+            author: &self.config.site.author,
+        };
+
+        let mut context = TeraContext::new();
+        context.insert("categories", &visible_categories);
+        context.insert("config", &template_config);
+        self.language.insert_into(&mut context);
+        context.insert("feeds", &FeedGenerator::site_feed_links(&self.config));
+
+        // Add theme context
+        context.insert("theme_variables", &self.theme_variables);
+        context.insert("theme_info", &self.theme_info);
+
+        // Add plugin data
+        for (key, value) in plugin_data {
+            context.insert(key, value);
+        }
+
+        let output = self.tera.render("404.html", &context)?;
+        let output_path = PathBuf::from(&self.config.build.output_dir).join("404.html");
+
+        self.write_page(output_path, output, plugin_manager, plugin_ctx)?;
+
+        Ok(())
+    }
+
+    fn has_template(&self, name: &str) -> bool {
+        self.tera.get_template_names().any(|t| t == name)
+    }
+
     fn build_pagination_context(
         &self,
         current_page: usize,
@@ -346,7 +594,7 @@ impl IndexGenerator {
         let total_pages = if total_posts == 0 {
             1
         } else {
-            (total_posts + posts_per_page - 1) / posts_per_page
+            total_posts.div_ceil(posts_per_page)
         };
 
         let first_url = base_url.to_string();
@@ -424,6 +672,18 @@ impl IndexGenerator {
         }
     }
 
+    fn write_page(
+        &self,
+        path: PathBuf,
+        html: String,
+        plugin_manager: &PluginManager,
+        plugin_ctx: &PluginContext,
+    ) -> Result<()> {
+        plugin_manager.write_page(&path, html, plugin_ctx)?;
+        self.written.borrow_mut().push(path);
+        Ok(())
+    }
+
     fn maybe_encode(&self, s: &str) -> String {
         if self.config.build.encode_filenames {
             slug::encode_for_url(s)
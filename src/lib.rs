@@ -0,0 +1,55 @@
+//! ssdocs builds a static blog from markdown posts, a theme and `config.yaml`.
+//!
+//! The `ssg` binary is a thin wrapper around [`Site`]; tools can build the
+//! same way and add their own plugins and shortcodes:
+//!
+//! ```no_run
+//! use ssdocs::config::load_config;
+//! use ssdocs::Site;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let mut site = Site::builder(load_config()?)
+//!     .with_parallel(true)
+//!     .with_shortcode("year", |_, _| Ok("2024".to_string()))
+//!     .init()?;
+//!
+//! let report = site.build()?;
+//! println!("{} posts built", report.built);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod cache;
+pub mod category;
+pub mod config;
+pub mod diagnostics;
+pub mod excerpt;
+pub mod feeds;
+pub mod generator;
+pub mod html;
+pub mod i18n;
+pub mod indices;
+pub mod livereload;
+pub mod metadata;
+pub mod navigation;
+pub mod parallel;
+pub mod parser;
+pub mod pipeline;
+pub mod plugin;
+pub mod plugins;
+pub mod reading;
+pub mod renderer;
+pub mod search;
+pub mod server;
+pub mod shortcodes;
+pub mod site;
+pub mod sitemap;
+pub mod slug;
+pub mod theme;
+pub mod types;
+pub mod watch;
+
+pub use config::SsgConfig;
+pub use pipeline::{BuildOptions, BuildReport, PostOutcome, PostStatus};
+pub use plugin::{Plugin, PluginContext};
+pub use site::{Site, SiteBuilder};
@@ -0,0 +1,204 @@
+use std::io::Write;
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Dev server path browsers subscribe to for reload events
+pub const ENDPOINT: &str = "/__livereload";
+
+/// A browser that takes longer than this to accept an event is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Injected into every HTML page the watch-mode dev server sends.
+///
+/// `css` events re-request same-origin stylesheets with a cache-busting query
+/// so the page keeps its state; any other event reloads the page.
+const SCRIPT: &str = r#"<script>
+(function () {
+  var source = new EventSource("/__livereload");
+  source.addEventListener("reload", function () {
+    location.reload();
+  });
+  source.addEventListener("css", function () {
+    document.querySelectorAll('link[rel="stylesheet"]').forEach(function (link) {
+      var url = new URL(link.href);
+      if (url.origin !== location.origin) return;
+      url.searchParams.set("livereload", Date.now());
+      link.href = url.href;
+    });
+  });
+})();
+</script>
+"#;
+
+/// What connected browsers should do after a rebuild
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadEvent {
+    /// Only stylesheets changed; swap them in place
+    Css,
+    Reload,
+}
+
+impl ReloadEvent {
+    /// Pick the event for a set of changed source files
+    pub fn for_changes(paths: &[PathBuf]) -> Self {
+        let css_only = !paths.is_empty()
+            && paths
+                .iter()
+                .all(|p| p.extension().is_some_and(|ext| ext == "css"));
+
+        if css_only {
+            Self::Css
+        } else {
+            Self::Reload
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Css => "css",
+            Self::Reload => "reload",
+        }
+    }
+}
+
+/// Browsers listening on [`ENDPOINT`], shared between the dev server and the
+/// watcher. Connections are kept open as server-sent event streams.
+#[derive(Debug, Clone, Default)]
+pub struct LiveReload {
+    clients: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+impl LiveReload {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer a request to [`ENDPOINT`] and keep the stream for later events
+    pub fn subscribe(&self, mut stream: TcpStream) {
+        let headers = "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n";
+        // Ask browsers to reconnect quickly once the server comes back
+        if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err()
+            || stream.write_all(headers.as_bytes()).is_err()
+            || stream.write_all(b"retry: 1000\n\n").is_err()
+            || stream.flush().is_err()
+        {
+            return;
+        }
+
+        self.clients.lock().unwrap().push(stream);
+    }
+
+    /// Send an event to every browser, forgetting the ones that went away.
+    /// Writes happen outside the lock, so a slow browser never blocks
+    /// new subscribers.
+    pub fn notify(&self, event: ReloadEvent) {
+        let message = format!("event: {}\ndata: {{}}\n\n", event.name());
+
+        let mut clients = std::mem::take(&mut *self.clients.lock().unwrap());
+        clients.retain_mut(|stream| {
+            stream.write_all(message.as_bytes()).is_ok() && stream.flush().is_ok()
+        });
+        self.clients.lock().unwrap().extend(clients);
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+}
+
+/// Add the reload script to an HTML document, before `</body>` when present
+pub fn inject_script(html: &[u8]) -> Vec<u8> {
+    inject(html, SCRIPT)
+}
+
+/// Add markup to an HTML document, before `</body>` when present
+pub fn inject(html: &[u8], fragment: &str) -> Vec<u8> {
+    let position = html
+        .windows(b"</body>".len())
+        .rposition(|window| window.eq_ignore_ascii_case(b"</body>"))
+        .unwrap_or(html.len());
+
+    let mut output = Vec::with_capacity(html.len() + fragment.len());
+    output.extend_from_slice(&html[..position]);
+    output.extend_from_slice(fragment.as_bytes());
+    output.extend_from_slice(&html[position..]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_inject_script() {
+        let html = inject_script(b"<html><body><p>Hi</p></BODY></html>");
+        let html = String::from_utf8(html).unwrap();
+
+        assert!(html.starts_with("<html><body><p>Hi</p><script>"));
+        assert!(html.ends_with("</script>\n</BODY></html>"));
+        assert!(html.contains(ENDPOINT));
+
+        let fragment = inject_script(b"<p>No body</p>");
+        assert!(fragment.starts_with(b"<p>No body</p><script>"));
+    }
+
+    #[test]
+    fn test_event_for_changes() {
+        let css = vec![PathBuf::from("themes/default/static/css/main.css")];
+        assert_eq!(ReloadEvent::for_changes(&css), ReloadEvent::Css);
+
+        let mixed = vec![
+            PathBuf::from("static/site.css"),
+            PathBuf::from("content/posts/dev/hello.md"),
+        ];
+        assert_eq!(ReloadEvent::for_changes(&mixed), ReloadEvent::Reload);
+        assert_eq!(ReloadEvent::for_changes(&[]), ReloadEvent::Reload);
+    }
+
+    #[test]
+    fn test_notify_drops_closed_clients() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let live_reload = LiveReload::new();
+
+        let mut browser = TcpStream::connect(addr).unwrap();
+        live_reload.subscribe(listener.accept().unwrap().0);
+        let closed = TcpStream::connect(addr).unwrap();
+        live_reload.subscribe(listener.accept().unwrap().0);
+        drop(closed);
+        assert_eq!(live_reload.client_count(), 2);
+
+        // A closed peer only fails once the first write has been reset
+        live_reload.notify(ReloadEvent::Css);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        live_reload.notify(ReloadEvent::Reload);
+        assert_eq!(live_reload.client_count(), 1);
+
+        browser
+            .set_read_timeout(Some(std::time::Duration::from_secs(1)))
+            .unwrap();
+        let mut received = String::new();
+        let mut buffer = [0; 1024];
+        while !received.contains("event: reload") {
+            let n = browser.read(&mut buffer).unwrap();
+            received.push_str(&String::from_utf8_lossy(&buffer[..n]));
+        }
+        assert!(received.starts_with("HTTP/1.1 200 OK\r\nContent-Type: text/event-stream"));
+        assert!(received.contains("event: css\ndata: {}\n\n"));
+    }
+
+    #[test]
+    fn test_subscribe_bounds_writes() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let _browser = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let stream = listener.accept().unwrap().0;
+        let socket = stream.try_clone().unwrap();
+
+        LiveReload::new().subscribe(stream);
+        assert_eq!(socket.write_timeout().unwrap(), Some(WRITE_TIMEOUT));
+    }
+}
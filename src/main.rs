@@ -1,46 +1,15 @@
-mod cache;
-mod category;
-mod config;
-mod feeds;
-mod generator;
-mod indices;
-mod metadata;
-mod navigation;
-mod parallel;
-mod parser;
-mod plugin;
-mod plugins;
-mod renderer;
-mod search;
-mod shortcodes;
-mod slug;
-mod theme;
-mod types;
-
 use anyhow::Result;
 use clap::{Parser as ClapParser, Subcommand};
-use std::path::{Path, PathBuf};
-use std::sync::{mpsc, Arc, Mutex};
-use walkdir::WalkDir;
-
-use crate::cache::{hash_directory, hash_file, BuildCache};
-use crate::category::{discover_categories, validate_category};
-use crate::config::load_config;
-use crate::feeds::FeedGenerator;
-use crate::generator::Generator;
-use crate::indices::IndexGenerator;
-use crate::metadata::MetadataCache;
-use crate::navigation::build_post_navigation;
-use crate::parallel::{
-    get_thread_count, BuildProgress, BuildResult, SkipReason, WorkQueue, WorkerPool,
-};
-use crate::parser::Parser;
-use crate::plugin::{PluginContext, PluginManager};
-use crate::plugins::RelatedPostsPlugin;
-use crate::renderer::Renderer;
-use crate::search::SearchIndexGenerator;
-use crate::shortcodes::ShortcodeRegistry;
-use serde_json::json;
+use std::path::Path;
+
+use ssdocs::category::{discover_categories, validate_category};
+use ssdocs::config::load_config;
+use ssdocs::livereload::{LiveReload, ReloadEvent};
+use ssdocs::parallel::get_thread_count;
+use ssdocs::search::query::SearchEngine;
+use ssdocs::server::DevServer;
+use ssdocs::watch::{self, ChangeSet};
+use ssdocs::{BuildOptions, BuildReport, Site};
 
 #[derive(ClapParser)]
 #[command(name = "ssg")]
@@ -66,13 +35,43 @@ enum Commands {
         /// Use parallel processing for faster builds
         #[arg(long, default_value_t = true, action = clap::ArgAction::Set)]
         parallel: bool,
+
+        /// Include scheduled and expired posts
+        #[arg(long)]
+        future: bool,
+
+        /// Include draft posts and pages
+        #[arg(long)]
+        drafts: bool,
     },
 
     /// Watch for changes and rebuild
     Watch {
-        /// Port for dev server
-        #[arg(short, long, default_value = "8080")]
-        port: u16,
+        /// Port for dev server (default: `server.port`, 8080)
+        #[arg(short, long)]
+        port: Option<u16>,
+
+        /// Address for dev server to listen on (default: `server.host`, 127.0.0.1)
+        #[arg(long)]
+        host: Option<String>,
+
+        /// Include scheduled and expired posts, marked with a banner
+        #[arg(long)]
+        future: bool,
+
+        /// Include draft posts and pages, marked with a banner
+        #[arg(long)]
+        drafts: bool,
+    },
+
+    /// Query the search index of the last build
+    Search {
+        /// Search query
+        query: String,
+
+        /// Maximum number of results
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
     },
 
     /// Create a new post
@@ -93,23 +92,34 @@ fn main() -> Result<()> {
             incremental,
             post,
             parallel,
+            future,
+            drafts,
         } => {
+            let options = BuildOptions {
+                incremental,
+                parallel,
+                future,
+                drafts,
+            };
             if let Some(post_path) = post {
-                build_single_post(&post_path)?;
-            } else if parallel {
+                build_single_post(&post_path, options)?;
+            } else {
                 if incremental {
                     println!("Note: Incremental build uses cache to skip unchanged files");
                 }
-                build_all_parallel(incremental)?;
-            } else if incremental {
-                println!("Note: Incremental build uses cache to skip unchanged files");
-                build_all(true)?;
-            } else {
-                build_all(false)?;
+                build_site(options)?;
             }
         }
-        Commands::Watch { port } => {
-            watch_mode(port)?;
+        Commands::Watch {
+            port,
+            host,
+            future,
+            drafts,
+        } => {
+            watch_mode(host, port, future, drafts)?;
+        }
+        Commands::Search { query, limit } => {
+            search_posts(&query, limit)?;
         }
         Commands::New { category, title } => {
             create_new_post(&category, &title)?;
@@ -119,637 +129,82 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn build_all(use_cache: bool) -> Result<()> {
-    println!("Building site...\n");
-
-    let config = load_config()?;
-    let renderer = Renderer::new();
-    let mut shortcode_registry = ShortcodeRegistry::new();
-    let generator = Generator::new(config.clone())?;
-    let mut cache = if use_cache {
-        BuildCache::load()?
-    } else {
-        BuildCache::new()
-    };
-    let mut metadata = if use_cache {
-        MetadataCache::load().unwrap_or_else(|_| MetadataCache::new())
+fn build_site(options: BuildOptions) -> Result<()> {
+    if options.parallel {
+        println!("Building site with {} threads...\n", get_thread_count());
     } else {
-        MetadataCache::new()
-    };
-
-    let mut plugin_manager = PluginManager::new();
-    plugin_manager.register(Box::new(RelatedPostsPlugin::new()));
-    plugin_manager.init_all(&config)?;
-
-    plugin_manager.register_shortcodes(&mut shortcode_registry);
-
-    println!(
-        "🔌 Loaded plugins: {}",
-        plugin_manager.list_plugins().join(", ")
-    );
-
-    let posts_dir = Path::new(&config.build.content_dir);
-
-    if !posts_dir.exists() {
-        anyhow::bail!(
-            "Content directory '{}' does not exist. Create it first with: mkdir -p {}",
-            config.build.content_dir,
-            config.build.content_dir
-        );
-    }
-
-    let template_hash = hash_directory(Path::new(&format!("themes/{}", config.theme.name)))?;
-
-    let categories = discover_categories(posts_dir)?;
-    if categories.is_empty() {
-        eprintln!("⚠️  Warning: No categories found in content directory");
-        eprintln!("   Create a category by adding a subdirectory with markdown files:");
-        eprintln!("   mkdir -p {}/dev", config.build.content_dir);
-    }
-    metadata.set_category_info(categories);
-
-    let mut built_count = 0;
-    let mut skipped_count = 0;
-
-    for entry in WalkDir::new(posts_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map_or(false, |ext| ext == "md"))
-    {
-        let path = entry.path();
-        let file_hash = hash_file(path)?;
-
-        if use_cache && !cache.needs_rebuild(path, &file_hash, &template_hash) {
-            println!("⏭  Skipping (unchanged): {}", path.display());
-            skipped_count += 1;
-            continue;
-        }
-
-        println!("🔨 Building: {}", path.display());
-
-        let mut post = Parser::parse_file(path)?;
-
-        if post.frontmatter.draft {
-            println!("   ⚠  Draft - skipping output");
-            skipped_count += 1;
-            continue;
-        }
-
-        let plugin_ctx = PluginContext {
-            config: &config,
-            metadata: &metadata,
-        };
-
-        plugin_manager.on_post_parsed(&mut post, &plugin_ctx)?;
-
-        let processed_content = shortcode_registry.process(&post.content)?;
-
-        let base_path = format!("{}", post.category);
-        let mut html = renderer.render_markdown_with_components(
-            &processed_content,
-            generator.get_tera(),
-            &base_path,
-        )?;
-
-        plugin_manager.on_post_rendered(&mut post, &mut html, &plugin_ctx)?;
-
-        post.rendered_html = Some(html);
-
-        let mut plugin_data = plugin_manager.template_context_post(&post, &plugin_ctx)?;
-
-        let navigation = build_post_navigation(&post.slug, &post.category, &metadata, false);
-        plugin_data.insert("prev_post".to_string(), json!(navigation.prev));
-        plugin_data.insert("next_post".to_string(), json!(navigation.next));
-
-        let output_path = generator.generate_post(&post, &plugin_data)?;
-
-        cache.update_entry(
-            path,
-            file_hash,
-            template_hash.clone(),
-            output_path.to_string_lossy().to_string(),
-        );
-
-        metadata.upsert_post(
-            post.slug.clone(),
-            post.category.clone(),
-            post.frontmatter.clone(),
-        );
-
-        built_count += 1;
-    }
-
-    if use_cache {
-        cache.save()?;
-    }
-    metadata.save()?;
-
-    let pages_dir = Path::new("content/pages");
-    if pages_dir.exists() {
-        println!("\n📄 Building pages...");
-        let mut pages_built = 0;
-
-        for entry in WalkDir::new(pages_dir)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().map_or(false, |ext| ext == "md"))
-        {
-            let path = entry.path();
-            println!("🔨 Building page: {}", path.display());
-
-            let mut page = Parser::parse_page_file(path)?;
-
-            if page.frontmatter.draft {
-                println!("   ⚠  Draft - skipping output");
-                continue;
-            }
-
-            let processed_content = shortcode_registry.process(&page.content)?;
-
-            let html = renderer.render_markdown_with_components(
-                &processed_content,
-                generator.get_tera(),
-                &page.slug,
-            )?;
-            page.rendered_html = Some(html);
-
-            let plugin_ctx = PluginContext {
-                config: &config,
-                metadata: &metadata,
-            };
-            let plugin_data = plugin_manager.template_context_page(&page, &plugin_ctx)?;
-
-            let output_path = generator.generate_page(&page, &plugin_data)?;
-            println!("   ✓ {}", output_path.display());
-
-            pages_built += 1;
-        }
-
-        if pages_built > 0 {
-            println!("✅ Built {} page(s)", pages_built);
-        }
-    }
-
-    let index_generator = IndexGenerator::new(config.clone())?;
-    index_generator.generate_all(&metadata, &plugin_manager)?;
-
-    println!("📄 Generating RSS feeds...");
-    FeedGenerator::generate_all_feeds(
-        &config,
-        &metadata,
-        posts_dir,
-        Path::new(&config.build.output_dir),
-    )?;
-
-    if config.build.search.enabled {
-        let search_generator = SearchIndexGenerator::new(config.clone());
-        search_generator.generate(&metadata)?;
+        println!("Building site...\n");
     }
 
-    println!("🎨 Generating syntax highlighting CSS...");
-    let css_dir = Path::new(&config.build.output_dir).join("css");
-    std::fs::create_dir_all(&css_dir)?;
-    renderer.write_syntax_css(css_dir.join("syntax.css"))?;
-
-    generator.copy_content_assets()?;
-    generator.copy_static_assets()?;
-
-    println!("\n✅ Build complete!");
-    println!("   Built: {}", built_count);
-    if use_cache {
-        println!("   Skipped: {}", skipped_count);
-    }
-    println!("   Categories: {}", metadata.get_categories().len());
-    println!("   Tags: {}", metadata.get_tags().len());
+    let mut site = init_site(options)?;
+    let report = site.build()?;
+    print_report(&report, options);
 
     Ok(())
 }
 
-fn build_all_parallel(use_cache: bool) -> Result<()> {
-    let start_time = std::time::Instant::now();
-    let num_threads = get_thread_count();
-    println!("Building site with {} threads...\n", num_threads);
+fn init_site(options: BuildOptions) -> Result<Site> {
+    let site = Site::builder(load_config()?)
+        .with_incremental(options.incremental)
+        .with_parallel(options.parallel)
+        .with_future(options.future)
+        .with_drafts(options.drafts)
+        .init()?;
 
-    let config = Arc::new(load_config()?);
-    let posts_dir = Path::new(&config.build.content_dir);
+    println!("🔌 Loaded plugins: {}", site.plugin_names().join(", "));
 
-    if !posts_dir.exists() {
-        anyhow::bail!(
-            "Content directory '{}' does not exist",
-            config.build.content_dir
-        );
-    }
-
-    let template_hash = Arc::new(hash_directory(Path::new(&format!(
-        "themes/{}",
-        config.theme.name
-    )))?);
-
-    let categories = discover_categories(posts_dir)?;
-    let mut metadata = if use_cache {
-        MetadataCache::load().unwrap_or_else(|_| MetadataCache::new())
-    } else {
-        MetadataCache::new()
-    };
-    metadata.set_category_info(categories);
-
-    let cache = Arc::new(Mutex::new(if use_cache {
-        BuildCache::load()?
-    } else {
-        BuildCache::new()
-    }));
-
-    let mut plugin_manager = PluginManager::new();
-    plugin_manager.register(Box::new(RelatedPostsPlugin::new()));
-    plugin_manager.init_all(&config)?;
-
-    let mut shortcode_registry = ShortcodeRegistry::new();
-    plugin_manager.register_shortcodes(&mut shortcode_registry);
-    let shortcode_registry = Arc::new(shortcode_registry);
+    Ok(site)
+}
 
+fn print_report(report: &BuildReport, options: BuildOptions) {
     println!(
-        "🔌 Loaded plugins: {}",
-        plugin_manager.list_plugins().join(", ")
+        "\n✅ Build complete in {:.2}s!",
+        report.elapsed.as_secs_f64()
     );
-
-    let file_paths: Vec<PathBuf> = WalkDir::new(posts_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().extension().map_or(false, |ext| ext == "md"))
-        .map(|e| e.path().to_path_buf())
-        .collect();
-
-    for path in &file_paths {
-        if let Ok(post) = Parser::parse_file(path) {
-            if !post.frontmatter.draft {
-                metadata.upsert_post(post.slug, post.category, post.frontmatter);
-            }
-        }
-    }
-
-    let metadata_for_nav = Arc::new(metadata.clone());
-
-    let progress = Arc::new(BuildProgress::new());
-
-    // Set up work queue and results channel
-    let work_queue = WorkQueue::new();
-    let work_rx = work_queue.get_receiver();
-    let (result_tx, result_rx) = mpsc::channel();
-
-    // Send all work to queue
-    for path in file_paths {
-        work_queue.send(path)?;
-    }
-    work_queue.close();
-
-    let mut pool = WorkerPool::new();
-
-    for _ in 0..num_threads {
-        let work_rx = Arc::clone(&work_rx);
-        let result_tx = result_tx.clone();
-        let config = Arc::clone(&config);
-        let cache = Arc::clone(&cache);
-        let template_hash = Arc::clone(&template_hash);
-        let shortcode_registry = Arc::clone(&shortcode_registry);
-        let progress = Arc::clone(&progress);
-        let metadata_for_nav = Arc::clone(&metadata_for_nav);
-
-        pool.spawn(move || {
-            let renderer = Renderer::new();
-            let generator = match Generator::new((*config).clone()) {
-                Ok(g) => g,
-                Err(e) => {
-                    eprintln!("Failed to create generator: {}", e);
-                    return;
-                }
-            };
-
-            loop {
-                let path = {
-                    let rx = work_rx.lock().unwrap();
-                    rx.recv().ok()
-                };
-
-                let path = match path {
-                    Some(p) => p,
-                    None => break,
-                };
-
-                let result = process_post_parallel(
-                    &path,
-                    &renderer,
-                    &generator,
-                    &shortcode_registry,
-                    &config,
-                    &cache,
-                    &template_hash,
-                    &metadata_for_nav,
-                    use_cache,
-                );
-
-                match &result {
-                    BuildResult::Success { .. } => progress.increment_built(),
-                    BuildResult::Skipped { .. } => progress.increment_skipped(),
-                    BuildResult::Error { .. } => {}
-                }
-
-                let _ = result_tx.send(result);
-            }
-        });
+    println!("   Built: {}", report.built);
+    if options.incremental {
+        println!("   Skipped: {}", report.skipped);
     }
-
-    drop(result_tx);
-
-    let mut results = Vec::new();
-    for result in result_rx {
-        results.push(result);
+    if report.pages > 0 {
+        println!("   Pages: {}", report.pages);
     }
-
-    pool.join().map_err(|e| anyhow::anyhow!(e))?;
-
-    let mut errors = Vec::new();
-    for result in results {
-        match result {
-            BuildResult::Success {
-                path,
-                slug,
-                category,
-                frontmatter,
-                file_hash,
-                template_hash,
-                output_path,
-            } => {
-                println!("🔨 Built: {}", path.display());
-                metadata.upsert_post(slug, category, frontmatter);
-                cache
-                    .lock()
-                    .unwrap()
-                    .update_entry(&path, file_hash, template_hash, output_path);
-            }
-            BuildResult::Skipped { path, reason } => match reason {
-                SkipReason::Cached => println!("⏭  Skipped (unchanged): {}", path.display()),
-                SkipReason::Draft => println!("   ⚠  Draft - skipping: {}", path.display()),
-            },
-            BuildResult::Error { path, error } => {
-                eprintln!("❌ Error building {}: {}", path.display(), error);
-                errors.push((path, error));
-            }
-        }
-    }
-
-    if !errors.is_empty() {
-        anyhow::bail!("{} posts failed to build", errors.len());
-    }
-
-    if use_cache {
-        cache.lock().unwrap().save()?;
-    }
-    metadata.save()?;
-
-    // Build pages (sequential)
-    let pages_dir = Path::new("content/pages");
-    if pages_dir.exists() {
-        println!("\n📄 Building pages...");
-        let renderer = Renderer::new();
-        let generator = Generator::new((*config).clone())?;
-        let mut pages_built = 0;
-
-        for entry in WalkDir::new(pages_dir)
-            .into_iter()
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().map_or(false, |ext| ext == "md"))
-        {
-            let path = entry.path();
-            println!("🔨 Building page: {}", path.display());
-
-            let mut page = Parser::parse_page_file(path)?;
-
-            if page.frontmatter.draft {
-                println!("   ⚠  Draft - skipping output");
-                continue;
-            }
-
-            let processed_content = shortcode_registry.process(&page.content)?;
-            let html = renderer.render_markdown_with_components(
-                &processed_content,
-                generator.get_tera(),
-                &page.slug,
-            )?;
-            page.rendered_html = Some(html);
-
-            let plugin_ctx = PluginContext {
-                config: &config,
-                metadata: &metadata,
-            };
-            let plugin_data = plugin_manager.template_context_page(&page, &plugin_ctx)?;
-
-            let output_path = generator.generate_page(&page, &plugin_data)?;
-            println!("   ✓ {}", output_path.display());
-
-            pages_built += 1;
-        }
-
-        if pages_built > 0 {
-            println!("✅ Built {} page(s)", pages_built);
-        }
-    }
-
-    let index_generator = IndexGenerator::new((*config).clone())?;
-    index_generator.generate_all(&metadata, &plugin_manager)?;
-
-    println!("📄 Generating RSS feeds...");
-    FeedGenerator::generate_all_feeds(
-        &config,
-        &metadata,
-        posts_dir,
-        Path::new(&config.build.output_dir),
-    )?;
-
-    if config.build.search.enabled {
-        let search_generator = SearchIndexGenerator::new((*config).clone());
-        search_generator.generate(&metadata)?;
-    }
-
-    println!("🎨 Generating syntax highlighting CSS...");
-    let renderer = Renderer::new();
-    let generator = Generator::new((*config).clone())?;
-    let css_dir = Path::new(&config.build.output_dir).join("css");
-    std::fs::create_dir_all(&css_dir)?;
-    renderer.write_syntax_css(css_dir.join("syntax.css"))?;
-
-    generator.copy_content_assets()?;
-    generator.copy_static_assets()?;
-
-    let elapsed = start_time.elapsed();
-    println!("\n✅ Build complete in {:.2}s!", elapsed.as_secs_f64());
-    println!("   Built: {}", progress.get_built());
-    if use_cache {
-        println!("   Skipped: {}", progress.get_skipped());
-    }
-    println!("   Categories: {}", metadata.get_categories().len());
-    println!("   Tags: {}", metadata.get_tags().len());
-
-    Ok(())
+    println!("   Categories: {}", report.categories);
+    println!("   Tags: {}", report.tags);
 }
 
-fn process_post_parallel(
-    path: &Path,
-    renderer: &Renderer,
-    generator: &Generator,
-    shortcode_registry: &ShortcodeRegistry,
-    _config: &crate::config::SsgConfig,
-    cache: &Arc<Mutex<BuildCache>>,
-    template_hash: &str,
-    metadata: &MetadataCache,
-    use_cache: bool,
-) -> BuildResult {
-    let file_hash = match hash_file(path) {
-        Ok(h) => h,
-        Err(e) => {
-            return BuildResult::Error {
-                path: path.to_path_buf(),
-                error: e.to_string(),
-            }
-        }
-    };
-
-    if use_cache {
-        let cache = cache.lock().unwrap();
-        if !cache.needs_rebuild(path, &file_hash, template_hash) {
-            return BuildResult::Skipped {
-                path: path.to_path_buf(),
-                reason: SkipReason::Cached,
-            };
-        }
-    }
-
-    let mut post = match Parser::parse_file(path) {
-        Ok(p) => p,
-        Err(e) => {
-            return BuildResult::Error {
-                path: path.to_path_buf(),
-                error: e.to_string(),
-            }
-        }
-    };
-
-    if post.frontmatter.draft {
-        return BuildResult::Skipped {
-            path: path.to_path_buf(),
-            reason: SkipReason::Draft,
-        };
-    }
-
-    let processed_content = match shortcode_registry.process(&post.content) {
-        Ok(c) => c,
-        Err(e) => {
-            return BuildResult::Error {
-                path: path.to_path_buf(),
-                error: e.to_string(),
-            }
-        }
-    };
-
-    let base_path = post.category.clone();
-    let html = match renderer.render_markdown_with_components(
-        &processed_content,
-        generator.get_tera(),
-        &base_path,
-    ) {
-        Ok(h) => h,
-        Err(e) => {
-            return BuildResult::Error {
-                path: path.to_path_buf(),
-                error: e.to_string(),
-            }
-        }
-    };
-
-    post.rendered_html = Some(html);
+fn build_single_post(post_path: &str, options: BuildOptions) -> Result<()> {
+    println!("Building single post: {}\n", post_path);
 
-    let mut plugin_data = std::collections::HashMap::new();
-    let navigation = build_post_navigation(&post.slug, &post.category, metadata, false);
-    plugin_data.insert("prev_post".to_string(), json!(navigation.prev));
-    plugin_data.insert("next_post".to_string(), json!(navigation.next));
+    let mut site = Site::builder(load_config()?)
+        .with_future(options.future)
+        .with_drafts(options.drafts)
+        .init()?;
+    let output_path = site.build_post(Path::new(post_path))?;
 
-    let output_path = match generator.generate_post(&post, &plugin_data) {
-        Ok(p) => p,
-        Err(e) => {
-            return BuildResult::Error {
-                path: path.to_path_buf(),
-                error: e.to_string(),
-            }
-        }
-    };
+    println!("\n✅ Built: {}", output_path.display());
 
-    BuildResult::Success {
-        path: path.to_path_buf(),
-        slug: post.slug,
-        category: post.category,
-        frontmatter: post.frontmatter,
-        file_hash,
-        template_hash: template_hash.to_string(),
-        output_path: output_path.to_string_lossy().to_string(),
-    }
+    Ok(())
 }
 
-fn build_single_post(post_path: &str) -> Result<()> {
-    println!("Building single post: {}\n", post_path);
-
+fn search_posts(query: &str, limit: usize) -> Result<()> {
     let config = load_config()?;
-    let renderer = Renderer::new();
-    let mut shortcode_registry = ShortcodeRegistry::new();
-    let generator = Generator::new(config.clone())?;
-    let metadata = MetadataCache::load().unwrap_or_else(|_| MetadataCache::new());
-
-    let mut plugin_manager = PluginManager::new();
-    plugin_manager.register(Box::new(RelatedPostsPlugin::new()));
-    plugin_manager.init_all(&config)?;
-
-    plugin_manager.register_shortcodes(&mut shortcode_registry);
-
-    let path = Path::new(post_path);
+    let engine = SearchEngine::load(Path::new(&config.build.output_dir))?
+        .with_boosts(config.build.search.boosts);
+    let results = engine.search(query, limit);
 
-    if !path.exists() {
-        anyhow::bail!("Post file not found: {}", post_path);
+    if results.is_empty() {
+        println!("No results for \"{}\"", query);
+        return Ok(());
     }
 
-    let mut post = Parser::parse_file(path)?;
-
-    if post.frontmatter.draft {
-        println!("⚠  This is a draft post");
+    for (i, result) in results.iter().enumerate() {
+        println!("{}. {} ({:.3})", i + 1, result.title, result.score);
+        println!("   {}", result.url);
+        if let Some(snippet) = &result.snippet {
+            println!("   {}", snippet);
+        }
     }
 
-    let plugin_ctx = PluginContext {
-        config: &config,
-        metadata: &metadata,
-    };
-
-    plugin_manager.on_post_parsed(&mut post, &plugin_ctx)?;
-
-    let processed_content = shortcode_registry.process(&post.content)?;
-
-    let base_path = format!("{}", post.category);
-    let mut html = renderer.render_markdown_with_components(
-        &processed_content,
-        generator.get_tera(),
-        &base_path,
-    )?;
-
-    plugin_manager.on_post_rendered(&mut post, &mut html, &plugin_ctx)?;
-
-    post.rendered_html = Some(html);
-
-    let mut plugin_data = plugin_manager.template_context_post(&post, &plugin_ctx)?;
-
-    let navigation = build_post_navigation(&post.slug, &post.category, &metadata, false);
-    plugin_data.insert("prev_post".to_string(), json!(navigation.prev));
-    plugin_data.insert("next_post".to_string(), json!(navigation.next));
-
-    let output_path = generator.generate_post(&post, &plugin_data)?;
-
-    println!("\n✅ Built: {}", output_path.display());
-
     Ok(())
 }
 
@@ -816,10 +271,11 @@ fn create_new_post(category: &str, title: &str) -> Result<()> {
         .filter(|c| c.is_alphanumeric() || *c == '-')
         .collect::<String>();
 
-    let filename = format!("content/posts/{}/{}.md", category, slug);
+    let category_dir = posts_dir.join(category);
+    let filename = category_dir.join(format!("{}.md", slug));
 
-    if Path::new(&filename).exists() {
-        anyhow::bail!("Post already exists: {}", filename);
+    if filename.exists() {
+        anyhow::bail!("Post already exists: {}", filename.display());
     }
 
     let content = format!(
@@ -838,10 +294,10 @@ Write your post here...
         category
     );
 
-    std::fs::create_dir_all(format!("content/posts/{}", category))?;
+    std::fs::create_dir_all(&category_dir)?;
     std::fs::write(&filename, content)?;
 
-    println!("✅ Created: {}", filename);
+    println!("✅ Created: {}", filename.display());
     println!("   Title: {}", title);
     println!("   Category: {}", category);
     println!("   Slug: {}", slug);
@@ -849,26 +305,81 @@ Write your post here...
     Ok(())
 }
 
-fn watch_mode(port: u16) -> Result<()> {
+/// How long events must stop arriving before watch mode rebuilds
+const DEBOUNCE_QUIET: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Rebuild anyway once events have kept arriving for this long
+const DEBOUNCE_LIMIT: std::time::Duration = std::time::Duration::from_millis(500);
+
+fn watch_mode(host: Option<String>, port: Option<u16>, future: bool, drafts: bool) -> Result<()> {
+    use anyhow::Context as _;
     use notify::{Event, RecursiveMode, Result as NotifyResult, Watcher};
+    use std::net::TcpListener;
     use std::sync::mpsc::channel;
     use std::time::Duration;
 
+    // Watch mode rebuilds only what changed
+    let watch_build = BuildOptions {
+        incremental: true,
+        parallel: true,
+        future,
+        drafts,
+    };
+
     println!("🔍 Watch mode starting...");
-    println!("   Watching for changes in:");
-    println!("   - content/");
-    println!("   - themes/");
-    println!("   - static/");
-    println!("\n   Serving on http://localhost:{}", port);
-    println!("   Press Ctrl+C to stop\n");
+    if future {
+        println!("   Including scheduled and expired posts");
+    }
+    if drafts {
+        println!("   Including drafts");
+    }
 
-    println!("📦 Initial build...");
-    build_all(true)?;
+    println!("\n📦 Initial build...");
+    if watch_build.parallel {
+        println!("Building site with {} threads...\n", get_thread_count());
+    }
+    // Kept between rebuilds, so an edit only rebuilds what it affects. Watch
+    // mode starts even when the site doesn't build, and waits for a fix
+    let (mut site, initial) = match init_site(watch_build) {
+        Ok(mut site) => {
+            let result = site.build();
+            (Some(site), result)
+        }
+        Err(e) => (None, Err(e)),
+    };
+    match &initial {
+        Ok(report) => print_report(report, watch_build),
+        Err(e) => eprintln!("❌ Build error: {:#}", e),
+    }
     println!();
 
+    // Until config.yaml loads, paths and the server follow the defaults
+    let default_config = ssdocs::SsgConfig::default();
+    let config = site.as_ref().map_or(&default_config, |site| site.config());
+
+    let server_config = &config.server;
+    let host = host.unwrap_or_else(|| server_config.host.clone());
+    let port = port.unwrap_or(server_config.port);
+    let listener = TcpListener::bind((host.as_str(), port))
+        .with_context(|| format!("Failed to bind dev server to {}:{}", host, port))?;
+    println!(
+        "🌐 Dev server listening on http://{}",
+        listener.local_addr()?
+    );
+    println!("   Press Ctrl+C to stop\n");
+
+    // Browsers reload through the dev server once a rebuild succeeds
+    let live_reload = LiveReload::new();
+    let server = DevServer::new(config).with_live_reload(live_reload.clone());
+    // The last good build stays online, under an overlay showing the error
+    if let Err(e) = &initial {
+        server.set_error(Some(e));
+    }
+
     // Start file server in background thread
+    let background_server = server.clone();
     let server_thread = std::thread::spawn(move || {
-        if let Err(e) = start_dev_server(port) {
+        if let Err(e) = background_server.serve(listener) {
             eprintln!("Dev server error: {}", e);
         }
     });
@@ -878,28 +389,72 @@ fn watch_mode(port: u16) -> Result<()> {
 
     let mut watcher = notify::recommended_watcher(move |res: NotifyResult<Event>| {
         if let Ok(event) = res {
-            tx.send(event).unwrap();
+            let _ = tx.send(event);
         }
     })?;
 
-    watcher.watch(Path::new("content"), RecursiveMode::Recursive)?;
-    watcher.watch(Path::new("themes"), RecursiveMode::Recursive)?;
-
-    if Path::new("static").exists() {
-        watcher.watch(Path::new("static"), RecursiveMode::Recursive)?;
+    // config.yaml is often replaced rather than written, so watch the directory holding it
+    watcher.watch(Path::new("."), RecursiveMode::NonRecursive)?;
+    let mut watched = Vec::new();
+    follow_dirs(&mut watcher, &mut watched, config)?;
+    println!("   Watching for changes in:");
+    for dir in &watched {
+        println!("   - {}/", dir.display());
     }
+    println!("   - config.yaml\n");
 
     loop {
         match rx.recv_timeout(Duration::from_secs(1)) {
             Ok(event) => {
-                if !should_rebuild(&event) {
+                // Editors save in several steps, and events pile up during a rebuild
+                let events = watch::debounce(&rx, event, DEBOUNCE_QUIET, DEBOUNCE_LIMIT);
+                let config = site.as_ref().map_or(&default_config, |site| site.config());
+                let changes = collect_changes(&events, config);
+                if changes.is_empty() {
                     continue;
                 }
 
-                println!("📝 File changed, rebuilding...");
-                match build_all(true) {
-                    Ok(_) => println!("✅ Rebuild complete!\n"),
-                    Err(e) => eprintln!("❌ Build error: {}\n", e),
+                println!("📝 {} file(s) changed, rebuilding...", changes.paths.len());
+                let result = match site.as_mut().filter(|_| !changes.config) {
+                    Some(site) => site.rebuild(&changes),
+                    // Plugins and the theme may be configured differently now.
+                    // A config that doesn't load keeps the previous site.
+                    None => init_site(watch_build).and_then(|mut new_site| {
+                        let result = new_site.build();
+                        server.update(new_site.config());
+                        site = Some(new_site);
+                        result
+                    }),
+                };
+
+                // The search index may have been rewritten, whether or not the build succeeded
+                server.refresh_search();
+
+                // The configured source directories may have moved
+                if changes.config {
+                    let config = site.as_ref().map_or(&default_config, |site| site.config());
+                    if let Err(e) = follow_dirs(&mut watcher, &mut watched, config) {
+                        eprintln!("⚠️  {:#}", e);
+                    }
+                }
+
+                match result {
+                    Ok(report) => {
+                        // Swapping stylesheets would leave the overlay in place
+                        let event = if server.has_error() {
+                            ReloadEvent::Reload
+                        } else {
+                            ReloadEvent::for_changes(&changes.paths)
+                        };
+                        server.set_error(None);
+                        live_reload.notify(event);
+                        println!("✅ Rebuild complete in {}ms!\n", report.elapsed.as_millis());
+                    }
+                    Err(e) => {
+                        eprintln!("❌ Build error: {:#}\n", e);
+                        server.set_error(Some(&e));
+                        live_reload.notify(ReloadEvent::Reload);
+                    }
                 }
             }
             Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
@@ -915,112 +470,54 @@ fn watch_mode(port: u16) -> Result<()> {
     }
 }
 
-fn should_rebuild(event: &notify::Event) -> bool {
-    use notify::EventKind;
-
-    match event.kind {
-        EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) => {
-            for path in &event.paths {
-                let path_str = path.to_string_lossy();
-                if path_str.contains(".build-cache") || path_str.contains("dist/") {
-                    return false;
-                }
-            }
-            true
-        }
-        _ => false,
-    }
-}
-
-fn start_dev_server(port: u16) -> Result<()> {
+/// Point the watcher at the source directories `config` names, dropping ones
+/// it no longer does
+fn follow_dirs(
+    watcher: &mut impl notify::Watcher,
+    watched: &mut Vec<std::path::PathBuf>,
+    config: &ssdocs::SsgConfig,
+) -> Result<()> {
     use anyhow::Context as _;
-    use std::io::Read;
-    use std::net::TcpListener;
-
-    let listener =
-        TcpListener::bind(format!("127.0.0.1:{}", port)).context("Failed to bind dev server")?;
 
-    println!("🌐 Dev server listening on http://localhost:{}", port);
-
-    for stream in listener.incoming() {
-        let mut stream = match stream {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("Connection error: {}", e);
-                continue;
-            }
-        };
-
-        let mut buffer = [0; 1024];
-        if stream.read(&mut buffer).is_err() {
-            continue;
+    let dirs = watch::watched_dirs(config);
+    for dir in watched.iter().filter(|dir| !dirs.contains(dir)) {
+        // Gone directories are no longer watched anyway
+        let _ = watcher.unwatch(dir);
+    }
+    watched.retain(|dir| dirs.contains(dir));
+
+    for dir in dirs {
+        if !watched.contains(&dir) {
+            watcher
+                .watch(&dir, notify::RecursiveMode::Recursive)
+                .with_context(|| format!("Failed to watch {}", dir.display()))?;
+            watched.push(dir);
         }
-
-        let request = String::from_utf8_lossy(&buffer);
-        let request_line = request.lines().next().unwrap_or("");
-
-        let path = if let Some(path_part) = request_line.split_whitespace().nth(1) {
-            slug::decode_from_url(path_part)
-        } else {
-            "/".to_string()
-        };
-
-        serve_file(&mut stream, &path);
     }
 
     Ok(())
 }
 
-fn serve_file(stream: &mut std::net::TcpStream, path: &str) {
-    use std::io::Write;
+/// Classify every path a burst of events touched, relative to the site root
+fn collect_changes(events: &[notify::Event], config: &ssdocs::SsgConfig) -> ChangeSet {
+    use notify::event::ModifyKind;
+    use notify::EventKind;
 
-    let file_path = if path == "/" {
-        "dist/index.html".to_string()
-    } else if path.ends_with('/') {
-        format!("dist{}index.html", path)
-    } else {
-        format!("dist{}", path)
-    };
+    let root = std::env::current_dir().unwrap_or_default();
+    let mut changes = ChangeSet::new();
 
-    let (status, content_type, body) = if let Ok(contents) = std::fs::read(&file_path) {
-        let content_type = get_content_type(&file_path);
-        ("200 OK", content_type, contents)
-    } else {
-        let index_path = format!("{}/index.html", file_path);
-        if let Ok(contents) = std::fs::read(&index_path) {
-            ("200 OK", "text/html", contents)
-        } else {
-            let body = b"404 Not Found".to_vec();
-            ("404 NOT FOUND", "text/plain", body)
+    for event in events {
+        match event.kind {
+            EventKind::Modify(ModifyKind::Metadata(_)) => continue,
+            EventKind::Modify(_) | EventKind::Create(_) | EventKind::Remove(_) => {}
+            _ => continue,
+        }
+        for path in &event.paths {
+            let path = path.strip_prefix(&root).unwrap_or(path);
+            let path = path.strip_prefix(".").unwrap_or(path);
+            changes.add(path, config);
         }
-    };
-
-    let response = format!(
-        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n",
-        status,
-        content_type,
-        body.len()
-    );
-
-    let _ = stream.write_all(response.as_bytes());
-    let _ = stream.write_all(&body);
-    let _ = stream.flush();
-}
-
-fn get_content_type(path: &str) -> &'static str {
-    if path.ends_with(".html") {
-        "text/html"
-    } else if path.ends_with(".css") {
-        "text/css"
-    } else if path.ends_with(".js") {
-        "application/javascript"
-    } else if path.ends_with(".png") {
-        "image/png"
-    } else if path.ends_with(".jpg") || path.ends_with(".jpeg") {
-        "image/jpeg"
-    } else if path.ends_with(".svg") {
-        "image/svg+xml"
-    } else {
-        "application/octet-stream"
     }
+
+    changes
 }
@@ -1,14 +1,32 @@
+use crate::excerpt::Excerpt;
+use crate::reading::ReadingStats;
+use crate::slug;
 use crate::types::{Category, Frontmatter};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PostMetadata {
     pub slug: String,
     pub category: String,
     pub frontmatter: Frontmatter,
+    /// Language code; `frontmatter.lang` or the main language
+    #[serde(default)]
+    pub lang: String,
+    /// Path of the post, e.g. `/en/dev/hello/`; main-language posts have no prefix
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub stats: ReadingStats,
+    /// Set once the post has been rendered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<Excerpt>,
+    /// Plain text of the rendered post, kept for full-text search
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,16 +35,23 @@ pub struct MetadataCache {
     pub posts: Vec<PostMetadata>,
     pub categories: HashMap<String, usize>,
     pub tags: HashMap<String, usize>,
+    /// Series name -> number of parts
+    #[serde(default)]
+    pub series: HashMap<String, usize>,
     #[serde(default)]
     pub category_info: Vec<Category>,
+    /// Posts without a `lang` are in this language and get no URL prefix
+    #[serde(default)]
+    pub default_language: String,
 }
 
 impl MetadataCache {
-    pub fn load() -> Result<Self> {
-        let cache_path = ".build-cache/metadata.json";
+    /// Load `metadata.json` from the cache directory, or start empty
+    pub fn load(cache_dir: &Path) -> Result<Self> {
+        let cache_path = cache_dir.join("metadata.json");
 
-        if std::path::Path::new(cache_path).exists() {
-            let content = fs::read_to_string(cache_path)?;
+        if cache_path.exists() {
+            let content = fs::read_to_string(&cache_path)?;
             Ok(serde_json::from_str(&content)?)
         } else {
             Ok(Self::new())
@@ -39,10 +64,64 @@ impl MetadataCache {
             posts: Vec::new(),
             categories: HashMap::new(),
             tags: HashMap::new(),
+            series: HashMap::new(),
             category_info: Vec::new(),
+            default_language: String::new(),
         }
     }
 
+    pub fn set_default_language(&mut self, code: &str) {
+        self.default_language = code.to_string();
+        for post in &mut self.posts {
+            post.lang = Self::resolve_lang(&post.frontmatter, code);
+            post.url = Self::post_url(&post.lang, code, &post.category, &post.slug);
+        }
+    }
+
+    /// URL prefix of a language: empty for the main language, `/<code>` otherwise
+    pub fn lang_prefix(&self, code: &str) -> String {
+        if code == self.default_language {
+            String::new()
+        } else {
+            format!("/{}", code)
+        }
+    }
+
+    /// The posts written in one language, as if they were the whole site.
+    /// Other languages only list the categories they have posts in.
+    pub fn for_language(&self, code: &str) -> MetadataCache {
+        let mut scoped = self.clone();
+        scoped.retain_posts(|p| p.lang == code);
+        if code != self.default_language {
+            let categories = scoped.categories.clone();
+            scoped
+                .category_info
+                .retain(|c| categories.contains_key(&c.slug));
+        }
+        scoped
+    }
+
+    fn resolve_lang(frontmatter: &Frontmatter, default_language: &str) -> String {
+        frontmatter
+            .lang
+            .clone()
+            .unwrap_or_else(|| default_language.to_string())
+    }
+
+    fn post_url(lang: &str, default_language: &str, category: &str, slug: &str) -> String {
+        let prefix = if lang == default_language {
+            String::new()
+        } else {
+            format!("/{}", lang)
+        };
+        format!(
+            "{}/{}/{}/",
+            prefix,
+            slug::encode_for_url(category),
+            slug::encode_for_url(slug)
+        )
+    }
+
     pub fn set_category_info(&mut self, categories: Vec<Category>) {
         self.category_info = categories;
     }
@@ -51,21 +130,92 @@ impl MetadataCache {
         &self.category_info
     }
 
-    pub fn upsert_post(&mut self, slug: String, category: String, frontmatter: Frontmatter) {
-        self.posts.retain(|p| p.slug != slug);
+    pub fn upsert_post(
+        &mut self,
+        slug: String,
+        category: String,
+        frontmatter: Frontmatter,
+        stats: ReadingStats,
+    ) {
+        self.posts
+            .retain(|p| !(p.slug == slug && p.category == category));
 
+        let lang = Self::resolve_lang(&frontmatter, &self.default_language);
+        let url = Self::post_url(&lang, &self.default_language, &category, &slug);
         self.posts.push(PostMetadata {
             slug,
             category,
             frontmatter,
+            lang,
+            url,
+            stats,
+            excerpt: None,
+            text: None,
         });
 
         self.recalculate_stats();
     }
 
+    /// Record what rendering a post produced: its excerpt and plain text
+    pub fn set_rendered(
+        &mut self,
+        category: &str,
+        slug: &str,
+        excerpt: Option<Excerpt>,
+        text: Option<String>,
+    ) {
+        if let Some(post) = self.find_post_mut(category, slug) {
+            post.excerpt = excerpt;
+            post.text = text;
+        }
+    }
+
+    /// Replace a post's frontmatter and stats, keeping its place and excerpt
+    pub fn refresh_post(
+        &mut self,
+        category: &str,
+        slug: &str,
+        frontmatter: Frontmatter,
+        stats: ReadingStats,
+    ) {
+        if let Some(post) = self.find_post_mut(category, slug) {
+            post.frontmatter = frontmatter;
+            post.stats = stats;
+        }
+        self.recalculate_stats();
+    }
+
+    fn find_post_mut(&mut self, category: &str, slug: &str) -> Option<&mut PostMetadata> {
+        self.posts
+            .iter_mut()
+            .find(|p| p.slug == slug && p.category == category)
+    }
+
+    /// Excerpts and plain text come from rendered HTML, so posts skipped by an
+    /// incremental build keep the ones from the previous build
+    pub fn inherit_rendered(&mut self, previous: &MetadataCache) {
+        for post in &mut self.posts {
+            if let Some(old) = previous
+                .posts
+                .iter()
+                .find(|p| p.slug == post.slug && p.category == post.category)
+            {
+                post.excerpt = old.excerpt.clone();
+                post.text = old.text.clone();
+            }
+        }
+    }
+
+    /// Keep only the posts `keep` accepts
+    pub fn retain_posts<F: FnMut(&PostMetadata) -> bool>(&mut self, keep: F) {
+        self.posts.retain(keep);
+        self.recalculate_stats();
+    }
+
     fn recalculate_stats(&mut self) {
         self.categories.clear();
         self.tags.clear();
+        self.series.clear();
 
         for post in &self.posts {
             *self.categories.entry(post.category.clone()).or_insert(0) += 1;
@@ -73,6 +223,10 @@ impl MetadataCache {
             for tag in &post.frontmatter.tags {
                 *self.tags.entry(tag.clone()).or_insert(0) += 1;
             }
+
+            if let Some(series) = &post.frontmatter.series {
+                *self.series.entry(series.clone()).or_insert(0) += 1;
+            }
         }
     }
 
@@ -108,10 +262,33 @@ impl MetadataCache {
         tags
     }
 
-    pub fn save(&self) -> Result<()> {
-        fs::create_dir_all(".build-cache")?;
+    pub fn get_series(&self) -> Vec<String> {
+        let mut series: Vec<_> = self.series.keys().cloned().collect();
+        series.sort();
+        series
+    }
+
+    /// Parts of a series in reading order: by `series_order`, then by date
+    pub fn get_series_posts(&self, series: &str) -> Vec<&PostMetadata> {
+        let mut posts: Vec<_> = self
+            .posts
+            .iter()
+            .filter(|p| p.frontmatter.series.as_deref() == Some(series))
+            .collect();
+        posts.sort_by(|a, b| {
+            let order = |p: &PostMetadata| p.frontmatter.series_order.unwrap_or(u32::MAX);
+            order(a)
+                .cmp(&order(b))
+                .then_with(|| a.frontmatter.date.cmp(&b.frontmatter.date))
+                .then_with(|| a.slug.cmp(&b.slug))
+        });
+        posts
+    }
+
+    pub fn save(&self, cache_dir: &Path) -> Result<()> {
+        fs::create_dir_all(cache_dir)?;
         let json = serde_json::to_string_pretty(self)?;
-        fs::write(".build-cache/metadata.json", json)?;
+        fs::write(cache_dir.join("metadata.json"), json)?;
         Ok(())
     }
 }
@@ -135,6 +312,11 @@ mod tests {
             featured_image: None,
             description: None,
             draft: false,
+            series: None,
+            series_order: None,
+            expires: None,
+            lang: None,
+            translation_key: None,
         };
         (category.to_string(), frontmatter)
     }
@@ -144,13 +326,47 @@ mod tests {
         let mut cache = MetadataCache::new();
 
         let (category, fm) = create_test_post("dev", vec!["rust", "webdev"]);
-        cache.upsert_post("test-post".to_string(), category, fm);
+        cache.upsert_post(
+            "test-post".to_string(),
+            category,
+            fm,
+            ReadingStats::default(),
+        );
 
         assert_eq!(cache.posts.len(), 1);
         assert_eq!(cache.categories.get("dev"), Some(&1));
         assert_eq!(cache.tags.get("rust"), Some(&1));
     }
 
+    #[test]
+    fn test_inherit_rendered() {
+        let excerpt = Excerpt {
+            html: "<p>Intro</p>".to_string(),
+            text: "Intro".to_string(),
+        };
+
+        let mut previous = MetadataCache::new();
+        let (category, fm) = create_test_post("dev", vec![]);
+        previous.upsert_post("post".to_string(), category, fm, ReadingStats::default());
+        previous.set_rendered(
+            "dev",
+            "post",
+            Some(excerpt.clone()),
+            Some("Intro and more".to_string()),
+        );
+
+        let mut cache = MetadataCache::new();
+        let (category, fm) = create_test_post("dev", vec!["rust"]);
+        cache.upsert_post("post".to_string(), category, fm, ReadingStats::default());
+        let (category, fm) = create_test_post("dev", vec![]);
+        cache.upsert_post("new".to_string(), category, fm, ReadingStats::default());
+        cache.inherit_rendered(&previous);
+
+        assert_eq!(cache.posts[0].excerpt, Some(excerpt));
+        assert_eq!(cache.posts[0].text.as_deref(), Some("Intro and more"));
+        assert_eq!(cache.posts[1].excerpt, None);
+    }
+
     #[test]
     fn test_get_posts_by_category() {
         let mut cache = MetadataCache::new();
@@ -159,9 +375,9 @@ mod tests {
         let (cat2, fm2) = create_test_post("chat", vec![]);
         let (cat3, fm3) = create_test_post("dev", vec![]);
 
-        cache.upsert_post("post1".to_string(), cat1, fm1);
-        cache.upsert_post("post2".to_string(), cat2, fm2);
-        cache.upsert_post("post3".to_string(), cat3, fm3);
+        cache.upsert_post("post1".to_string(), cat1, fm1, ReadingStats::default());
+        cache.upsert_post("post2".to_string(), cat2, fm2, ReadingStats::default());
+        cache.upsert_post("post3".to_string(), cat3, fm3, ReadingStats::default());
 
         let dev_posts = cache.get_posts_by_category("dev");
         assert_eq!(dev_posts.len(), 2);
@@ -175,11 +391,74 @@ mod tests {
         let (cat2, fm2) = create_test_post("dev", vec!["rust", "webdev"]);
         let (cat3, fm3) = create_test_post("chat", vec!["webdev"]);
 
-        cache.upsert_post("post1".to_string(), cat1, fm1);
-        cache.upsert_post("post2".to_string(), cat2, fm2);
-        cache.upsert_post("post3".to_string(), cat3, fm3);
+        cache.upsert_post("post1".to_string(), cat1, fm1, ReadingStats::default());
+        cache.upsert_post("post2".to_string(), cat2, fm2, ReadingStats::default());
+        cache.upsert_post("post3".to_string(), cat3, fm3, ReadingStats::default());
 
         let rust_posts = cache.get_posts_by_tag("rust");
         assert_eq!(rust_posts.len(), 2);
     }
+
+    #[test]
+    fn test_series_order() {
+        let mut cache = MetadataCache::new();
+        let parts = [
+            ("part-b", None),
+            ("part-2", Some(2)),
+            ("part-1", Some(1)),
+            ("part-a", None),
+        ];
+        for (day, (slug, order)) in parts.into_iter().enumerate() {
+            let (category, mut fm) = create_test_post("dev", vec![]);
+            fm.date = crate::types::PostDate::new(Utc::now() + chrono::Duration::days(day as i64));
+            fm.series = Some("Rust 101".to_string());
+            fm.series_order = order;
+            cache.upsert_post(slug.to_string(), category, fm, ReadingStats::default());
+        }
+        let (category, fm) = create_test_post("dev", vec![]);
+        cache.upsert_post(
+            "standalone".to_string(),
+            category,
+            fm,
+            ReadingStats::default(),
+        );
+
+        assert_eq!(cache.get_series(), vec!["Rust 101"]);
+        assert_eq!(cache.series.get("Rust 101"), Some(&4));
+        let slugs: Vec<_> = cache
+            .get_series_posts("Rust 101")
+            .iter()
+            .map(|p| p.slug.as_str())
+            .collect();
+        // Parts without series_order follow in date order
+        assert_eq!(slugs, vec!["part-1", "part-2", "part-b", "part-a"]);
+    }
+
+    #[test]
+    fn test_languages() {
+        let mut cache = MetadataCache::new();
+        cache.set_default_language("ko");
+        let category = |slug: &str| serde_yaml::from_str::<Category>(&format!("slug: {}", slug));
+        cache.set_category_info(vec![category("dev").unwrap(), category("chat").unwrap()]);
+
+        let (category, fm) = create_test_post("dev", vec![]);
+        cache.upsert_post("안녕".to_string(), category, fm, ReadingStats::default());
+        let (category, mut fm) = create_test_post("dev", vec![]);
+        fm.lang = Some("en".to_string());
+        cache.upsert_post("hello".to_string(), category, fm, ReadingStats::default());
+        let (category, fm) = create_test_post("chat", vec![]);
+        cache.upsert_post("잡담".to_string(), category, fm, ReadingStats::default());
+
+        assert_eq!(cache.posts[0].lang, "ko");
+        assert_eq!(cache.posts[0].url, "/dev/%EC%95%88%EB%85%95/");
+        assert_eq!(cache.posts[1].url, "/en/dev/hello/");
+        assert_eq!(cache.lang_prefix("en"), "/en");
+
+        let en = cache.for_language("en");
+        assert_eq!(en.posts.len(), 1);
+        assert_eq!(en.get_categories(), vec!["dev"]);
+        assert_eq!(en.get_category_info().len(), 1);
+        // The main language keeps categories without posts
+        assert_eq!(cache.for_language("ko").get_category_info().len(), 2);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::metadata::MetadataCache;
+use crate::metadata::{MetadataCache, PostMetadata};
 use crate::slug;
 use serde::Serialize;
 
@@ -16,6 +16,94 @@ pub struct PostLink {
     pub category: String,
 }
 
+impl PostLink {
+    fn new(post: &PostMetadata) -> Self {
+        Self {
+            slug: post.slug.clone(),
+            title: post.frontmatter.title.clone(),
+            url: post.url.clone(),
+            category: post.category.clone(),
+        }
+    }
+}
+
+/// A post's place in its series
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesNavigation {
+    pub name: String,
+    pub slug: String,
+    /// Overview page listing every part
+    pub url: String,
+    /// Every part in reading order
+    pub parts: Vec<SeriesPart>,
+    /// Index of the current post in `parts`
+    pub index: usize,
+    pub prev: Option<PostLink>,
+    pub next: Option<PostLink>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesPart {
+    /// 1-based part number
+    pub number: usize,
+    pub slug: String,
+    pub title: String,
+    pub url: String,
+    pub category: String,
+    pub current: bool,
+}
+
+/// Slug of a series overview page
+pub fn series_slug(name: &str) -> String {
+    slug::slugify(name)
+}
+
+/// URL of a series overview page
+pub fn series_url(name: &str) -> String {
+    format!("/series/{}/", slug::encode_for_url(&series_slug(name)))
+}
+
+/// Parts, position and neighbours of the current post within its series
+pub fn build_series_navigation(
+    current_slug: &str,
+    current_category: &str,
+    series: &str,
+    metadata: &MetadataCache,
+) -> Option<SeriesNavigation> {
+    let posts = metadata.get_series_posts(series);
+    let index = posts
+        .iter()
+        .position(|p| p.slug == current_slug && p.category == current_category)?;
+
+    let parts = posts
+        .iter()
+        .enumerate()
+        .map(|(i, p)| SeriesPart {
+            number: i + 1,
+            slug: p.slug.clone(),
+            title: p.frontmatter.title.clone(),
+            url: p.url.clone(),
+            category: p.category.clone(),
+            current: i == index,
+        })
+        .collect();
+
+    Some(SeriesNavigation {
+        name: series.to_string(),
+        slug: series_slug(series),
+        // The overview of a translated series lives under the language prefix
+        url: format!(
+            "{}{}",
+            metadata.lang_prefix(&posts[index].lang),
+            series_url(series)
+        ),
+        parts,
+        index,
+        prev: index.checked_sub(1).map(|i| PostLink::new(posts[i])),
+        next: posts.get(index + 1).map(|p| PostLink::new(p)),
+    })
+}
+
 pub fn build_post_navigation(
     current_slug: &str,
     current_category: &str,
@@ -41,33 +129,13 @@ pub fn build_post_navigation(
     let (prev, next) = match current_index {
         Some(index) => {
             let prev = if index < posts.len() - 1 {
-                let p = &posts[index + 1];
-                Some(PostLink {
-                    slug: p.slug.clone(),
-                    title: p.frontmatter.title.clone(),
-                    url: format!(
-                        "/{}/{}/",
-                        slug::encode_for_url(&p.category),
-                        slug::encode_for_url(&p.slug)
-                    ),
-                    category: p.category.clone(),
-                })
+                Some(PostLink::new(posts[index + 1]))
             } else {
                 None
             };
 
             let next = if index > 0 {
-                let p = &posts[index - 1];
-                Some(PostLink {
-                    slug: p.slug.clone(),
-                    title: p.frontmatter.title.clone(),
-                    url: format!(
-                        "/{}/{}/",
-                        slug::encode_for_url(&p.category),
-                        slug::encode_for_url(&p.slug)
-                    ),
-                    category: p.category.clone(),
-                })
+                Some(PostLink::new(posts[index - 1]))
             } else {
                 None
             };
@@ -84,6 +152,7 @@ pub fn build_post_navigation(
 mod tests {
     use super::*;
     use crate::metadata::PostMetadata;
+    use crate::reading::ReadingStats;
     use crate::types::{Frontmatter, PostDate};
     use chrono::Utc;
     use std::collections::HashMap;
@@ -105,7 +174,17 @@ mod tests {
                     description: None,
                     featured_image: None,
                     draft: false,
+                    series: None,
+                    series_order: None,
+                    expires: None,
+                    lang: None,
+                    translation_key: None,
                 },
+                lang: String::new(),
+                url: "/dev/post-1/".to_string(),
+                stats: ReadingStats::default(),
+                excerpt: None,
+                text: None,
             },
             PostMetadata {
                 slug: "post-2".to_string(),
@@ -120,7 +199,17 @@ mod tests {
                     description: None,
                     featured_image: None,
                     draft: false,
+                    series: None,
+                    series_order: None,
+                    expires: None,
+                    lang: None,
+                    translation_key: None,
                 },
+                lang: String::new(),
+                url: "/dev/post-2/".to_string(),
+                stats: ReadingStats::default(),
+                excerpt: None,
+                text: None,
             },
             PostMetadata {
                 slug: "post-3".to_string(),
@@ -135,7 +224,17 @@ mod tests {
                     description: None,
                     featured_image: None,
                     draft: false,
+                    series: None,
+                    series_order: None,
+                    expires: None,
+                    lang: None,
+                    translation_key: None,
                 },
+                lang: String::new(),
+                url: "/dev/post-3/".to_string(),
+                stats: ReadingStats::default(),
+                excerpt: None,
+                text: None,
             },
         ];
 
@@ -144,7 +243,9 @@ mod tests {
             posts,
             categories: HashMap::new(),
             tags: HashMap::new(),
+            series: HashMap::new(),
             category_info: vec![],
+            default_language: String::new(),
         }
     }
 
@@ -188,4 +289,28 @@ mod tests {
         assert!(nav.prev.is_some());
         assert_eq!(nav.prev.as_ref().unwrap().url, "/dev/post-1/");
     }
+
+    #[test]
+    fn test_series_navigation() {
+        let mut metadata = create_test_metadata();
+        // Part 1 is the newest post, so series order must win over date
+        for (post, order) in metadata.posts.iter_mut().zip([2, 3, 1]) {
+            post.frontmatter.series = Some("Rust Basics".to_string());
+            post.frontmatter.series_order = Some(order);
+        }
+
+        let nav = build_series_navigation("post-1", "dev", "Rust Basics", &metadata).unwrap();
+
+        assert_eq!(nav.slug, "rust-basics");
+        assert_eq!(nav.url, "/series/rust-basics/");
+        assert_eq!(nav.index, 1);
+        let slugs: Vec<_> = nav.parts.iter().map(|p| p.slug.as_str()).collect();
+        assert_eq!(slugs, vec!["post-3", "post-1", "post-2"]);
+        assert_eq!(nav.parts[1].number, 2);
+        assert!(nav.parts[1].current);
+        assert_eq!(nav.prev.unwrap().slug, "post-3");
+        assert_eq!(nav.next.unwrap().url, "/dev/post-2/");
+
+        assert!(build_series_navigation("post-1", "dev", "Other", &metadata).is_none());
+    }
 }
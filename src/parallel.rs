@@ -1,19 +1,26 @@
+use crate::cache::Dependencies;
+use crate::excerpt::Excerpt;
+use crate::reading::ReadingStats;
 use crate::types::Frontmatter;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{mpsc, Arc, Mutex};
-use std::thread::{self, JoinHandle};
 
 /// Results from parallel build operations
+#[allow(clippy::large_enum_variant)]
 pub enum BuildResult {
     Success {
         path: PathBuf,
         slug: String,
         category: String,
         frontmatter: Frontmatter,
+        stats: ReadingStats,
+        excerpt: Option<Excerpt>,
+        /// Plain text of the rendered post, when full-text search indexes it
+        text: Option<String>,
         file_hash: String,
-        template_hash: String,
         output_path: String,
+        dependencies: Dependencies,
     },
     Skipped {
         path: PathBuf,
@@ -21,7 +28,7 @@ pub enum BuildResult {
     },
     Error {
         path: PathBuf,
-        error: String,
+        error: anyhow::Error,
     },
 }
 
@@ -29,6 +36,8 @@ pub enum BuildResult {
 pub enum SkipReason {
     Cached,
     Draft,
+    Scheduled,
+    Expired,
 }
 
 /// Progress tracking for parallel builds
@@ -103,33 +112,9 @@ impl<T: Send + 'static> WorkQueue<T> {
     }
 }
 
-/// Simple worker pool for parallel processing
-pub struct WorkerPool {
-    handles: Vec<JoinHandle<()>>,
-}
-
-impl WorkerPool {
-    pub fn new() -> Self {
-        Self {
-            handles: Vec::new(),
-        }
-    }
-
-    pub fn spawn<F>(&mut self, f: F)
-    where
-        F: FnOnce() + Send + 'static,
-    {
-        let handle = thread::spawn(f);
-        self.handles.push(handle);
-    }
-
-    pub fn join(self) -> Result<(), String> {
-        for handle in self.handles {
-            handle
-                .join()
-                .map_err(|_| "Worker thread panicked".to_string())?;
-        }
-        Ok(())
+impl<T: Send + 'static> Default for WorkQueue<T> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
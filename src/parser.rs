@@ -1,45 +1,76 @@
+use crate::diagnostics::SourceError;
+use crate::reading::ReadingStats;
 use crate::types::{Frontmatter, Page, PageFrontmatter, Post};
 use anyhow::{Context, Result};
 use std::fs;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
 pub struct Parser;
 
 impl Parser {
-    pub fn parse_file(path: &Path) -> Result<Post> {
+    /// Parse a post; its category is the directory under `content_dir` it is in
+    pub fn parse_file(path: &Path, content_dir: &Path) -> Result<Post> {
         let content = fs::read_to_string(path)
             .with_context(|| format!("Failed to read {}", path.display()))?;
 
         let (frontmatter_str, markdown) = Self::split_frontmatter(&content)?;
-        let frontmatter = Self::parse_frontmatter(frontmatter_str)?;
+        let frontmatter = Self::parse_frontmatter(frontmatter_str)
+            .map_err(|e| Self::frontmatter_error(path, &content, frontmatter_str, &e))?;
         let slug = Self::path_to_slug(path)?;
-        let category = Self::extract_category(path)?;
+        let category = Self::extract_category(path, content_dir)?;
 
         Ok(Post {
             slug,
             category,
+            is_draft: frontmatter.draft,
             frontmatter,
             content: markdown.to_string(),
             rendered_html: None,
+            toc: Vec::new(),
+            stats: ReadingStats::default(),
+            excerpt: None,
         })
     }
 
-    fn extract_category(path: &Path) -> Result<String> {
-        let components: Vec<_> = path.components().collect();
-
-        for i in 0..components.len() {
-            if let std::path::Component::Normal(comp) = components[i] {
-                if comp == "posts" && i + 1 < components.len() {
-                    if let std::path::Component::Normal(category) = components[i + 1] {
-                        return category.to_str().map(|s| s.to_string()).ok_or_else(|| {
-                            anyhow::anyhow!("Invalid category name in path: {}", path.display())
-                        });
-                    }
-                }
-            }
+    fn extract_category(path: &Path, content_dir: &Path) -> Result<String> {
+        let relative = Self::relative_to(path, content_dir);
+        let mut components = relative.iter().flat_map(|p| p.components());
+
+        // The category directory, and the post file inside it
+        if let (Some(Component::Normal(category)), Some(_)) = (components.next(), components.next())
+        {
+            return category.to_str().map(|s| s.to_string()).ok_or_else(|| {
+                anyhow::anyhow!("Invalid category name in path: {}", path.display())
+            });
         }
 
-        anyhow::bail!("Could not extract category from path: {}. Expected path format: content/posts/<category>/...", path.display())
+        anyhow::bail!(
+            "Could not extract category from path: {}. Expected path format: {}/<category>/...",
+            path.display(),
+            content_dir.display()
+        )
+    }
+
+    /// `path` below `dir`, whether either is written relative to the working
+    /// directory, with a leading `./`, or absolute
+    fn relative_to(path: &Path, dir: &Path) -> Option<PathBuf> {
+        let normalize = |p: &Path| -> PathBuf {
+            let p = if p.is_relative() {
+                std::env::current_dir()
+                    .map(|cwd| cwd.join(p))
+                    .unwrap_or_else(|_| p.to_path_buf())
+            } else {
+                p.to_path_buf()
+            };
+            p.components()
+                .filter(|c| !matches!(c, Component::CurDir))
+                .collect()
+        };
+
+        normalize(path)
+            .strip_prefix(normalize(dir))
+            .ok()
+            .map(Path::to_path_buf)
     }
 
     pub fn parse_page_file(path: &Path) -> Result<Page> {
@@ -50,13 +81,15 @@ impl Parser {
 
         if content.trim_start().starts_with("---") {
             let (frontmatter_str, markdown) = Self::split_frontmatter(&content)?;
-            let frontmatter = Self::parse_page_frontmatter(frontmatter_str)?;
+            let frontmatter = Self::parse_page_frontmatter(frontmatter_str)
+                .map_err(|e| Self::frontmatter_error(path, &content, frontmatter_str, &e))?;
 
             Ok(Page {
                 slug,
                 frontmatter,
                 content: markdown.to_string(),
                 rendered_html: None,
+                toc: Vec::new(),
             })
         } else {
             Ok(Page {
@@ -68,6 +101,7 @@ impl Parser {
                 },
                 content: content.to_string(),
                 rendered_html: None,
+                toc: Vec::new(),
             })
         }
     }
@@ -82,12 +116,24 @@ impl Parser {
         Ok((parts[1].trim(), parts[2].trim()))
     }
 
-    fn parse_frontmatter(yaml: &str) -> Result<Frontmatter> {
-        serde_yaml::from_str(yaml).context("Failed to parse frontmatter YAML")
+    fn parse_frontmatter(yaml: &str) -> serde_yaml::Result<Frontmatter> {
+        serde_yaml::from_str(yaml)
+    }
+
+    fn parse_page_frontmatter(yaml: &str) -> serde_yaml::Result<PageFrontmatter> {
+        serde_yaml::from_str(yaml)
     }
 
-    fn parse_page_frontmatter(yaml: &str) -> Result<PageFrontmatter> {
-        serde_yaml::from_str(yaml).context("Failed to parse page frontmatter YAML")
+    /// Locate a frontmatter error in the whole file; `frontmatter` is a slice of `content`
+    fn frontmatter_error(
+        path: &Path,
+        content: &str,
+        frontmatter: &str,
+        error: &serde_yaml::Error,
+    ) -> SourceError {
+        let start = frontmatter.as_ptr() as usize - content.as_ptr() as usize;
+        let lines_before = content[..start].matches('\n').count();
+        SourceError::yaml(path, error).offset_lines(lines_before)
     }
 
     fn path_to_slug(path: &Path) -> Result<String> {
@@ -135,4 +181,32 @@ Content with multiple lines"#;
         let slug = Parser::path_to_slug(path).unwrap();
         assert_eq!(slug, "hello-world");
     }
+
+    #[test]
+    fn test_extract_category() {
+        let path = Path::new("notes/dev/hello-world.md");
+        assert_eq!(
+            Parser::extract_category(path, Path::new("notes")).unwrap(),
+            "dev"
+        );
+        assert_eq!(
+            Parser::extract_category(path, Path::new("./notes/")).unwrap(),
+            "dev"
+        );
+
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            Parser::extract_category(path, &cwd.join("notes")).unwrap(),
+            "dev"
+        );
+
+        // Posts sit in a category directory, and nothing is assumed to be called "posts"
+        let error =
+            Parser::extract_category(Path::new("notes/hello.md"), Path::new("notes")).unwrap_err();
+        assert!(error.to_string().contains("notes/<category>/"));
+        assert!(
+            Parser::extract_category(Path::new("content/posts/dev/a.md"), Path::new("notes"))
+                .is_err()
+        );
+    }
 }
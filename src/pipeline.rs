@@ -0,0 +1,1924 @@
+//! The site build, split into explicit stages.
+//!
+//! Every way of building (`ssg build` with or without `--parallel`, single
+//! posts, watch mode) goes through [`BuildPipeline`], so they cannot drift
+//! apart: posts are parsed into metadata before any of them is rendered, and
+//! rendered posts are applied back in source order whichever thread built them.
+
+use crate::cache::{fingerprint, hash_file, BuildCache, Dependencies, Listings};
+use crate::category::discover_categories;
+use crate::config::{site_path, SsgConfig};
+use crate::excerpt::{self, Excerpt};
+use crate::feeds::FeedGenerator;
+use crate::generator::Generator;
+use crate::indices::IndexGenerator;
+use crate::metadata::MetadataCache;
+use crate::navigation::{build_post_navigation, build_series_navigation};
+use crate::parallel::{get_thread_count, BuildProgress, BuildResult, SkipReason, WorkQueue};
+use crate::parser::Parser;
+use crate::plugin::{PluginContext, PluginManager};
+use crate::reading::ReadingStats;
+use crate::renderer::Renderer;
+use crate::search::SearchIndexGenerator;
+use crate::shortcodes::ShortcodeRegistry;
+use crate::sitemap::SitemapGenerator;
+use crate::types::{Frontmatter, Post, Visibility};
+use crate::watch::ChangeSet;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value as JsonValue};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+/// Options that change how a build runs, never what it produces
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildOptions {
+    /// Skip posts whose source and dependencies are unchanged since the last build
+    pub incremental: bool,
+    /// Render posts on a worker pool
+    pub parallel: bool,
+    /// Include scheduled and expired posts, e.g. for previews
+    pub future: bool,
+    /// Include draft posts and pages, e.g. for previews
+    pub drafts: bool,
+}
+
+/// Summary of a finished build
+#[derive(Debug, Clone, Default)]
+pub struct BuildReport {
+    pub built: usize,
+    /// Unchanged, draft, scheduled and expired posts
+    pub skipped: usize,
+    /// Every post source, in source order
+    pub posts: Vec<PostOutcome>,
+    pub pages: usize,
+    pub categories: usize,
+    pub tags: usize,
+    pub elapsed: Duration,
+}
+
+/// What a build did with one post source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostOutcome {
+    pub source: PathBuf,
+    pub status: PostStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PostStatus {
+    Built {
+        output_path: PathBuf,
+    },
+    /// Skipped by an incremental build
+    Unchanged,
+    Draft,
+    /// `date.posted` is in the future
+    Scheduled,
+    /// `expires` has passed
+    Expired,
+}
+
+/// (category, slug): slugs only identify a post within its category
+type PostKey = (String, String);
+
+/// What else an edit to a post's body makes stale, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stale {
+    Nothing,
+    /// Feeds and the full-text search index, which include the body
+    Feeds,
+    /// Also every page showing the post's excerpt or reading stats:
+    /// listings, the search index and posts linking to it
+    Listings,
+    /// Also the sitemap, after a new title, description, image or modified date
+    Metadata,
+}
+
+pub struct BuildPipeline {
+    config: SsgConfig,
+    options: BuildOptions,
+    renderer: Renderer,
+    generator: Generator,
+    plugin_manager: PluginManager,
+    shortcode_registry: ShortcodeRegistry,
+    metadata: MetadataCache,
+    cache: BuildCache,
+    /// Listing pages and feeds the last build wrote
+    listings: Listings,
+    /// Metadata of each language's posts, when the site has several languages
+    language_metadata: HashMap<String, MetadataCache>,
+    /// Scheduled and expired posts are judged against the start of the build
+    now: DateTime<Utc>,
+    /// Whether the last full build finished, so its metadata describes the output
+    complete: bool,
+}
+
+impl BuildPipeline {
+    /// `plugin_manager` must already be initialized and its shortcodes registered
+    pub fn new(
+        config: SsgConfig,
+        options: BuildOptions,
+        plugin_manager: PluginManager,
+        shortcode_registry: ShortcodeRegistry,
+    ) -> Result<Self> {
+        let cache = if options.incremental {
+            BuildCache::load(Path::new(&config.build.cache_dir))?
+        } else {
+            BuildCache::new()
+        };
+        let listings = Listings::load(Path::new(&config.build.cache_dir))?;
+
+        Ok(Self {
+            renderer: Renderer::new().with_heading_anchors(config.build.toc.anchors),
+            generator: Generator::new(config.clone())?,
+            plugin_manager,
+            shortcode_registry,
+            metadata: MetadataCache::new(),
+            cache,
+            listings,
+            language_metadata: HashMap::new(),
+            now: Utc::now(),
+            complete: false,
+            options,
+            config,
+        })
+    }
+
+    pub fn config(&self) -> &SsgConfig {
+        &self.config
+    }
+
+    pub fn plugin_names(&self) -> Vec<String> {
+        self.plugin_manager.list_plugins()
+    }
+
+    /// Run every stage in order
+    pub fn run(&mut self) -> Result<BuildReport> {
+        let start_time = Instant::now();
+        self.complete = false;
+
+        let post_files = self.collect()?;
+        let progress = BuildProgress::new();
+        let posts = self.build_posts(&post_files, &progress)?;
+        let pages = self.build_pages()?;
+        self.generate_listings()?;
+        self.save()?;
+        self.write_assets()?;
+        self.finish()?;
+        self.complete = true;
+
+        Ok(BuildReport {
+            built: progress.get_built(),
+            skipped: progress.get_skipped(),
+            posts,
+            pages,
+            categories: self.metadata.get_categories().len(),
+            tags: self.metadata.get_tags().len(),
+            elapsed: start_time.elapsed(),
+        })
+    }
+
+    /// Rebuild after `changes`, running only the stages they affect.
+    ///
+    /// An edited post is rendered on its own, followed by the feeds it appears
+    /// in and the search index; when its excerpt, reading stats, title,
+    /// description, image or modified date changed, also its listings, the
+    /// posts linking to it and the sitemap. Anything that can move posts
+    /// around (new, removed or withheld posts, other frontmatter, categories,
+    /// templates, body edits a plugin compares across posts) runs every stage,
+    /// skipping unchanged posts when the build is incremental. Pages and
+    /// static files are built or copied one by one.
+    pub fn rebuild(&mut self, changes: &ChangeSet) -> Result<BuildReport> {
+        if changes.templates || changes.config {
+            self.generator = Generator::new(self.config.clone())?;
+        }
+        if changes.is_structural() || !self.complete {
+            return self.run();
+        }
+
+        match self.apply_changes(changes)? {
+            Some(report) => {
+                self.save()?;
+                Ok(report)
+            }
+            None => self.run(),
+        }
+    }
+
+    /// Every stage of `rebuild` except `save`; `None` as soon as the changes
+    /// turn out to need a full build
+    fn apply_changes(&mut self, changes: &ChangeSet) -> Result<Option<BuildReport>> {
+        let start_time = Instant::now();
+        let progress = BuildProgress::new();
+        let mut posts = Vec::new();
+        // Every post whose edit shows elsewhere
+        let mut edited: Vec<(PostKey, Stale)> = Vec::new();
+        for path in &changes.posts {
+            let Some((outcome, key, stale)) = self.rebuild_post(path, &progress)? else {
+                return Ok(None);
+            };
+            posts.push(outcome);
+            if stale > Stale::Nothing {
+                edited.push((key, stale));
+            }
+        }
+        self.scope_languages();
+
+        // Posts showing an edited post's excerpt or stats, e.g. as its neighbour.
+        // Cache keys and changed paths may be written differently.
+        let changed: BTreeSet<PathBuf> = changes.posts.iter().map(site_path).collect();
+        let readers: Vec<PathBuf> = self
+            .cache
+            .entries
+            .iter()
+            .filter(|(_, entry)| {
+                edited.iter().any(|((category, slug), stale)| {
+                    *stale >= Stale::Listings
+                        && entry
+                            .dependencies
+                            .siblings
+                            .contains_key(&Dependencies::sibling_key(category, slug))
+                })
+            })
+            .map(|(source, _)| PathBuf::from(source))
+            .filter(|source| !changed.contains(&site_path(source)))
+            .collect();
+        for path in readers {
+            match self.rebuild_post(&path, &progress)? {
+                Some((outcome, _, _)) => posts.push(outcome),
+                None => return Ok(None),
+            }
+        }
+
+        for ((category, slug), stale) in &edited {
+            self.refresh_listings(category, slug, *stale)?;
+        }
+        let full_text = self.config.build.search.full_text;
+        if edited
+            .iter()
+            .any(|(_, stale)| full_text || *stale >= Stale::Listings)
+        {
+            self.generate_search_index()?;
+        }
+
+        let mut pages = 0;
+        for path in &changes.pages {
+            if !path.exists() {
+                return Ok(None);
+            }
+            if self.build_page(path)? {
+                pages += 1;
+            }
+        }
+        if !changes.pages.is_empty() || edited.iter().any(|(_, stale)| *stale == Stale::Metadata) {
+            self.generate_sitemap()?;
+        }
+
+        for path in &changes.static_files {
+            self.generator.copy_static_file(path)?;
+        }
+        for path in &changes.content_assets {
+            self.generator.copy_content_asset(path)?;
+        }
+
+        Ok(Some(BuildReport {
+            built: progress.get_built(),
+            skipped: progress.get_skipped(),
+            posts,
+            pages,
+            categories: self.metadata.get_categories().len(),
+            tags: self.metadata.get_tags().len(),
+            elapsed: start_time.elapsed(),
+        }))
+    }
+
+    /// Render one edited post against the current metadata. `None` when the
+    /// edit may move it in or out of other posts' navigation, listings or
+    /// related posts, e.g. new tags or dates, or a change in whether it is
+    /// published, so other posts may have to change too.
+    fn rebuild_post(
+        &mut self,
+        path: &Path,
+        progress: &BuildProgress,
+    ) -> Result<Option<(PostOutcome, PostKey, Stale)>> {
+        if !path.exists() || self.plugin_manager.reads_post_bodies() {
+            return Ok(None);
+        }
+
+        let post = parse_post(path, &self.config)?;
+        let key = (post.category.clone(), post.slug.clone());
+        let Some(current) = self
+            .metadata
+            .posts
+            .iter()
+            .find(|p| p.slug == post.slug && p.category == post.category)
+        else {
+            return Ok(None);
+        };
+        if self.withheld(&post.frontmatter).is_some() {
+            return Ok(None);
+        }
+        let current_frontmatter = fingerprint(&current.frontmatter);
+        let current_placement = placement(&current.frontmatter);
+        let current_listing = fingerprint(&(&current.excerpt, &current.stats));
+
+        let mut result = self.process_post(path)?;
+        let mut stale = Stale::Feeds;
+        if let BuildResult::Success {
+            slug,
+            category,
+            frontmatter,
+            stats,
+            ..
+        } = &result
+        {
+            if fingerprint(frontmatter) != current_frontmatter {
+                if placement(frontmatter) != current_placement {
+                    return Ok(None);
+                }
+                // The post shows itself through metadata too, e.g. in its series
+                self.metadata
+                    .refresh_post(category, slug, frontmatter.clone(), *stats);
+                self.scope_languages();
+                result = self.process_post(path)?;
+                stale = Stale::Metadata;
+            }
+        }
+
+        match result {
+            BuildResult::Success {
+                path,
+                slug,
+                category,
+                frontmatter,
+                stats,
+                excerpt,
+                text,
+                file_hash,
+                output_path,
+                dependencies,
+            } => {
+                println!("🔨 Built: {}", path.display());
+                progress.increment_built();
+                if fingerprint(&(&excerpt, &stats)) != current_listing {
+                    stale = stale.max(Stale::Listings);
+                }
+                self.metadata
+                    .refresh_post(&category, &slug, frontmatter, stats);
+                self.metadata.set_rendered(&category, &slug, excerpt, text);
+                self.cache
+                    .update_entry(&path, file_hash, output_path.clone(), dependencies);
+
+                let outcome = PostOutcome {
+                    source: path,
+                    status: PostStatus::Built {
+                        output_path: PathBuf::from(output_path),
+                    },
+                };
+                Ok(Some((outcome, key, stale)))
+            }
+            BuildResult::Skipped {
+                path,
+                reason: SkipReason::Cached,
+            } => {
+                progress.increment_skipped();
+                let outcome = PostOutcome {
+                    source: path,
+                    status: PostStatus::Unchanged,
+                };
+                Ok(Some((outcome, key, Stale::Nothing)))
+            }
+            BuildResult::Skipped { .. } => Ok(None),
+            BuildResult::Error { error, .. } => Err(error),
+        }
+    }
+
+    /// Regenerate the feeds, and from [`Stale::Listings`] on the listings, that
+    /// show one post, in the post's language
+    fn refresh_listings(&self, category: &str, slug: &str, stale: Stale) -> Result<()> {
+        let Some(post) = self
+            .metadata
+            .posts
+            .iter()
+            .find(|p| p.slug == slug && p.category == category)
+        else {
+            return Ok(());
+        };
+        let language = self
+            .config
+            .language(&post.lang)
+            .unwrap_or_else(|| self.config.default_language());
+        let config = self.config.localized(&language);
+        let metadata = self
+            .language_metadata
+            .get(&language.code)
+            .unwrap_or(&self.metadata);
+
+        if stale >= Stale::Listings {
+            IndexGenerator::new(config.clone())?
+                .with_language(self.generator.language_context(&language.code))
+                .generate_for_post(metadata, &self.plugin_manager, post)?;
+        }
+        FeedGenerator::generate_post_feeds(
+            &config,
+            metadata,
+            Path::new(&self.config.build.content_dir),
+            Path::new(&config.build.output_dir),
+            post,
+        )
+    }
+
+    /// Stage 1: discover categories and parse every post, so navigation and
+    /// plugins see the whole site while posts are rendered
+    pub fn collect(&mut self) -> Result<Vec<PathBuf>> {
+        self.metadata = MetadataCache::new();
+        self.metadata
+            .set_default_language(&self.config.site.language);
+        self.now = Utc::now();
+        self.plugin_manager.on_build_start(&PluginContext {
+            config: &self.config,
+            metadata: &self.metadata,
+        })?;
+
+        let posts_dir = Path::new(&self.config.build.content_dir);
+
+        if !posts_dir.exists() {
+            anyhow::bail!(
+                "Content directory '{}' does not exist. Create it first with: mkdir -p {}",
+                self.config.build.content_dir,
+                self.config.build.content_dir
+            );
+        }
+
+        let categories = discover_categories(posts_dir)?;
+        if categories.is_empty() {
+            eprintln!("⚠️  Warning: No categories found in content directory");
+            eprintln!("   Create a category by adding a subdirectory with markdown files:");
+            eprintln!("   mkdir -p {}/dev", self.config.build.content_dir);
+        }
+
+        self.metadata.set_category_info(categories);
+
+        let post_files = collect_post_files(posts_dir);
+        for path in &post_files {
+            if let Ok(post) = parse_post(path, &self.config) {
+                if self.withheld(&post.frontmatter).is_none() {
+                    self.metadata.upsert_post(
+                        post.slug,
+                        post.category,
+                        post.frontmatter,
+                        post.stats,
+                    );
+                }
+            }
+        }
+
+        if self.options.incremental {
+            if let Ok(previous) = MetadataCache::load(self.cache_dir()) {
+                self.metadata.inherit_rendered(&previous);
+            }
+        }
+        self.scope_languages();
+
+        Ok(post_files)
+    }
+
+    /// Stage 2: render posts, then record them in metadata and the build cache.
+    /// Plugins get the finished metadata before anything else reads it.
+    pub fn build_posts(
+        &mut self,
+        post_files: &[PathBuf],
+        progress: &BuildProgress,
+    ) -> Result<Vec<PostOutcome>> {
+        let results = if self.options.parallel {
+            self.process_posts_parallel(post_files, progress)
+        } else {
+            post_files
+                .iter()
+                .map(|path| self.process_post_or_error(path, progress))
+                .collect()
+        };
+
+        let mut outcomes = Vec::new();
+        let mut errors = Vec::new();
+        for result in results {
+            match result {
+                BuildResult::Success {
+                    path,
+                    slug,
+                    category,
+                    frontmatter,
+                    stats,
+                    excerpt,
+                    text,
+                    file_hash,
+                    output_path,
+                    dependencies,
+                } => {
+                    println!("🔨 Built: {}", path.display());
+                    self.metadata
+                        .upsert_post(slug.clone(), category.clone(), frontmatter, stats);
+                    self.metadata.set_rendered(&category, &slug, excerpt, text);
+                    outcomes.push(PostOutcome {
+                        status: PostStatus::Built {
+                            output_path: PathBuf::from(&output_path),
+                        },
+                        source: path.clone(),
+                    });
+                    self.cache
+                        .update_entry(&path, file_hash, output_path, dependencies);
+                }
+                BuildResult::Skipped { path, reason } => {
+                    let status = match reason {
+                        SkipReason::Cached => {
+                            println!("⏭  Skipped (unchanged): {}", path.display());
+                            PostStatus::Unchanged
+                        }
+                        SkipReason::Draft => {
+                            println!("   ⚠  Draft - skipping: {}", path.display());
+                            self.cache.remove_entry(&path);
+                            PostStatus::Draft
+                        }
+                        SkipReason::Scheduled => {
+                            println!("   ⏰ Scheduled - skipping: {}", path.display());
+                            self.cache.remove_entry(&path);
+                            PostStatus::Scheduled
+                        }
+                        SkipReason::Expired => {
+                            println!("   ⌛ Expired - skipping: {}", path.display());
+                            self.cache.remove_entry(&path);
+                            PostStatus::Expired
+                        }
+                    };
+                    outcomes.push(PostOutcome {
+                        source: path,
+                        status,
+                    });
+                }
+                BuildResult::Error { path, error } => {
+                    eprintln!("❌ Error building {}: {:#}", path.display(), error);
+                    errors.push(error);
+                }
+            }
+        }
+
+        // The first error comes along, so watch mode can point at it
+        let count = errors.len();
+        if let Some(error) = errors.into_iter().next() {
+            return Err(error.context(format!("{} posts failed to build", count)));
+        }
+
+        self.plugin_manager
+            .on_metadata_ready(&mut self.metadata, &self.config)?;
+
+        Ok(outcomes)
+    }
+
+    /// Process posts on worker threads; results come back in `post_files` order
+    fn process_posts_parallel(
+        &self,
+        post_files: &[PathBuf],
+        progress: &BuildProgress,
+    ) -> Vec<BuildResult> {
+        let work_queue = WorkQueue::new();
+        let work_rx = work_queue.get_receiver();
+        for (index, path) in post_files.iter().enumerate() {
+            let _ = work_queue.send((index, path.clone()));
+        }
+        work_queue.close();
+
+        let (result_tx, result_rx) = mpsc::channel();
+        let num_threads = get_thread_count().min(post_files.len()).max(1);
+
+        std::thread::scope(|scope| {
+            for _ in 0..num_threads {
+                let work_rx = &work_rx;
+                let result_tx = result_tx.clone();
+
+                scope.spawn(move || loop {
+                    let next = work_rx.lock().unwrap().recv();
+                    let Ok((index, path)) = next else {
+                        break;
+                    };
+
+                    let result = self.process_post_or_error(&path, progress);
+                    let _ = result_tx.send((index, result));
+                });
+            }
+        });
+        drop(result_tx);
+
+        let mut results: Vec<_> = result_rx.into_iter().collect();
+        results.sort_by_key(|(index, _)| *index);
+        results.into_iter().map(|(_, result)| result).collect()
+    }
+
+    fn process_post_or_error(&self, path: &Path, progress: &BuildProgress) -> BuildResult {
+        let result = match self.process_post(path) {
+            Ok(result) => result,
+            Err(error) => BuildResult::Error {
+                path: path.to_path_buf(),
+                error,
+            },
+        };
+
+        match &result {
+            BuildResult::Success { .. } => progress.increment_built(),
+            BuildResult::Skipped { .. } => progress.increment_skipped(),
+            BuildResult::Error { .. } => {}
+        }
+
+        result
+    }
+
+    /// Build one post against the metadata collected in stage 1
+    fn process_post(&self, path: &Path) -> Result<BuildResult> {
+        let file_hash = hash_file(path)?;
+        let mut post = parse_post(path, &self.config)?;
+
+        if let Some(reason) = self.withheld(&post.frontmatter) {
+            // Take down what an earlier build published
+            self.generator.remove_post(&post)?;
+            return Ok(BuildResult::Skipped {
+                path: path.to_path_buf(),
+                reason,
+            });
+        }
+
+        let plugin_ctx = PluginContext {
+            config: &self.config,
+            metadata: self.post_metadata(&post),
+        };
+
+        self.plugin_manager.on_post_parsed(&mut post, &plugin_ctx)?;
+
+        let plugin_data = self.post_template_context(&post, &plugin_ctx)?;
+        let mut dependencies = self.post_dependencies(&post, &plugin_data);
+
+        if self.options.incremental
+            && !self.cache.needs_rebuild(
+                path,
+                &file_hash,
+                &dependencies,
+                self.generator.template_hashes(),
+            )
+        {
+            return Ok(BuildResult::Skipped {
+                path: path.to_path_buf(),
+                reason: SkipReason::Cached,
+            });
+        }
+
+        let (output_path, components) = self.render_post(&mut post, &plugin_data, &plugin_ctx)?;
+        dependencies.templates = self
+            .generator
+            .template_dependencies("post.html", &components);
+        let text = self
+            .config
+            .build
+            .search
+            .full_text
+            .then(|| excerpt::plain_text(post.rendered_html.as_deref().unwrap_or_default()));
+
+        Ok(BuildResult::Success {
+            path: path.to_path_buf(),
+            slug: post.slug,
+            category: post.category,
+            frontmatter: post.frontmatter,
+            stats: post.stats,
+            excerpt: post.excerpt,
+            text,
+            file_hash,
+            output_path: output_path.to_string_lossy().to_string(),
+            dependencies,
+        })
+    }
+
+    /// Build a single post against the metadata of the last full build.
+    /// Drafts, scheduled and expired posts need the options that include them.
+    pub fn build_single_post(&mut self, path: &Path) -> Result<PathBuf> {
+        if !path.exists() {
+            anyhow::bail!("Post file not found: {}", path.display());
+        }
+
+        // The last build may have been a preview that included more posts
+        let mut metadata =
+            MetadataCache::load(self.cache_dir()).unwrap_or_else(|_| MetadataCache::new());
+        metadata.retain_posts(|p| self.withheld(&p.frontmatter).is_none());
+        metadata.set_default_language(&self.config.site.language);
+        self.metadata = metadata;
+        self.scope_languages();
+
+        let mut post = parse_post(path, &self.config)?;
+
+        match self.withheld(&post.frontmatter) {
+            Some(SkipReason::Draft) => {
+                anyhow::bail!("{} is a draft; build it with --drafts", path.display())
+            }
+            Some(SkipReason::Scheduled) => {
+                anyhow::bail!("{} is scheduled; build it with --future", path.display())
+            }
+            Some(SkipReason::Expired) => {
+                anyhow::bail!("{} has expired; build it with --future", path.display())
+            }
+            Some(SkipReason::Cached) | None => {}
+        }
+
+        let plugin_ctx = PluginContext {
+            config: &self.config,
+            metadata: self.post_metadata(&post),
+        };
+
+        self.plugin_manager.on_post_parsed(&mut post, &plugin_ctx)?;
+
+        let plugin_data = self.post_template_context(&post, &plugin_ctx)?;
+        let (output_path, _) = self.render_post(&mut post, &plugin_data, &plugin_ctx)?;
+
+        Ok(output_path)
+    }
+
+    /// Shortcodes, markdown, components and plugin hooks, then the post template.
+    /// Returns the output path and the components the content used.
+    fn render_post(
+        &self,
+        post: &mut Post,
+        plugin_data: &HashMap<String, JsonValue>,
+        plugin_ctx: &PluginContext,
+    ) -> Result<(PathBuf, Vec<String>)> {
+        let processed_content = self.shortcode_registry.process(&post.content)?;
+
+        let rendered = self.renderer.render_markdown_with_components(
+            &processed_content,
+            self.generator.get_tera(),
+            &post.category,
+        )?;
+        let mut html = rendered.html;
+        post.toc = rendered.toc;
+
+        self.plugin_manager
+            .on_post_rendered(post, &mut html, plugin_ctx)?;
+
+        post.excerpt = Excerpt::from_html(&html, &self.config.build.excerpt);
+        post.rendered_html = Some(html);
+
+        let output_path =
+            self.generator
+                .generate_post(post, plugin_data, &self.plugin_manager, plugin_ctx)?;
+
+        Ok((output_path, rendered.components))
+    }
+
+    /// Template context a post reads besides its own content: plugin data and navigation
+    fn post_template_context(
+        &self,
+        post: &Post,
+        plugin_ctx: &PluginContext,
+    ) -> Result<HashMap<String, JsonValue>> {
+        let mut plugin_data = self
+            .plugin_manager
+            .template_context_post(post, plugin_ctx)?;
+
+        let navigation =
+            build_post_navigation(&post.slug, &post.category, plugin_ctx.metadata, false);
+        plugin_data.insert("prev_post".to_string(), json!(navigation.prev));
+        plugin_data.insert("next_post".to_string(), json!(navigation.next));
+
+        let series = post.frontmatter.series.as_deref().and_then(|name| {
+            build_series_navigation(&post.slug, &post.category, name, plugin_ctx.metadata)
+        });
+        plugin_data.insert("series".to_string(), json!(series));
+        plugin_data.insert("alternates".to_string(), json!(self.alternates(post)));
+
+        // Only scheduled and expired posts built for a preview carry a notice
+        let preview = match post.frontmatter.visibility(self.now) {
+            Visibility::Published => None,
+            Visibility::Scheduled => Some(json!({
+                "state": Visibility::Scheduled,
+                "date": post.frontmatter.date.posted,
+            })),
+            Visibility::Expired => Some(json!({
+                "state": Visibility::Expired,
+                "date": post.frontmatter.expires,
+            })),
+        };
+        plugin_data.insert("preview".to_string(), json!(preview));
+
+        Ok(plugin_data)
+    }
+
+    /// Translations of a post, itself included, in site language order.
+    /// Empty unless another post shares its `translation_key`.
+    fn alternates(&self, post: &Post) -> Vec<JsonValue> {
+        let Some(key) = post.frontmatter.translation_key.as_deref() else {
+            return Vec::new();
+        };
+        let site_url = self.config.site.url.trim_end_matches('/');
+        // Translations may share a slug, even across categories
+        let lang = post
+            .frontmatter
+            .lang
+            .as_deref()
+            .unwrap_or(&self.config.site.language);
+
+        let alternates: Vec<_> = self
+            .config
+            .languages()
+            .into_iter()
+            .flat_map(|language| {
+                self.metadata
+                    .posts
+                    .iter()
+                    .filter(|p| {
+                        p.lang == language.code
+                            && p.frontmatter.translation_key.as_deref() == Some(key)
+                    })
+                    .map(|p| {
+                        json!({
+                            "lang": language.code,
+                            "locale": language.locale,
+                            "name": language.name,
+                            "url": format!("{}{}", site_url, p.url),
+                            "title": p.frontmatter.title,
+                            "slug": p.slug,
+                            "category": p.category,
+                            "current": p.lang == lang
+                                && p.category == post.category
+                                && p.slug == post.slug,
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        if alternates.len() > 1 {
+            alternates
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Split metadata by language, so a post's navigation, series and related
+    /// posts only ever point at posts in its own language
+    fn scope_languages(&mut self) {
+        self.language_metadata = self.language_scopes();
+    }
+
+    fn language_scopes(&self) -> HashMap<String, MetadataCache> {
+        if self.config.site.languages.len() < 2 {
+            return HashMap::new();
+        }
+        self.config
+            .languages()
+            .into_iter()
+            .map(|language| {
+                let metadata = self.metadata.for_language(&language.code);
+                (language.code, metadata)
+            })
+            .collect()
+    }
+
+    /// Metadata a post is built against: the posts in its language
+    fn post_metadata(&self, post: &Post) -> &MetadataCache {
+        let lang = post
+            .frontmatter
+            .lang
+            .as_deref()
+            .unwrap_or(&self.config.site.language);
+        self.language_metadata.get(lang).unwrap_or(&self.metadata)
+    }
+
+    /// Why a post is left out of this build, if it is.
+    /// Every stage asks this, so a withheld post cannot reach any output.
+    fn withheld(&self, frontmatter: &Frontmatter) -> Option<SkipReason> {
+        if frontmatter.draft && !self.options.drafts {
+            return Some(SkipReason::Draft);
+        }
+        if self.options.future {
+            return None;
+        }
+        match frontmatter.visibility(self.now) {
+            Visibility::Published => None,
+            Visibility::Scheduled => Some(SkipReason::Scheduled),
+            Visibility::Expired => Some(SkipReason::Expired),
+        }
+    }
+
+    /// Everything a post output reads except templates, which are only known after rendering
+    fn post_dependencies(
+        &self,
+        post: &Post,
+        plugin_data: &HashMap<String, JsonValue>,
+    ) -> Dependencies {
+        let mut dependencies = Dependencies {
+            shortcodes: self.shortcode_registry.used_shortcodes(&post.content),
+            metadata: self.generator.metadata_dependencies(),
+            ..Default::default()
+        };
+        // Everything plugins and navigation add to the template context, e.g.
+        // related-post scores or a preview notice, in a stable order
+        let context: BTreeMap<&String, &JsonValue> = plugin_data.iter().collect();
+        dependencies
+            .metadata
+            .insert("plugin_data".to_string(), fingerprint(&context));
+        dependencies.record_siblings(
+            &Dependencies::sibling_key(&post.category, &post.slug),
+            plugin_data,
+            &self.metadata,
+        );
+        dependencies
+    }
+
+    /// Persist the build cache and metadata for incremental and single-post
+    /// builds, and the listings written for the next build to clean up
+    pub fn save(&self) -> Result<()> {
+        if self.options.incremental {
+            self.cache.save(self.cache_dir())?;
+        }
+        self.listings.save(self.cache_dir())?;
+        self.metadata.save(self.cache_dir())
+    }
+
+    fn cache_dir(&self) -> &Path {
+        Path::new(&self.config.build.cache_dir)
+    }
+
+    /// Stage 3: standalone pages
+    pub fn build_pages(&self) -> Result<usize> {
+        let pages_dir = Path::new(&self.config.build.pages_dir);
+        if !pages_dir.exists() {
+            return Ok(0);
+        }
+
+        println!("\n📄 Building pages...");
+        let mut pages_built = 0;
+
+        for entry in WalkDir::new(pages_dir)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
+        {
+            if self.build_page(entry.path())? {
+                pages_built += 1;
+            }
+        }
+
+        if pages_built > 0 {
+            println!("✅ Built {} page(s)", pages_built);
+        }
+
+        Ok(pages_built)
+    }
+
+    /// Build one page; returns whether it was written rather than withheld as a draft
+    fn build_page(&self, path: &Path) -> Result<bool> {
+        println!("🔨 Building page: {}", path.display());
+
+        let mut page = Parser::parse_page_file(path)?;
+
+        if page.frontmatter.draft && !self.options.drafts {
+            println!("   ⚠  Draft - skipping output");
+            self.generator.remove_page(&page)?;
+            return Ok(false);
+        }
+
+        let processed_content = self.shortcode_registry.process(&page.content)?;
+        let rendered = self.renderer.render_markdown_with_components(
+            &processed_content,
+            self.generator.get_tera(),
+            &page.slug,
+        )?;
+        page.rendered_html = Some(rendered.html);
+        page.toc = rendered.toc;
+
+        let plugin_ctx = PluginContext {
+            config: &self.config,
+            metadata: &self.metadata,
+        };
+        let plugin_data = self
+            .plugin_manager
+            .template_context_page(&page, &plugin_ctx)?;
+
+        let output_path =
+            self.generator
+                .generate_page(&page, &plugin_data, &self.plugin_manager, &plugin_ctx)?;
+        println!("   ✓ {}", output_path.display());
+
+        Ok(true)
+    }
+
+    /// Stage 4: everything generated from metadata rather than a single source
+    /// file: index, category and tag pages, feeds, search index, sitemap and robots.txt
+    pub fn generate_listings(&mut self) -> Result<()> {
+        let posts_dir = Path::new(&self.config.build.content_dir);
+        let mut written = Vec::new();
+
+        // Every language gets its own indices and feeds under its prefix
+        let scopes = self.language_scopes();
+        for language in self.config.languages() {
+            let config = self.config.localized(&language);
+            let metadata = scopes.get(&language.code).unwrap_or(&self.metadata);
+            let language_dir = Path::new(&config.build.output_dir);
+
+            written.extend(
+                IndexGenerator::new(config.clone())?
+                    .with_language(self.generator.language_context(&language.code))
+                    .generate_all(metadata, &self.plugin_manager)?,
+            );
+
+            println!("📄 Generating feeds...");
+            written.extend(FeedGenerator::generate_all_feeds(
+                &config,
+                metadata,
+                posts_dir,
+                language_dir,
+            )?);
+        }
+
+        // A tag, series, page or feed that only withheld posts (e.g. drafts from
+        // a `--drafts` preview) filled is not written again
+        let output_dir = Path::new(&self.config.build.output_dir);
+        let listings = Listings::new(output_dir, written);
+        self.listings.remove_stale(&listings, output_dir)?;
+        self.listings = listings;
+
+        self.generate_search_index()?;
+        self.generate_sitemap()
+    }
+
+    fn generate_search_index(&self) -> Result<()> {
+        if self.config.build.search.enabled {
+            let search_generator = SearchIndexGenerator::new(self.config.clone());
+            search_generator.generate(&self.metadata)?;
+        }
+        Ok(())
+    }
+
+    /// The sitemap, which lists pages as well as posts, and robots.txt
+    fn generate_sitemap(&self) -> Result<()> {
+        let output_dir = Path::new(&self.config.build.output_dir);
+
+        if self.config.build.sitemap.enabled {
+            println!("🗺️  Generating sitemap...");
+            let url_count = SitemapGenerator::generate(
+                &self.config,
+                &self.metadata,
+                Path::new(&self.config.build.pages_dir),
+                output_dir,
+            )?;
+            println!("   ✓ {} URLs", url_count);
+        }
+
+        if self.config.build.robots.enabled {
+            SitemapGenerator::generate_robots(&self.config, output_dir)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stage 5: syntax highlighting CSS, content images and static files
+    pub fn write_assets(&self) -> Result<()> {
+        println!("🎨 Generating syntax highlighting CSS...");
+        let css_dir = Path::new(&self.config.build.output_dir).join("css");
+        std::fs::create_dir_all(&css_dir)?;
+        self.renderer.write_syntax_css(css_dir.join("syntax.css"))?;
+
+        self.generator.copy_content_assets()?;
+        self.generator.copy_static_assets()?;
+
+        Ok(())
+    }
+
+    /// Stage 6: let plugins see (and add to) the finished output
+    pub fn finish(&self) -> Result<()> {
+        let plugin_ctx = PluginContext {
+            config: &self.config,
+            metadata: &self.metadata,
+        };
+        self.plugin_manager
+            .on_build_finished(Path::new(&self.config.build.output_dir), &plugin_ctx)
+    }
+}
+
+/// Fingerprint of the frontmatter fields that decide where a post is listed
+/// and linked from; the title, description, image and modified date only
+/// change how it is shown there
+fn placement(frontmatter: &Frontmatter) -> String {
+    let mut placement = frontmatter.clone();
+    placement.title.clear();
+    placement.description = None;
+    placement.featured_image = None;
+    placement.date.modified = None;
+    fingerprint(&placement)
+}
+
+/// Markdown sources under the content directory, in a stable order
+fn collect_post_files(posts_dir: &Path) -> Vec<PathBuf> {
+    WalkDir::new(posts_dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
+        .map(|e| e.path().to_path_buf())
+        .collect()
+}
+
+/// Parse a post and measure its length
+fn parse_post(path: &Path, config: &SsgConfig) -> Result<Post> {
+    let mut post = Parser::parse_file(path, Path::new(&config.build.content_dir))?;
+    if let Some(lang) = &post.frontmatter.lang {
+        if config.language(lang).is_none() {
+            anyhow::bail!(
+                "Unknown language '{}' in {}; add it to site.languages in config.yaml",
+                lang,
+                path.display()
+            );
+        }
+    }
+    post.stats = ReadingStats::from_markdown(&post.content, &config.build.reading);
+    Ok(post)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FeedFormat;
+    use crate::plugin::Plugin;
+    use crate::site::{Site, SiteBuilder};
+    use crate::slug;
+    use crate::watch::ChangeSet;
+    use std::collections::BTreeMap;
+    use std::fs;
+    use std::sync::{Arc, Mutex};
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn post(title: &str, date: &str, tags: &str, extra: &str, body: &str) -> String {
+        format!(
+            "---\ntitle: \"{}\"\ndate: {}\ntags: [{}]\n{}---\n\n{}\n",
+            title, date, tags, extra, body
+        )
+    }
+
+    fn read_tree(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+        WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_file())
+            .map(|e| {
+                let relative = e.path().strip_prefix(dir).unwrap().to_path_buf();
+                (relative, fs::read(e.path()).unwrap())
+            })
+            .collect()
+    }
+
+    fn build(config: &SsgConfig, parallel: bool) {
+        run_stages(Site::builder(config.clone()).with_parallel(parallel));
+    }
+
+    fn run_stages(builder: SiteBuilder) {
+        let mut pipeline = builder.into_pipeline().unwrap();
+
+        let post_files = pipeline.collect().unwrap();
+        pipeline
+            .build_posts(&post_files, &BuildProgress::new())
+            .unwrap();
+        pipeline.build_pages().unwrap();
+        pipeline.generate_listings().unwrap();
+        pipeline.save().unwrap();
+        pipeline.write_assets().unwrap();
+        pipeline.finish().unwrap();
+    }
+
+    #[test]
+    fn test_sequential_and_parallel_builds_match() {
+        let root = tempfile::tempdir().unwrap();
+        let posts = root.path().join("posts");
+        let same_day = "2024-03-01T09:00:00Z";
+
+        for i in 0..6 {
+            write(
+                &posts.join(format!("dev/post-{}.md", i)),
+                &post(
+                    &format!("Post {}", i),
+                    same_day,
+                    "rust, web",
+                    "",
+                    &format!(
+                        "## Heading\n\nBody of post {}.\n\n<!-- more -->\n\nRest.",
+                        i
+                    ),
+                ),
+            );
+        }
+        write(
+            &posts.join("chat/hello.md"),
+            &post(
+                "안녕하세요",
+                "2024-02-01T09:00:00Z",
+                "rust",
+                "",
+                "한국어 본문입니다.",
+            ),
+        );
+        write(
+            &posts.join("chat/draft.md"),
+            &post("Draft", same_day, "secret", "draft: true\n", "Not yet."),
+        );
+
+        let mut config = SsgConfig::default();
+        config.build.content_dir = posts.to_string_lossy().to_string();
+        config.build.cache_dir = root.path().join("cache").to_string_lossy().to_string();
+        config.build.search.full_text = true;
+        config.build.feeds.formats = vec![FeedFormat::Rss, FeedFormat::Atom, FeedFormat::Json];
+
+        let sequential = root.path().join("sequential");
+        config.build.output_dir = sequential.to_string_lossy().to_string();
+        build(&config, false);
+
+        let parallel = root.path().join("parallel");
+        config.build.output_dir = parallel.to_string_lossy().to_string();
+        build(&config, true);
+
+        let sequential = read_tree(&sequential);
+        let parallel = read_tree(&parallel);
+
+        assert!(sequential.contains_key(Path::new("dev/post-0/index.html")));
+        assert!(sequential.contains_key(Path::new("tags/rust/feed.xml")));
+        assert!(!sequential.contains_key(Path::new("chat/draft/index.html")));
+        assert_eq!(
+            sequential.keys().collect::<Vec<_>>(),
+            parallel.keys().collect::<Vec<_>>()
+        );
+        for (path, contents) in &sequential {
+            assert!(
+                contents == &parallel[path],
+                "{} differs between sequential and parallel builds",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_category_feeds_follow_category_pages() {
+        let root = tempfile::tempdir().unwrap();
+        let posts = root.path().join("posts");
+        let output = root.path().join("dist");
+        write(
+            &posts.join("개발/hello.md"),
+            &post("Hello", "2024-01-01T00:00:00Z", "", "", "Body."),
+        );
+        write(&posts.join("개발/.category.yaml"), "name: Development\n");
+
+        let mut config = SsgConfig::default();
+        config.build.content_dir = posts.to_string_lossy().to_string();
+        config.build.cache_dir = root.path().join("cache").to_string_lossy().to_string();
+        config.build.output_dir = output.to_string_lossy().to_string();
+        config.build.encode_filenames = true;
+        build(&config, false);
+
+        // The feed sits next to the category page, and is linked by the same title
+        let category_dir = output.join(slug::encode_for_url("개발"));
+        assert!(category_dir.join("index.html").is_file());
+        assert!(category_dir.join("feed.xml").is_file());
+
+        let feed_link = "title=\"marshallku blog - Development\"";
+        let page = fs::read_to_string(category_dir.join("index.html")).unwrap();
+        assert!(page.contains(feed_link));
+        let post = fs::read_to_string(category_dir.join("hello/index.html")).unwrap();
+        assert!(post.contains(feed_link));
+    }
+
+    #[test]
+    fn test_search_index_uses_rendered_text() {
+        let root = tempfile::tempdir().unwrap();
+        let posts = root.path().join("posts");
+        let output = root.path().join("dist");
+        write(
+            &posts.join("dev/hello.md"),
+            &post(
+                "Hello",
+                "2024-01-01T00:00:00Z",
+                "",
+                "",
+                "Say [upper]quietly[/upper] and **boldly**.",
+            ),
+        );
+
+        let mut config = SsgConfig::default();
+        config.build.content_dir = posts.to_string_lossy().to_string();
+        config.build.cache_dir = root.path().join("cache").to_string_lossy().to_string();
+        config.build.output_dir = output.to_string_lossy().to_string();
+        config.build.search.full_text = true;
+        config.build.search.shards = 3;
+        let builder = |config: &SsgConfig| {
+            Site::builder(config.clone()).with_shortcode("upper", |_, content| {
+                Ok(content.unwrap_or("").to_uppercase())
+            })
+        };
+        run_stages(builder(&config));
+
+        let index: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output.join("search-index.json")).unwrap())
+                .unwrap();
+        assert_eq!(index["posts"][0]["body"], "Say QUIETLY and boldly.");
+        assert!(output.join("search-index-2.json").is_file());
+
+        // Fewer shards leave none of the old ones behind
+        config.build.search.shards = 1;
+        run_stages(builder(&config));
+        assert!(!output.join("search-index-0.json").exists());
+        assert!(!output.join("search-index-2.json").exists());
+    }
+
+    #[test]
+    fn test_targeted_rebuilds_match_full_build() {
+        let root = tempfile::tempdir().unwrap();
+        let posts = root.path().join("posts");
+        let sources: Vec<PathBuf> = (0..4)
+            .map(|i| posts.join(format!("dev/post-{}.md", i)))
+            .collect();
+        let body = |i: usize, intro: &str, rest: &str| {
+            post(
+                &format!("Post {}", i),
+                &format!("2024-03-0{}T09:00:00Z", i + 1),
+                "rust, web",
+                "",
+                &format!("{}\n\n<!-- more -->\n\n{}", intro, rest),
+            )
+        };
+        for (i, source) in sources.iter().enumerate() {
+            write(source, &body(i, "Intro.", "Rest."));
+        }
+        // Same slug, another category: edits must not reach it
+        write(
+            &posts.join("chat/post-1.md"),
+            &post("Chat 1", "2024-02-01T09:00:00Z", "rust, web", "", "Chat."),
+        );
+
+        let mut config = SsgConfig::default();
+        config.build.content_dir = posts.to_string_lossy().to_string();
+        config.build.cache_dir = root.path().join("cache").to_string_lossy().to_string();
+        config.build.search.full_text = true;
+        config.build.output_dir = root.path().join("watch").to_string_lossy().to_string();
+
+        let mut pipeline = Site::builder(config.clone()).into_pipeline().unwrap();
+        let post_files = pipeline.collect().unwrap();
+        pipeline
+            .build_posts(&post_files, &BuildProgress::new())
+            .unwrap();
+        pipeline.generate_listings().unwrap();
+        pipeline.write_assets().unwrap();
+
+        let matches_full_build = |name: &str| {
+            let mut config = config.clone();
+            config.build.output_dir = root.path().join(name).to_string_lossy().to_string();
+            build(&config, false);
+            assert_eq!(
+                read_tree(&root.path().join("watch")),
+                read_tree(&root.path().join(name))
+            );
+        };
+        let changes = |path: &Path| {
+            let mut changes = ChangeSet::new();
+            assert!(changes.add(path, &config));
+            changes
+        };
+
+        // Only the body below the excerpt changed, not its length: the post
+        // alone is rendered, then its feeds and the search index
+        write(&sources[1], &body(1, "Intro.", "Done."));
+        let report = pipeline
+            .apply_changes(&changes(&sources[1]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.built, 1);
+        matches_full_build("body");
+
+        // A new excerpt and length also reach the listings and the posts linking to it
+        write(
+            &sources[1],
+            &body(1, "A new intro.", "Rewritten rest, searchable."),
+        );
+        let report = pipeline
+            .apply_changes(&changes(&sources[1]))
+            .unwrap()
+            .unwrap();
+        assert!(report.built > 1);
+        matches_full_build("excerpt");
+
+        // A new title only changes how the post is shown where it is listed
+        let renamed =
+            body(1, "A new intro.", "Rewritten rest, searchable.").replace("Post 1", "Renamed");
+        write(&sources[1], &renamed);
+        let report = pipeline
+            .apply_changes(&changes(&sources[1]))
+            .unwrap()
+            .unwrap();
+        assert!(report.built > 1);
+        matches_full_build("title");
+
+        // New tags can move posts around, which takes a full build
+        write(&sources[1], &renamed.replace("rust, web", "rust"));
+        assert!(pipeline
+            .apply_changes(&changes(&sources[1]))
+            .unwrap()
+            .is_none());
+    }
+
+    /// Records the order hooks run in and writes a redirects file
+    struct Lifecycle {
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Lifecycle {
+        fn record(&self, event: String) {
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    impl Plugin for Lifecycle {
+        fn name(&self) -> &str {
+            "lifecycle"
+        }
+
+        fn on_build_start(&self, ctx: &PluginContext) -> Result<()> {
+            assert!(ctx.metadata.posts.is_empty());
+            self.record("start".to_string());
+            Ok(())
+        }
+
+        fn on_metadata_ready(
+            &self,
+            metadata: &mut MetadataCache,
+            _config: &SsgConfig,
+        ) -> Result<()> {
+            for post in &mut metadata.posts {
+                post.frontmatter.title = post.frontmatter.title.to_uppercase();
+            }
+            self.record("metadata".to_string());
+            Ok(())
+        }
+
+        fn on_page_generated(
+            &self,
+            path: &Path,
+            html: &mut String,
+            _ctx: &PluginContext,
+        ) -> Result<()> {
+            html.push_str("<!-- lifecycle -->");
+            self.record(format!("page {}", path.display()));
+            Ok(())
+        }
+
+        fn on_build_finished(&self, output_dir: &Path, ctx: &PluginContext) -> Result<()> {
+            assert!(output_dir.join("index.html").exists());
+            assert!(ctx.emit_file("../escape", "no").is_err());
+
+            let redirects: String = ctx
+                .metadata
+                .posts
+                .iter()
+                .map(|post| format!("/old/{} /{}/{}/ 301\n", post.slug, post.category, post.slug))
+                .collect();
+            ctx.emit_file("_redirects", redirects)?;
+            self.record("finished".to_string());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_lifecycle_hooks_and_emitted_files() {
+        let root = tempfile::tempdir().unwrap();
+        let posts = root.path().join("posts");
+        let output = root.path().join("dist");
+        write(
+            &posts.join("dev/hello.md"),
+            &post("Hello", "2024-03-01T09:00:00Z", "rust", "", "Hi."),
+        );
+
+        let mut config = SsgConfig::default();
+        config.build.content_dir = posts.to_string_lossy().to_string();
+        config.build.cache_dir = root.path().join("cache").to_string_lossy().to_string();
+        config.build.output_dir = output.to_string_lossy().to_string();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut pipeline = Site::builder(config)
+            .with_plugin(Lifecycle {
+                events: Arc::clone(&events),
+            })
+            .into_pipeline()
+            .unwrap();
+        let post_files = pipeline.collect().unwrap();
+        pipeline
+            .build_posts(&post_files, &BuildProgress::new())
+            .unwrap();
+        pipeline.build_pages().unwrap();
+        pipeline.generate_listings().unwrap();
+        pipeline.write_assets().unwrap();
+        pipeline.finish().unwrap();
+
+        let post_page = format!("page {}", output.join("dev/hello/index.html").display());
+        let homepage = format!("page {}", output.join("index.html").display());
+        let events = events.lock().unwrap();
+        let position = |event: &str| events.iter().position(|e| e == event).unwrap();
+        assert_eq!(position("start"), 0);
+        assert!(position(&post_page) < position("metadata"));
+        assert!(position("metadata") < position(&homepage));
+        assert_eq!(position("finished"), events.len() - 1);
+
+        let homepage = fs::read_to_string(output.join("index.html")).unwrap();
+        assert!(homepage.contains("HELLO"));
+        assert!(homepage.ends_with("<!-- lifecycle -->"));
+        assert_eq!(
+            fs::read_to_string(output.join("_redirects")).unwrap(),
+            "/old/hello /dev/hello/ 301\n"
+        );
+        assert!(!root.path().join("escape").exists());
+    }
+
+    /// Paths of every output file that mentions `needle`
+    fn mentions(tree: &BTreeMap<PathBuf, Vec<u8>>, needle: &str) -> Vec<PathBuf> {
+        tree.iter()
+            .filter(|(_, contents)| String::from_utf8_lossy(contents).contains(needle))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_scheduled_and_expired_posts() {
+        let root = tempfile::tempdir().unwrap();
+        let posts = root.path().join("posts");
+        let output = root.path().join("dist");
+        write(
+            &posts.join("dev/live.md"),
+            &post("Live", "2024-03-01T09:00:00Z", "rust", "", "Out now."),
+        );
+        write(
+            &posts.join("dev/upcoming-launch.md"),
+            &post("Upcoming", "2999-01-01T00:00:00Z", "rust", "", "Soon."),
+        );
+        write(
+            &posts.join("dev/old-promo.md"),
+            &post(
+                "Promo",
+                "2024-01-01T09:00:00Z",
+                "rust",
+                "expires: 2024-02-01T00:00:00Z\n",
+                "Gone.",
+            ),
+        );
+
+        let mut config = SsgConfig::default();
+        config.build.content_dir = posts.to_string_lossy().to_string();
+        config.build.cache_dir = root.path().join("cache").to_string_lossy().to_string();
+        config.build.output_dir = output.to_string_lossy().to_string();
+        config.build.search.full_text = true;
+
+        run_stages(Site::builder(config.clone()).with_future(true));
+        let preview = read_tree(&output);
+        let upcoming = preview[Path::new("dev/upcoming-launch/index.html")].clone();
+        assert!(String::from_utf8_lossy(&upcoming).contains("preview-banner"));
+        assert!(preview.contains_key(Path::new("dev/old-promo/index.html")));
+        assert!(
+            !String::from_utf8_lossy(&preview[Path::new("dev/live/index.html")])
+                .contains("preview-banner")
+        );
+
+        // A regular build over the preview takes both posts down again
+        run_stages(Site::builder(config));
+        let published = read_tree(&output);
+        assert!(published.contains_key(Path::new("dev/live/index.html")));
+        let live = mentions(&published, "/dev/live");
+        for listing in ["index.html", "feed.xml", "search-index.json", "sitemap.xml"] {
+            assert!(
+                live.contains(&PathBuf::from(listing)),
+                "{} misses the live post",
+                listing
+            );
+        }
+        assert_eq!(
+            mentions(&published, "upcoming-launch"),
+            Vec::<PathBuf>::new()
+        );
+        assert_eq!(mentions(&published, "old-promo"), Vec::<PathBuf>::new());
+    }
+
+    #[test]
+    fn test_drafts_never_leak_unless_included() {
+        let root = tempfile::tempdir().unwrap();
+        let posts = root.path().join("posts");
+        let output = root.path().join("dist");
+        let series = "series: \"Guide\"\n";
+        write(
+            &posts.join("dev/public.md"),
+            &post(
+                "Public",
+                "2024-03-01T09:00:00Z",
+                "rust",
+                series,
+                "Rust ownership.",
+            ),
+        );
+        write(
+            &posts.join("dev/hidden-draft.md"),
+            &post(
+                "Hidden Draft",
+                "2024-03-02T09:00:00Z",
+                "rust, secret-tag",
+                &format!("draft: true\n{}", series),
+                "Rust ownership, classified.",
+            ),
+        );
+        write(
+            &posts.join("chat/other.md"),
+            &post("Other", "2024-02-01T09:00:00Z", "rust", "", "Elsewhere."),
+        );
+
+        let mut config = SsgConfig::default();
+        config.build.content_dir = posts.to_string_lossy().to_string();
+        config.build.cache_dir = root.path().join("cache").to_string_lossy().to_string();
+        config.build.output_dir = output.to_string_lossy().to_string();
+        config.build.search.full_text = true;
+        config.build.feeds.formats = vec![FeedFormat::Rss, FeedFormat::Atom, FeedFormat::Json];
+
+        let assert_no_draft = |tree: &BTreeMap<PathBuf, Vec<u8>>| {
+            for needle in ["hidden-draft", "Hidden Draft", "secret-tag", "classified"] {
+                assert_eq!(mentions(tree, needle), Vec::<PathBuf>::new(), "{}", needle);
+            }
+        };
+
+        for parallel in [false, true] {
+            build(&config, parallel);
+            assert_no_draft(&read_tree(&output));
+        }
+
+        // Preview: the draft reaches every generator, flagged as a draft
+        run_stages(Site::builder(config.clone()).with_drafts(true));
+        let preview = read_tree(&output);
+        let draft_page =
+            String::from_utf8_lossy(&preview[Path::new("dev/hidden-draft/index.html")]).to_string();
+        assert!(draft_page.contains("초안: 아직 발행되지 않은 글입니다"));
+        let listed = mentions(&preview, "hidden-draft");
+        for output in [
+            "index.html",
+            "dev/index.html",
+            "tag/rust/index.html",
+            "tag/secret-tag/index.html",
+            "series/guide/index.html",
+            "feed.xml",
+            "atom.xml",
+            "feed.json",
+            "dev/feed.xml",
+            "tags/rust/feed.xml",
+            "search-index.json",
+            // Navigation, series and related posts
+            "dev/public/index.html",
+        ] {
+            assert!(
+                listed.contains(&PathBuf::from(output)),
+                "{} misses the draft",
+                output
+            );
+        }
+        assert!(!listed.contains(&PathBuf::from("sitemap.xml")));
+
+        // A regular build over the preview leaves nothing of the draft behind
+        build(&config, true);
+        assert_no_draft(&read_tree(&output));
+
+        // Single-post builds follow the same rule
+        let mut pipeline = Site::builder(config.clone()).into_pipeline().unwrap();
+        let error = pipeline
+            .build_single_post(&posts.join("dev/hidden-draft.md"))
+            .unwrap_err();
+        assert!(error.to_string().contains("--drafts"));
+        let mut pipeline = Site::builder(config)
+            .with_drafts(true)
+            .into_pipeline()
+            .unwrap();
+        assert!(pipeline
+            .build_single_post(&posts.join("dev/hidden-draft.md"))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_rebuild_keeps_posts_under_listing_paths() {
+        let root = tempfile::tempdir().unwrap();
+        let posts = root.path().join("posts");
+        let output = root.path().join("public");
+
+        for (i, path) in ["dev/page.md", "dev/other.md", "series/intro.md"]
+            .iter()
+            .enumerate()
+        {
+            write(
+                &posts.join(path),
+                &post(
+                    &format!("Post {}", i),
+                    &format!("2024-03-0{}T09:00:00Z", i + 1),
+                    "rust",
+                    "",
+                    "Body.",
+                ),
+            );
+        }
+
+        let mut config = SsgConfig::default();
+        config.build.content_dir = posts.to_string_lossy().to_string();
+        config.build.cache_dir = root.path().join("cache").to_string_lossy().to_string();
+        config.build.output_dir = output.to_string_lossy().to_string();
+        config.build.posts_per_page = 1;
+        build(&config, false);
+        assert!(output.join("dev/page/2/index.html").exists());
+
+        // Fewer pages: the stale one goes, posts named like listings stay
+        config.build.posts_per_page = 10;
+        build(&config, false);
+        assert!(!output.join("dev/page/2").exists());
+        assert!(output.join("dev/page/index.html").exists());
+        assert!(output.join("series/intro/index.html").exists());
+        assert!(output.join("tag/rust/index.html").exists());
+    }
+
+    #[test]
+    fn test_multilingual_site() {
+        let root = tempfile::tempdir().unwrap();
+        let posts = root.path().join("posts");
+        let output = root.path().join("dist");
+        let day = |d: u32| format!("2024-03-0{}T09:00:00Z", d);
+        write(
+            &posts.join("dev/hello.md"),
+            &post("안녕", &day(1), "rust", "translation_key: hello\n", "본문"),
+        );
+        write(
+            &posts.join("dev/only-ko.md"),
+            &post("한국어만", &day(2), "rust", "", "본문"),
+        );
+        write(
+            &posts.join("chat/talk.md"),
+            &post("잡담", &day(3), "", "", "본문"),
+        );
+        write(
+            &posts.join("dev/hello-en.md"),
+            &post(
+                "Hello",
+                &day(4),
+                "rust",
+                "lang: en\ntranslation_key: hello\n",
+                "Body",
+            ),
+        );
+        write(
+            &posts.join("dev/greeting.md"),
+            &post("인사", &day(5), "", "translation_key: greeting\n", "본문"),
+        );
+        write(
+            &posts.join("blog/greeting.md"),
+            &post(
+                "Greeting",
+                &day(6),
+                "",
+                "lang: en\ntranslation_key: greeting\n",
+                "Body",
+            ),
+        );
+
+        let mut config = SsgConfig::default();
+        config.build.content_dir = posts.to_string_lossy().to_string();
+        config.build.cache_dir = root.path().join("cache").to_string_lossy().to_string();
+        config.build.output_dir = output.to_string_lossy().to_string();
+        config.site.languages.insert(
+            "en".to_string(),
+            crate::config::LanguageConfig {
+                name: Some("English".to_string()),
+                locale: Some("en-US".to_string()),
+                title: Some("English blog".to_string()),
+                ..Default::default()
+            },
+        );
+
+        build(&config, false);
+        let tree = read_tree(&output);
+        let text = |path: &str| String::from_utf8_lossy(&tree[Path::new(path)]).to_string();
+
+        // Translations live under their language prefix, with their own listings
+        assert!(tree.contains_key(Path::new("en/dev/hello-en/index.html")));
+        assert!(!tree.contains_key(Path::new("dev/hello-en/index.html")));
+        assert!(tree.contains_key(Path::new("en/dev/index.html")));
+        assert!(!tree.contains_key(Path::new("en/chat/index.html")));
+        assert_eq!(
+            mentions(&tree, "hello-en")
+                .into_iter()
+                .filter(|path| !path.starts_with("en"))
+                .collect::<Vec<_>>(),
+            vec![
+                PathBuf::from("dev/hello/index.html"),
+                PathBuf::from("search-index.json"),
+                PathBuf::from("sitemap.xml"),
+            ]
+        );
+        assert!(text("search-index.json").contains("/en/dev/hello-en/"));
+        assert!(text("sitemap.xml").contains("https://marshallku.com/en/dev/hello-en/"));
+
+        // Navigation never crosses languages
+        assert!(!text("en/dev/hello-en/index.html").contains("only-ko"));
+        assert!(!text("en/index.html").contains("only-ko"));
+
+        // Feeds carry the locale of their language
+        assert!(text("en/feed.xml").contains("<language>en-US</language>"));
+        assert!(text("en/feed.xml").contains("https://marshallku.com/en/dev/hello-en"));
+        assert!(text("feed.xml").contains("<language>ko-KR</language>"));
+
+        // Translations link to each other, and UI strings follow the language
+        let hello = text("dev/hello/index.html");
+        assert!(hello.contains(r#"rel="alternate" hreflang="en-US""#));
+        assert!(hello.contains(r#"hreflang="ko-KR""#));
+        assert!(hello.contains(r#"<html lang="ko-KR">"#));
+        assert!(text("en/dev/hello-en/index.html").contains(r#"<html lang="en-US">"#));
+        assert!(!text("dev/only-ko/index.html").contains(r#"rel="alternate" hreflang"#));
+
+        // A translation sharing the slug is still linked as the other language
+        let translations = |path: &str| {
+            let html = text(path);
+            let start = html.find(r#"<p class="translations">"#).unwrap();
+            let end = start + html[start..].find("</p>").unwrap();
+            html[start..end].to_string()
+        };
+        let greeting = translations("dev/greeting/index.html");
+        assert!(greeting.contains(r#"lang="en-US">English</a>"#));
+        assert!(!greeting.contains(r#"lang="ko-KR""#));
+        let greeting = translations("en/blog/greeting/index.html");
+        assert!(greeting.contains(r#"lang="ko-KR">"#));
+        assert!(!greeting.contains(r#"lang="en-US""#));
+
+        // A language missing from config.yaml is an error
+        write(
+            &posts.join("dev/bonjour.md"),
+            &post("Bonjour", &day(5), "", "lang: fr\n", "Corps"),
+        );
+        let mut pipeline = Site::builder(config).into_pipeline().unwrap();
+        let error = pipeline
+            .build_single_post(&posts.join("dev/bonjour.md"))
+            .unwrap_err();
+        assert!(error.to_string().starts_with("Unknown language 'fr'"));
+    }
+
+    #[test]
+    fn test_incremental_build_refreshes_related_scores() {
+        let root = tempfile::tempdir().unwrap();
+        let posts = root.path().join("posts");
+        let output = root.path().join("dist");
+        let date = "2024-03-01T09:00:00Z";
+        write(
+            &posts.join("dev/a.md"),
+            &post("A", date, "rust, web", "", "A."),
+        );
+        write(
+            &posts.join("dev/b.md"),
+            &post("B", date, "rust, go", "", "B."),
+        );
+        write(&posts.join("ops/d.md"), &post("D", date, "k8s", "", "D."));
+
+        let mut config = SsgConfig::default();
+        config.build.content_dir = posts.to_string_lossy().to_string();
+        config.build.cache_dir = root.path().join("cache").to_string_lossy().to_string();
+        config.build.output_dir = output.to_string_lossy().to_string();
+
+        let build = || {
+            Site::builder(config.clone())
+                .with_incremental(true)
+                .init()
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+        let score = || {
+            let html = fs::read_to_string(output.join("dev/a/index.html")).unwrap();
+            let start = html.find("data-score=\"").unwrap() + "data-score=\"".len();
+            html[start..start + html[start..].find('"').unwrap()].to_string()
+        };
+
+        build();
+        build();
+        let before = score();
+
+        // A new post elsewhere makes "go" more common, and "rust" rarer, site-wide;
+        // A's related posts stay the same, but not their scores
+        write(&posts.join("ops/c.md"), &post("C", date, "go", "", "C."));
+        let report = build();
+
+        assert_ne!(score(), before);
+        assert!(report
+            .posts
+            .iter()
+            .any(|outcome| outcome.source == posts.join("dev/a.md")
+                && matches!(outcome.status, PostStatus::Built { .. })));
+    }
+}
@@ -2,17 +2,43 @@ use crate::config::SsgConfig;
 use crate::metadata::MetadataCache;
 use crate::shortcodes::ShortcodeRegistry;
 use crate::types::{Page, Post};
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
 
 /// Context provided to plugins during execution
 pub struct PluginContext<'a> {
-    #[allow(unused)]
     pub config: &'a SsgConfig,
     pub metadata: &'a MetadataCache,
 }
 
+impl PluginContext<'_> {
+    /// Write a file into the output directory, e.g. `_redirects` or
+    /// `old-post/index.html`. `path` must be relative and stay inside it.
+    pub fn emit_file(&self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<PathBuf> {
+        let path = path.as_ref();
+        let inside = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !inside || path.as_os_str().is_empty() {
+            bail!(
+                "Plugin output path '{}' must be relative to the output directory",
+                path.display()
+            );
+        }
+
+        let output_path = Path::new(&self.config.build.output_dir).join(path);
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&output_path, contents)
+            .with_context(|| format!("Failed to write {}", output_path.display()))?;
+        Ok(output_path)
+    }
+}
+
 /// Plugin trait for extending ssdocs functionality
 pub trait Plugin: Send + Sync {
     /// Plugin name (must be unique)
@@ -23,6 +49,17 @@ pub trait Plugin: Send + Sync {
         Ok(())
     }
 
+    /// Hook: Run before any content is read; `ctx.metadata` is still empty
+    fn on_build_start(&self, _ctx: &PluginContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Hook: Inspect or change the metadata of every rendered post before
+    /// listings, feeds and the search index are generated from it
+    fn on_metadata_ready(&self, _metadata: &mut MetadataCache, _config: &SsgConfig) -> Result<()> {
+        Ok(())
+    }
+
     /// Hook: Modify post content after parsing but before rendering
     fn on_post_parsed(&self, _post: &mut Post, _ctx: &PluginContext) -> Result<()> {
         Ok(())
@@ -61,8 +98,30 @@ pub trait Plugin: Send + Sync {
         Ok(HashMap::new())
     }
 
+    /// Hook: Modify a generated HTML page (post, page or index) before it is written
+    fn on_page_generated(
+        &self,
+        _path: &Path,
+        _html: &mut String,
+        _ctx: &PluginContext,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Hook: Run after every output file has been written
+    fn on_build_finished(&self, _output_dir: &Path, _ctx: &PluginContext) -> Result<()> {
+        Ok(())
+    }
+
     /// Hook: Register custom shortcodes
     fn register_shortcodes(&self, _registry: &mut ShortcodeRegistry) {}
+
+    /// Whether the context this plugin adds to a post reads other posts'
+    /// bodies, so that editing one body can change other posts' pages.
+    /// Watch mode then rebuilds the whole site after such edits.
+    fn reads_post_bodies(&self) -> bool {
+        false
+    }
 }
 
 /// Plugin manager for loading and executing plugins
@@ -82,14 +141,37 @@ impl PluginManager {
         self.plugins.push(plugin);
     }
 
-    /// Initialize all plugins
+    /// Check the `plugins` section of the config against the registered
+    /// plugins, drop the disabled ones and initialize the rest
     pub fn init_all(&mut self, config: &SsgConfig) -> Result<()> {
+        let names = self.list_plugins();
+        for name in config.plugins.keys() {
+            if !names.contains(name) {
+                bail!(
+                    "Unknown plugin '{}' in config.yaml (available: {})",
+                    name,
+                    names.join(", ")
+                );
+            }
+        }
+
+        self.plugins
+            .retain(|plugin| config.plugin_enabled(plugin.name()));
+
         for plugin in &mut self.plugins {
-            plugin.init(config)?;
+            let name = plugin.name().to_string();
+            plugin
+                .init(config)
+                .with_context(|| format!("Failed to initialize plugin '{}'", name))?;
         }
         Ok(())
     }
 
+    /// Whether any plugin reads other posts' bodies
+    pub fn reads_post_bodies(&self) -> bool {
+        self.plugins.iter().any(|plugin| plugin.reads_post_bodies())
+    }
+
     /// Register shortcodes from all plugins
     pub fn register_shortcodes(&self, registry: &mut ShortcodeRegistry) {
         for plugin in &self.plugins {
@@ -97,6 +179,26 @@ impl PluginManager {
         }
     }
 
+    /// Execute on_build_start hooks
+    pub fn on_build_start(&self, ctx: &PluginContext) -> Result<()> {
+        for plugin in &self.plugins {
+            plugin.on_build_start(ctx)?;
+        }
+        Ok(())
+    }
+
+    /// Execute on_metadata_ready hooks
+    pub fn on_metadata_ready(
+        &self,
+        metadata: &mut MetadataCache,
+        config: &SsgConfig,
+    ) -> Result<()> {
+        for plugin in &self.plugins {
+            plugin.on_metadata_ready(metadata, config)?;
+        }
+        Ok(())
+    }
+
     /// Execute on_post_parsed hooks
     pub fn on_post_parsed(&self, post: &mut Post, ctx: &PluginContext) -> Result<()> {
         for plugin in &self.plugins {
@@ -118,6 +220,27 @@ impl PluginManager {
         Ok(())
     }
 
+    /// Execute on_page_generated hooks over a page, then write it to `path`
+    pub fn write_page(&self, path: &Path, mut html: String, ctx: &PluginContext) -> Result<()> {
+        for plugin in &self.plugins {
+            plugin.on_page_generated(path, &mut html, ctx)?;
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, html)?;
+        Ok(())
+    }
+
+    /// Execute on_build_finished hooks
+    pub fn on_build_finished(&self, output_dir: &Path, ctx: &PluginContext) -> Result<()> {
+        for plugin in &self.plugins {
+            plugin.on_build_finished(output_dir, ctx)?;
+        }
+        Ok(())
+    }
+
     /// Collect template context from all plugins for posts
     pub fn template_context_post(
         &self,
@@ -214,4 +337,30 @@ mod tests {
         manager.register(plugin);
         assert_eq!(manager.list_plugins(), vec!["test"]);
     }
+
+    #[test]
+    fn test_init_all_applies_plugin_config() {
+        let manager = |names: &[&str]| {
+            let mut manager = PluginManager::new();
+            for name in names {
+                manager.register(Box::new(TestPlugin {
+                    name: name.to_string(),
+                }));
+            }
+            manager
+        };
+
+        let config: SsgConfig =
+            serde_yaml::from_str("plugins:\n  first:\n    enabled: false\n").unwrap();
+        let mut plugins = manager(&["first", "second"]);
+        plugins.init_all(&config).unwrap();
+        assert_eq!(plugins.list_plugins(), vec!["second"]);
+
+        let config: SsgConfig = serde_yaml::from_str("plugins:\n  third: {}\n").unwrap();
+        let error = manager(&["first", "second"]).init_all(&config).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown plugin 'third' in config.yaml (available: first, second)"
+        );
+    }
 }
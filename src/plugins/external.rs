@@ -0,0 +1,634 @@
+//! Plugins that run as a separate program.
+//!
+//! The program is started once per build and kept alive until the build is
+//! dropped. ssdocs writes one JSON request per line to its stdin and reads one
+//! JSON reply per line from its stdout:
+//!
+//! ```text
+//! → {"id": 1, "hook": "init", "protocol": 1, "name": "sample", "options": {...}, "config": {...}}
+//! ← {"id": 1, "result": {"hooks": ["on_post_rendered"], "shortcodes": ["upper"]}}
+//! → {"id": 2, "hook": "on_post_rendered", "post": {...}, "html": "<p>...</p>"}
+//! ← {"id": 2, "result": {"html": "<p>...</p>"}}
+//! ← {"id": 3, "error": "something went wrong"}
+//! ```
+//!
+//! Only the hooks listed in the `init` reply are called. A call that takes
+//! longer than the configured timeout stops the program and fails the build.
+//! See `examples/plugins/sample_plugin.py` for every hook.
+
+use crate::config::{PluginConfig, SsgConfig};
+use crate::metadata::MetadataCache;
+use crate::plugin::{Plugin, PluginContext};
+use crate::shortcodes::ShortcodeRegistry;
+use crate::types::{Frontmatter, Page, Post};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value as JsonValue};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Version of the message format, sent with `init`
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Hooks an external plugin may ask for in its `init` reply
+const HOOKS: &[&str] = &[
+    "on_build_start",
+    "on_post_parsed",
+    "on_post_rendered",
+    "template_context_post",
+    "template_context_page",
+    "template_context_index",
+    "on_metadata_ready",
+    "on_page_generated",
+    "on_build_finished",
+];
+
+/// How long a plugin gets to exit on its own once its stdin is closed
+const EXIT_GRACE: Duration = Duration::from_secs(1);
+
+/// A plugin implemented by an external program, configured with
+/// `plugins.<name>.command` in config.yaml
+pub struct ExternalPlugin {
+    name: String,
+    command: Vec<String>,
+    timeout: Duration,
+    process: Option<Arc<PluginProcess>>,
+    hooks: HashSet<String>,
+    shortcodes: Vec<String>,
+}
+
+/// Reply to `init`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Handshake {
+    #[serde(default)]
+    hooks: Vec<String>,
+    #[serde(default)]
+    shortcodes: Vec<String>,
+}
+
+/// Changes to a post returned by `on_post_parsed` and `on_post_rendered`
+#[derive(Debug, Default, Deserialize)]
+struct PostChanges {
+    frontmatter: Option<Frontmatter>,
+    content: Option<String>,
+    html: Option<String>,
+}
+
+impl PostChanges {
+    fn apply(self, post: &mut Post) {
+        if let Some(frontmatter) = self.frontmatter {
+            post.frontmatter = frontmatter;
+        }
+        if let Some(content) = self.content {
+            post.content = content;
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PageChanges {
+    html: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MetadataChanges {
+    metadata: Option<MetadataCache>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EmittedFiles {
+    #[serde(default)]
+    files: Vec<EmittedFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmittedFile {
+    path: String,
+    contents: String,
+}
+
+impl ExternalPlugin {
+    pub fn new(name: &str, config: &PluginConfig) -> Result<Self> {
+        let command = config.command.clone().unwrap_or_default();
+        if command.is_empty() {
+            bail!("Plugin '{}' has an empty command", name);
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            command,
+            timeout: Duration::from_millis(config.timeout_ms),
+            process: None,
+            hooks: HashSet::new(),
+            shortcodes: Vec::new(),
+        })
+    }
+
+    /// Send a request if the plugin asked for `hook`, decoding the reply's result
+    fn call<T: for<'de> Deserialize<'de> + Default>(
+        &self,
+        hook: &str,
+        request: JsonValue,
+    ) -> Result<T> {
+        if !self.hooks.contains(hook) {
+            return Ok(T::default());
+        }
+        let process = self
+            .process
+            .as_ref()
+            .ok_or_else(|| anyhow!("Plugin '{}' was not initialized", self.name))?;
+        decode(&self.name, hook, process.call(hook, request)?)
+    }
+}
+
+impl Plugin for ExternalPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn init(&mut self, config: &SsgConfig) -> Result<()> {
+        let process = Arc::new(PluginProcess::spawn(
+            &self.name,
+            &self.command,
+            self.timeout,
+        )?);
+
+        let options = config
+            .plugins
+            .get(&self.name)
+            .map(|plugin| serde_json::to_value(&plugin.options))
+            .transpose()?
+            .unwrap_or(JsonValue::Null);
+        let reply = process.call(
+            "init",
+            json!({
+                "protocol": PROTOCOL_VERSION,
+                "name": self.name,
+                "options": options,
+                "config": config,
+            }),
+        )?;
+        let handshake: Handshake = decode(&self.name, "init", reply)?;
+
+        if let Some(hook) = handshake
+            .hooks
+            .iter()
+            .find(|h| !HOOKS.contains(&h.as_str()))
+        {
+            bail!(
+                "Plugin '{}' asked for unknown hook '{}' (available: {})",
+                self.name,
+                hook,
+                HOOKS.join(", ")
+            );
+        }
+
+        self.hooks = handshake.hooks.into_iter().collect();
+        self.shortcodes = handshake.shortcodes;
+        self.process = Some(process);
+        Ok(())
+    }
+
+    fn on_build_start(&self, _ctx: &PluginContext) -> Result<()> {
+        self.call::<Option<JsonValue>>("on_build_start", json!({}))?;
+        Ok(())
+    }
+
+    fn on_metadata_ready(&self, metadata: &mut MetadataCache, _config: &SsgConfig) -> Result<()> {
+        let changes: MetadataChanges =
+            self.call("on_metadata_ready", json!({ "metadata": metadata }))?;
+        if let Some(changed) = changes.metadata {
+            *metadata = changed;
+        }
+        Ok(())
+    }
+
+    fn on_post_parsed(&self, post: &mut Post, _ctx: &PluginContext) -> Result<()> {
+        let changes: PostChanges = self.call("on_post_parsed", json!({ "post": post }))?;
+        changes.apply(post);
+        Ok(())
+    }
+
+    fn on_post_rendered(
+        &self,
+        post: &mut Post,
+        html: &mut String,
+        _ctx: &PluginContext,
+    ) -> Result<()> {
+        let mut changes: PostChanges =
+            self.call("on_post_rendered", json!({ "post": post, "html": html }))?;
+        if let Some(changed) = changes.html.take() {
+            *html = changed;
+        }
+        changes.apply(post);
+        Ok(())
+    }
+
+    fn template_context_post(
+        &self,
+        post: &Post,
+        _ctx: &PluginContext,
+    ) -> Result<HashMap<String, JsonValue>> {
+        self.call("template_context_post", json!({ "post": post }))
+    }
+
+    fn template_context_page(
+        &self,
+        page: &Page,
+        _ctx: &PluginContext,
+    ) -> Result<HashMap<String, JsonValue>> {
+        self.call(
+            "template_context_page",
+            json!({
+                "page": {
+                    "slug": page.slug,
+                    "frontmatter": page.frontmatter,
+                    "content": page.content,
+                }
+            }),
+        )
+    }
+
+    fn template_context_index(&self, _ctx: &PluginContext) -> Result<HashMap<String, JsonValue>> {
+        self.call("template_context_index", json!({}))
+    }
+
+    fn on_page_generated(
+        &self,
+        path: &Path,
+        html: &mut String,
+        _ctx: &PluginContext,
+    ) -> Result<()> {
+        let changes: PageChanges =
+            self.call("on_page_generated", json!({ "path": path, "html": html }))?;
+        if let Some(changed) = changes.html {
+            *html = changed;
+        }
+        Ok(())
+    }
+
+    fn on_build_finished(&self, output_dir: &Path, ctx: &PluginContext) -> Result<()> {
+        let emitted: EmittedFiles =
+            self.call("on_build_finished", json!({ "output_dir": output_dir }))?;
+        for file in emitted.files {
+            ctx.emit_file(&file.path, file.contents)?;
+        }
+        Ok(())
+    }
+
+    fn register_shortcodes(&self, registry: &mut ShortcodeRegistry) {
+        let Some(process) = &self.process else {
+            return;
+        };
+
+        for shortcode in &self.shortcodes {
+            let process = Arc::clone(process);
+            let name = shortcode.clone();
+            registry.register(
+                shortcode,
+                Box::new(move |attrs, content| {
+                    let reply = process.call(
+                        "shortcode",
+                        json!({ "name": name, "attrs": attrs, "content": content }),
+                    )?;
+                    reply
+                        .get("html")
+                        .and_then(JsonValue::as_str)
+                        .map(str::to_string)
+                        .ok_or_else(|| {
+                            anyhow!(
+                                "Plugin '{}' returned no html for shortcode '{}'",
+                                process.name,
+                                name
+                            )
+                        })
+                }),
+            );
+        }
+    }
+}
+
+fn decode<T: for<'de> Deserialize<'de> + Default>(
+    name: &str,
+    hook: &str,
+    result: JsonValue,
+) -> Result<T> {
+    if result.is_null() {
+        return Ok(T::default());
+    }
+    serde_json::from_value(result)
+        .with_context(|| format!("Plugin '{}' sent an invalid reply to {}", name, hook))
+}
+
+/// The running program; calls from worker threads take turns
+struct PluginProcess {
+    name: String,
+    timeout: Duration,
+    state: Mutex<ProcessState>,
+}
+
+struct ProcessState {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    /// Lines read from stdout by a reader thread, so reads can time out
+    replies: mpsc::Receiver<String>,
+    next_id: u64,
+    /// Set once the program timed out or exited; later calls fail with it
+    failure: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    id: u64,
+    #[serde(default)]
+    result: JsonValue,
+    error: Option<String>,
+}
+
+impl PluginProcess {
+    fn spawn(name: &str, command: &[String], timeout: Duration) -> Result<Self> {
+        let mut child = Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| {
+                format!("Failed to start plugin '{}' ({})", name, command.join(" "))
+            })?;
+
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (sender, replies) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            name: name.to_string(),
+            timeout,
+            state: Mutex::new(ProcessState {
+                child,
+                stdin,
+                replies,
+                next_id: 1,
+                failure: None,
+            }),
+        })
+    }
+
+    /// Send one request and wait for its reply's `result`
+    fn call(&self, hook: &str, mut request: JsonValue) -> Result<JsonValue> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(failure) = &state.failure {
+            bail!("Plugin '{}' is not running: {}", self.name, failure);
+        }
+
+        let id = state.next_id;
+        state.next_id += 1;
+        request["id"] = json!(id);
+        request["hook"] = json!(hook);
+
+        let mut line = serde_json::to_string(&request)?;
+        line.push('\n');
+        let sent = state
+            .stdin
+            .as_mut()
+            .map(|stdin| stdin.write_all(line.as_bytes()).and_then(|_| stdin.flush()));
+        if !matches!(sent, Some(Ok(()))) {
+            return Err(state.fail(&self.name, format!("exited before {}", hook)));
+        }
+
+        let reply = match state.replies.recv_timeout(self.timeout) {
+            Ok(reply) => reply,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                return Err(state.kill(
+                    &self.name,
+                    format!("timed out after {}ms in {}", self.timeout.as_millis(), hook),
+                ));
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(state.fail(&self.name, format!("exited during {}", hook)));
+            }
+        };
+
+        // Past a protocol error, replies can't be matched to requests anymore
+        let reply: Reply = match serde_json::from_str(&reply) {
+            Ok(reply) => reply,
+            Err(e) => {
+                return Err(state.kill(
+                    &self.name,
+                    format!(
+                        "wrote something other than a JSON reply to stdout during {}: {} ({})",
+                        hook, reply, e
+                    ),
+                ));
+            }
+        };
+        if reply.id != id {
+            return Err(state.kill(
+                &self.name,
+                format!(
+                    "answered request {} while {} was waiting for {}",
+                    reply.id, hook, id
+                ),
+            ));
+        }
+        if let Some(error) = reply.error {
+            bail!("Plugin '{}' failed in {}: {}", self.name, hook, error);
+        }
+        Ok(reply.result)
+    }
+}
+
+impl ProcessState {
+    fn fail(&mut self, name: &str, reason: String) -> anyhow::Error {
+        self.stdin = None;
+        self.failure = Some(reason.clone());
+        anyhow!("Plugin '{}' {}", name, reason)
+    }
+
+    /// Stop a process that can't be talked to anymore
+    fn kill(&mut self, name: &str, reason: String) -> anyhow::Error {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        self.fail(name, reason)
+    }
+}
+
+impl Drop for PluginProcess {
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap_or_else(|e| e.into_inner());
+
+        // Closing stdin is the signal to exit
+        state.stdin = None;
+        let deadline = Instant::now() + EXIT_GRACE;
+        while Instant::now() < deadline {
+            if !matches!(state.child.try_wait(), Ok(None)) {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let _ = state.child.kill();
+        let _ = state.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parallel::BuildProgress;
+    use crate::site::Site;
+    use std::fs;
+
+    const SAMPLE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/examples/plugins/sample_plugin.py"
+    );
+
+    fn site(root: &Path, options: &str, timeout_ms: u64) -> SsgConfig {
+        for (slug, title) in [("first", "First"), ("second", "Second")] {
+            let path = root.join(format!("posts/dev/{}.md", slug));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(
+                path,
+                format!(
+                    "---\ntitle: \"{}\"\ndate: 2024-01-01T00:00:00Z\ntags: [rust]\n---\n\n\
+                     Say [upper]hello[/upper].\n",
+                    title
+                ),
+            )
+            .unwrap();
+        }
+
+        let yaml = format!(
+            "plugins:\n  sample:\n    command: [\"python3\", \"{}\"]\n    timeout_ms: {}\n    \
+             options: {}\n",
+            SAMPLE, timeout_ms, options
+        );
+        let mut config: SsgConfig = serde_yaml::from_str(&yaml).unwrap();
+        config.build.content_dir = root.join("posts").to_string_lossy().to_string();
+        config.build.output_dir = root.join("dist").to_string_lossy().to_string();
+        config
+    }
+
+    fn python_available() -> bool {
+        Command::new("python3").arg("--version").output().is_ok()
+    }
+
+    #[test]
+    fn test_sample_plugin_conformance() {
+        if !python_available() {
+            eprintln!("python3 not found, skipping external plugin test");
+            return;
+        }
+        let root = tempfile::tempdir().unwrap();
+        let config = site(root.path(), "{tag: external}", 10_000);
+
+        let mut pipeline = Site::builder(config)
+            .with_parallel(true)
+            .into_pipeline()
+            .unwrap();
+        assert_eq!(pipeline.plugin_names(), vec!["related_posts", "sample"]);
+
+        let post_files = pipeline.collect().unwrap();
+        pipeline
+            .build_posts(&post_files, &BuildProgress::new())
+            .unwrap();
+        pipeline.generate_listings().unwrap();
+        pipeline.finish().unwrap();
+
+        let dist = root.path().join("dist");
+        let post = fs::read_to_string(dist.join("dev/first/index.html")).unwrap();
+        assert!(post.contains("Say HELLO."));
+        assert!(post.contains("<!-- rendered by sample plugin -->"));
+        assert!(post.contains("<!-- sample --></body>"));
+        assert!(dist.join("tag/external/index.html").exists());
+
+        // One process saw every post
+        assert_eq!(
+            fs::read_to_string(dist.join("sample-plugin.txt")).unwrap(),
+            "first\nsecond\n"
+        );
+    }
+
+    #[test]
+    fn test_timeout_stops_the_build() {
+        if !python_available() {
+            eprintln!("python3 not found, skipping external plugin test");
+            return;
+        }
+        let root = tempfile::tempdir().unwrap();
+        let config = site(root.path(), "{hang_on: on_post_rendered}", 300);
+
+        let mut site = Site::builder(config).init().unwrap();
+        let started = Instant::now();
+        let error = site
+            .build_post(&root.path().join("posts/dev/first.md"))
+            .unwrap_err();
+
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(
+            error.to_string(),
+            "Plugin 'sample' timed out after 300ms in on_post_rendered"
+        );
+        let error = site
+            .build_post(&root.path().join("posts/dev/second.md"))
+            .unwrap_err();
+        assert!(error.to_string().contains("is not running"));
+    }
+
+    #[test]
+    fn test_mismatched_reply_stops_the_plugin() {
+        if !python_available() {
+            eprintln!("python3 not found, skipping external plugin test");
+            return;
+        }
+        let root = tempfile::tempdir().unwrap();
+        let config = site(root.path(), "{wrong_id_on: on_post_rendered}", 10_000);
+
+        let mut site = Site::builder(config).init().unwrap();
+        let error = site
+            .build_post(&root.path().join("posts/dev/first.md"))
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("Plugin 'sample' answered request"));
+
+        // Later calls don't read replies meant for earlier ones
+        let error = site
+            .build_post(&root.path().join("posts/dev/second.md"))
+            .unwrap_err();
+        assert!(error.to_string().contains("is not running"));
+    }
+
+    #[test]
+    fn test_invalid_command() {
+        let mut config = SsgConfig::default();
+        config.plugins.insert(
+            "missing".to_string(),
+            PluginConfig {
+                command: Some(vec!["/nonexistent/plugin".to_string()]),
+                ..Default::default()
+            },
+        );
+        let error = Site::builder(config.clone()).init().err().unwrap();
+        assert!(format!("{:#}", error).contains("Failed to start plugin 'missing'"));
+
+        config.plugins.get_mut("missing").unwrap().command = Some(Vec::new());
+        let error = Site::builder(config).init().err().unwrap();
+        assert_eq!(error.to_string(), "Plugin 'missing' has an empty command");
+    }
+}
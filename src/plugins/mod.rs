@@ -1,3 +1,35 @@
+pub mod external;
 pub mod related_posts;
 
+pub use external::ExternalPlugin;
 pub use related_posts::RelatedPostsPlugin;
+
+use crate::config::SsgConfig;
+use crate::plugin::Plugin;
+use anyhow::{bail, Result};
+
+/// Plugins that ship with ssdocs, in the order they run
+pub fn builtin_plugins() -> Vec<Box<dyn Plugin>> {
+    vec![Box::new(RelatedPostsPlugin::new())]
+}
+
+/// Plugins with a `command` in config.yaml, in name order. Their programs
+/// are started when the plugins are initialized.
+pub fn external_plugins(config: &SsgConfig) -> Result<Vec<Box<dyn Plugin>>> {
+    let builtin: Vec<String> = builtin_plugins()
+        .iter()
+        .map(|plugin| plugin.name().to_string())
+        .collect();
+
+    let mut plugins: Vec<Box<dyn Plugin>> = Vec::new();
+    for (name, plugin_config) in &config.plugins {
+        if plugin_config.command.is_none() {
+            continue;
+        }
+        if builtin.contains(name) {
+            bail!("Plugin '{}' is built in and cannot have a command", name);
+        }
+        plugins.push(Box::new(ExternalPlugin::new(name, plugin_config)?));
+    }
+    Ok(plugins)
+}
@@ -1,17 +1,185 @@
+use crate::config::SsgConfig;
+use crate::metadata::{MetadataCache, PostMetadata};
+use crate::parser::Parser;
 use crate::plugin::{Plugin, PluginContext};
+use crate::renderer::Renderer;
+use crate::search::tokenizer::tokenize;
 use crate::types::Post;
-use anyhow::Result;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value as JsonValue};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use walkdir::WalkDir;
 
-/// Plugin that adds related posts to the template context
-pub struct RelatedPostsPlugin {
+/// Options from `plugins.related_posts.options` in config.yaml
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RelatedPostsOptions {
+    /// Maximum number of related posts (default: 3)
+    #[serde(default = "default_limit")]
     limit: usize,
+    /// Weight of shared tags, each weighted by how rare it is (default: 1.0)
+    #[serde(default = "default_tag_weight")]
+    tag_weight: f64,
+    /// Weight of being in the same category (default: 0.25)
+    #[serde(default = "default_category_weight")]
+    category_weight: f64,
+    /// Weight of shared body terms; 0 skips reading post bodies (default: 0)
+    #[serde(default)]
+    body_weight: f64,
+    /// Only posts scoring above this are related (default: 0)
+    #[serde(default)]
+    min_score: f64,
+}
+
+fn default_limit() -> usize {
+    3
+}
+
+fn default_tag_weight() -> f64 {
+    1.0
+}
+
+fn default_category_weight() -> f64 {
+    0.25
+}
+
+impl Default for RelatedPostsOptions {
+    fn default() -> Self {
+        Self {
+            limit: default_limit(),
+            tag_weight: default_tag_weight(),
+            category_weight: default_category_weight(),
+            body_weight: 0.0,
+            min_score: 0.0,
+        }
+    }
+}
+
+/// A related post as templates see it: the post's metadata plus why it matched
+#[derive(Debug, Serialize)]
+struct RelatedPost<'a> {
+    #[serde(flatten)]
+    post: &'a PostMetadata,
+    /// Weighted sum of the tag, category and body similarities
+    score: f64,
+    shared_tags: Vec<&'a str>,
+}
+
+/// Body terms of every post, keyed by (lang, category, slug)
+type BodyTerms = HashMap<(String, String, String), HashSet<String>>;
+
+/// Plugin that adds the posts most similar to the current one to the template context.
+///
+/// Similarity is the weighted sum of an IDF-weighted Jaccard index over tags,
+/// a same-category bonus and, optionally, a Jaccard index over body terms.
+/// Newer posts win ties.
+pub struct RelatedPostsPlugin {
+    options: RelatedPostsOptions,
+    /// Read once per build, and only when `body_weight` is set
+    body_terms: Mutex<Option<Arc<BodyTerms>>>,
 }
 
 impl RelatedPostsPlugin {
     pub fn new() -> Self {
-        Self { limit: 3 }
+        Self {
+            options: RelatedPostsOptions::default(),
+            body_terms: Mutex::new(None),
+        }
+    }
+
+    fn body_terms(&self, config: &SsgConfig) -> Arc<BodyTerms> {
+        let mut cached = self.body_terms.lock().unwrap_or_else(|e| e.into_inner());
+        let terms = cached.get_or_insert_with(|| {
+            let bodies =
+                collect_bodies(Path::new(&config.build.content_dir), &config.site.language);
+            let ngram = config.build.search.ngram;
+            Arc::new(
+                bodies
+                    .into_iter()
+                    .map(|(key, body)| {
+                        let terms = tokenize(&body, ngram).into_iter().map(|t| t.term);
+                        (key, terms.collect())
+                    })
+                    .collect(),
+            )
+        });
+        Arc::clone(terms)
+    }
+
+    /// Every other post scoring above `min_score`, best first
+    fn related<'a>(
+        &self,
+        post: &'a Post,
+        metadata: &'a MetadataCache,
+        body_terms: Option<&BodyTerms>,
+    ) -> Vec<RelatedPost<'a>> {
+        let idf = tag_idf(metadata);
+        let tags: HashSet<&str> = post.frontmatter.tags.iter().map(String::as_str).collect();
+        let lang = post
+            .frontmatter
+            .lang
+            .clone()
+            .unwrap_or_else(|| metadata.default_language.clone());
+        let body = body_terms
+            .and_then(|terms| terms.get(&(lang, post.category.clone(), post.slug.clone())));
+
+        let mut related: Vec<RelatedPost> = metadata
+            .posts
+            .iter()
+            .filter(|other| !(other.slug == post.slug && other.category == post.category))
+            .filter_map(|other| {
+                let other_tags: HashSet<&str> =
+                    other.frontmatter.tags.iter().map(String::as_str).collect();
+                let mut shared_tags: Vec<&str> = other_tags.intersection(&tags).copied().collect();
+                shared_tags.sort_unstable();
+
+                let idf_of = |tag: &str| idf.get(tag).copied().unwrap_or(0.0);
+                let union: f64 = tags.union(&other_tags).map(|tag| idf_of(tag)).sum();
+                let shared: f64 = shared_tags.iter().map(|tag| idf_of(tag)).sum();
+                let tag_similarity = if union > 0.0 { shared / union } else { 0.0 };
+
+                let same_category = if other.category == post.category {
+                    1.0
+                } else {
+                    0.0
+                };
+
+                let body_similarity = match (body, body_terms) {
+                    (Some(body), Some(terms)) => terms
+                        .get(&(
+                            other.lang.clone(),
+                            other.category.clone(),
+                            other.slug.clone(),
+                        ))
+                        .map_or(0.0, |other_body| jaccard(body, other_body)),
+                    _ => 0.0,
+                };
+
+                let score = self.options.tag_weight * tag_similarity
+                    + self.options.category_weight * same_category
+                    + self.options.body_weight * body_similarity;
+                // Rounded so scores read well in templates and sort stably
+                let score = (score * 1000.0).round() / 1000.0;
+
+                (score > self.options.min_score).then_some(RelatedPost {
+                    post: other,
+                    score,
+                    shared_tags,
+                })
+            })
+            .collect();
+
+        related.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| b.post.frontmatter.date.cmp(&a.post.frontmatter.date))
+                .then_with(|| a.post.slug.cmp(&b.post.slug))
+        });
+        related.truncate(self.options.limit);
+        related
     }
 }
 
@@ -20,6 +188,32 @@ impl Plugin for RelatedPostsPlugin {
         "related_posts"
     }
 
+    fn init(&mut self, config: &SsgConfig) -> Result<()> {
+        let options: RelatedPostsOptions = config.plugin_options(self.name())?;
+        if options.limit == 0 {
+            bail!("limit must be at least 1 (set enabled: false to turn the plugin off)");
+        }
+        let weights = [
+            options.tag_weight,
+            options.category_weight,
+            options.body_weight,
+        ];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+            bail!("tag_weight, category_weight and body_weight must be zero or positive");
+        }
+        if weights.iter().all(|w| *w == 0.0) {
+            bail!("at least one of tag_weight, category_weight and body_weight must be positive");
+        }
+        self.options = options;
+        Ok(())
+    }
+
+    fn on_build_start(&self, _ctx: &PluginContext) -> Result<()> {
+        // Post bodies may have changed since the last build
+        *self.body_terms.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+
     fn template_context_post(
         &self,
         post: &Post,
@@ -27,21 +221,16 @@ impl Plugin for RelatedPostsPlugin {
     ) -> Result<HashMap<String, JsonValue>> {
         let mut context = HashMap::new();
 
-        let mut posts: Vec<_> = ctx
-            .metadata
-            .posts
-            .iter()
-            .filter(|p| p.category == post.category && p.slug != post.slug)
-            .collect();
-
-        posts.sort_by(|a, b| b.frontmatter.date.cmp(&a.frontmatter.date));
-
-        let related_posts: Vec<_> = posts.into_iter().take(self.limit).collect();
-        let related_json = json!(related_posts);
-        context.insert("related_posts".to_string(), related_json);
+        let body_terms = (self.options.body_weight > 0.0).then(|| self.body_terms(ctx.config));
+        let related_posts = self.related(post, ctx.metadata, body_terms.as_deref());
+        context.insert("related_posts".to_string(), json!(related_posts));
 
         Ok(context)
     }
+
+    fn reads_post_bodies(&self) -> bool {
+        self.options.body_weight > 0.0
+    }
 }
 
 impl Default for RelatedPostsPlugin {
@@ -49,3 +238,237 @@ impl Default for RelatedPostsPlugin {
         Self::new()
     }
 }
+
+/// Inverse document frequency of every tag: rare tags say more about a post
+fn tag_idf(metadata: &MetadataCache) -> HashMap<&str, f64> {
+    let total = metadata.posts.len() as f64;
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for post in &metadata.posts {
+        let tags: HashSet<&str> = post.frontmatter.tags.iter().map(String::as_str).collect();
+        for tag in tags {
+            *counts.entry(tag).or_default() += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .map(|(tag, count)| (tag, (1.0 + total / count as f64).ln()))
+        .collect()
+}
+
+/// Plain text of every post's markdown, keyed by (lang, category, slug); posts
+/// without a `lang` are in `default_lang`. Read from the sources, since posts
+/// are scored before any of them is rendered.
+fn collect_bodies(
+    content_dir: &Path,
+    default_lang: &str,
+) -> HashMap<(String, String, String), String> {
+    WalkDir::new(content_dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
+        .filter_map(|e| Parser::parse_file(e.path(), content_dir).ok())
+        .map(|post| {
+            let text = Renderer::render_plain_text(&post.content);
+            let lang = post
+                .frontmatter
+                .lang
+                .unwrap_or_else(|| default_lang.to_string());
+            ((lang, post.category, post.slug), text)
+        })
+        .collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reading::ReadingStats;
+    use crate::types::{Frontmatter, PostDate};
+    use chrono::{TimeZone, Utc};
+    use std::fs;
+
+    fn init(yaml: &str) -> Result<RelatedPostsPlugin> {
+        let config: SsgConfig = serde_yaml::from_str(yaml).unwrap();
+        let mut plugin = RelatedPostsPlugin::new();
+        plugin.init(&config)?;
+        Ok(plugin)
+    }
+
+    fn frontmatter(title: &str, day: u32, tags: &[&str]) -> Frontmatter {
+        Frontmatter {
+            title: title.to_string(),
+            date: PostDate {
+                posted: Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap(),
+                modified: None,
+            },
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            featured_image: None,
+            description: None,
+            draft: false,
+            series: None,
+            series_order: None,
+            expires: None,
+            lang: None,
+            translation_key: None,
+        }
+    }
+
+    fn metadata(posts: &[(&str, &str, u32, &[&str])]) -> MetadataCache {
+        let mut metadata = MetadataCache::new();
+        for (slug, category, day, tags) in posts {
+            metadata.upsert_post(
+                slug.to_string(),
+                category.to_string(),
+                frontmatter(slug, *day, tags),
+                ReadingStats::default(),
+            );
+        }
+        metadata
+    }
+
+    fn current(slug: &str, category: &str, tags: &[&str]) -> Post {
+        Post {
+            slug: slug.to_string(),
+            category: category.to_string(),
+            frontmatter: frontmatter(slug, 1, tags),
+            is_draft: false,
+            content: String::new(),
+            rendered_html: None,
+            toc: Vec::new(),
+            stats: ReadingStats::default(),
+            excerpt: None,
+        }
+    }
+
+    fn slugs(related: &[RelatedPost]) -> Vec<String> {
+        related.iter().map(|r| r.post.slug.clone()).collect()
+    }
+
+    #[test]
+    fn test_limit_option() {
+        assert_eq!(init("site: {}").unwrap().options.limit, 3);
+        assert_eq!(
+            init("plugins:\n  related_posts:\n    options:\n      limit: 6\n")
+                .unwrap()
+                .options
+                .limit,
+            6
+        );
+        assert!(init("plugins:\n  related_posts:\n    options:\n      limit: 0\n").is_err());
+        assert!(init("plugins:\n  related_posts:\n    options:\n      limit: many\n").is_err());
+        assert!(init("plugins:\n  related_posts:\n    options:\n      body_weight: -1\n").is_err());
+        assert!(init(
+            "plugins:\n  related_posts:\n    options:\n      tag_weight: 0\n      category_weight: 0\n"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_rare_shared_tags_rank_first() {
+        let metadata = metadata(&[
+            ("current", "dev", 1, &["rust", "wasm"]),
+            ("common", "dev", 9, &["rust"]),
+            ("rare", "chat", 2, &["wasm"]),
+            ("both", "chat", 3, &["rust", "wasm"]),
+            ("unrelated", "chat", 8, &["cooking"]),
+            ("filler-1", "chat", 4, &["rust"]),
+            ("filler-2", "chat", 5, &["rust"]),
+        ]);
+        let plugin = RelatedPostsPlugin {
+            options: RelatedPostsOptions {
+                limit: 10,
+                ..Default::default()
+            },
+            body_terms: Mutex::new(None),
+        };
+
+        let post = current("current", "dev", &["rust", "wasm"]);
+        let related = plugin.related(&post, &metadata, None);
+
+        assert_eq!(
+            slugs(&related),
+            vec!["both", "common", "rare", "filler-2", "filler-1"]
+        );
+        assert_eq!(related[0].score, 1.0);
+        assert_eq!(related[0].shared_tags, vec!["rust", "wasm"]);
+        // Sharing the rare tag beats sharing the frequent one
+        assert!(related[2].score > related[3].score);
+        // Equal scores: the newer post comes first
+        assert_eq!(related[3].score, related[4].score);
+    }
+
+    #[test]
+    fn test_recency_breaks_ties_and_body_overlap() {
+        let metadata = metadata(&[
+            ("current", "dev", 1, &[]),
+            ("older", "dev", 2, &[]),
+            ("newer", "dev", 3, &[]),
+            ("other", "chat", 4, &[]),
+        ]);
+        let mut plugin = RelatedPostsPlugin::new();
+
+        let post = current("current", "dev", &[]);
+        assert_eq!(
+            slugs(&plugin.related(&post, &metadata, None)),
+            vec!["newer", "older"]
+        );
+
+        let terms = |words: &str| words.split(' ').map(str::to_string).collect();
+        let body_terms: BodyTerms = [
+            (
+                (String::new(), "dev".to_string(), "current".to_string()),
+                terms("borrow checker lifetimes"),
+            ),
+            (
+                (String::new(), "dev".to_string(), "older".to_string()),
+                terms("borrow checker tips"),
+            ),
+            (
+                (String::new(), "chat".to_string(), "other".to_string()),
+                terms("borrow checker lifetimes"),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        plugin.options.body_weight = 1.0;
+
+        let related = plugin.related(&post, &metadata, Some(&body_terms));
+        assert_eq!(slugs(&related), vec!["other", "older", "newer"]);
+        assert_eq!(related[0].score, 1.0);
+        assert_eq!(related[1].score, 0.75);
+    }
+
+    #[test]
+    fn test_collect_bodies_per_language() {
+        let root = tempfile::tempdir().unwrap();
+        for (path, lang, body) in [
+            ("dev/hello.md", "", "안녕하세요"),
+            ("dev/en/hello.md", "lang: en\n", "Hello"),
+        ] {
+            let path = root.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(
+                path,
+                format!(
+                    "---\ntitle: Hello\ndate: 2024-01-01T00:00:00Z\n{}---\n\n{}\n",
+                    lang, body
+                ),
+            )
+            .unwrap();
+        }
+
+        let bodies = collect_bodies(root.path(), "ko");
+        let body =
+            |lang: &str| bodies[&(lang.to_string(), "dev".to_string(), "hello".to_string())].trim();
+        assert_eq!(body("ko"), "안녕하세요");
+        assert_eq!(body("en"), "Hello");
+    }
+}
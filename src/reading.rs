@@ -0,0 +1,116 @@
+use crate::config::ReadingConfig;
+use crate::renderer::Renderer;
+use crate::search::tokenizer::is_cjk;
+use serde::{Deserialize, Serialize};
+
+/// Length metrics of a post body
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadingStats {
+    /// Whitespace-separated words, with every CJK character counted as one word
+    pub word_count: usize,
+    /// Characters excluding whitespace
+    pub char_count: usize,
+    /// Estimated minutes to read, rounded up
+    pub reading_time: usize,
+}
+
+impl ReadingStats {
+    /// Measure the rendered text of a markdown body, ignoring markup
+    pub fn from_markdown(markdown: &str, config: &ReadingConfig) -> Self {
+        Self::from_text(&Renderer::render_plain_text(markdown), config)
+    }
+
+    /// Korean, Chinese and Japanese have no reliable word boundaries, so CJK
+    /// characters are counted one by one and read at `cjk_chars_per_minute`,
+    /// while other text is counted in words read at `words_per_minute`.
+    pub fn from_text(text: &str, config: &ReadingConfig) -> Self {
+        let mut words = 0;
+        let mut cjk_chars = 0;
+        let mut char_count = 0;
+        let mut in_word = false;
+
+        for c in text.chars() {
+            if c.is_whitespace() {
+                in_word = false;
+                continue;
+            }
+
+            char_count += 1;
+
+            if is_cjk(c) {
+                cjk_chars += 1;
+                in_word = false;
+            } else if c.is_alphanumeric() && !in_word {
+                words += 1;
+                in_word = true;
+            }
+        }
+
+        let minutes = words as f64 / config.words_per_minute.max(1) as f64
+            + cjk_chars as f64 / config.cjk_chars_per_minute.max(1) as f64;
+        let reading_time = if char_count == 0 {
+            0
+        } else {
+            (minutes.ceil() as usize).max(1)
+        };
+
+        Self {
+            word_count: words + cjk_chars,
+            char_count,
+            reading_time,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ReadingConfig {
+        ReadingConfig {
+            words_per_minute: 200,
+            cjk_chars_per_minute: 500,
+        }
+    }
+
+    #[test]
+    fn test_counts_latin_words() {
+        let stats = ReadingStats::from_text("Hello, world! It's Rust-2024.", &config());
+
+        assert_eq!(stats.word_count, 4);
+        assert_eq!(stats.char_count, 26);
+        assert_eq!(stats.reading_time, 1);
+    }
+
+    #[test]
+    fn test_counts_cjk_by_character() {
+        let stats = ReadingStats::from_text("한글 문장입니다 Rust로", &config());
+
+        assert_eq!(stats.word_count, 9);
+        assert_eq!(stats.char_count, 12);
+    }
+
+    #[test]
+    fn test_reading_time() {
+        let latin = "word ".repeat(450);
+        assert_eq!(ReadingStats::from_text(&latin, &config()).reading_time, 3);
+
+        let korean = "가".repeat(1000);
+        assert_eq!(ReadingStats::from_text(&korean, &config()).reading_time, 2);
+
+        assert_eq!(
+            ReadingStats::from_text("   ", &config()),
+            ReadingStats::default()
+        );
+    }
+
+    #[test]
+    fn test_from_markdown_ignores_markup() {
+        let stats = ReadingStats::from_markdown(
+            "# Title\n\n<div class=\"x\">raw</div>\n\n[link text](https://example.com/a-b-c)",
+            &config(),
+        );
+
+        assert_eq!(stats.word_count, 3);
+    }
+}
@@ -1,6 +1,9 @@
+use crate::html::{parse as parse_html, Element, Node};
+use crate::slug;
+use crate::types::TocEntry;
 use anyhow::Result;
-use pulldown_cmark::{html, Options, Parser as MdParser};
-use std::collections::HashMap;
+use pulldown_cmark::{html, Event, Options, Parser as MdParser, Tag};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fs;
 use std::path::Path;
 use syntect::highlighting::{Theme, ThemeSet};
@@ -9,9 +12,20 @@ use syntect::parsing::SyntaxSet;
 use syntect::util::LinesWithEndings;
 use tera::{Context, Tera};
 
+/// Markdown rendered through the component pipeline
+#[derive(Debug, Clone, Default)]
+pub struct RenderedContent {
+    pub html: String,
+    /// Component templates (`components/<tag>.html`) that were applied
+    pub components: Vec<String>,
+    /// Nested headings, matching the `id`s written into `html`
+    pub toc: Vec<TocEntry>,
+}
+
 pub struct Renderer {
     syntax_set: SyntaxSet,
     theme_set: ThemeSet,
+    heading_anchors: bool,
 }
 
 impl Renderer {
@@ -19,237 +33,310 @@ impl Renderer {
         Self {
             syntax_set: SyntaxSet::load_defaults_newlines(),
             theme_set: ThemeSet::load_defaults(),
+            heading_anchors: false,
         }
     }
 
-    pub fn render_markdown(&self, markdown: &str) -> String {
-        let options = Options::all();
-        let parser = MdParser::new_ext(markdown, options);
-
-        let mut html_output = String::new();
-        html::push_html(&mut html_output, parser);
-
-        self.highlight_code_blocks(&html_output)
+    /// Append a `<a class="heading-anchor" href="#id">#</a>` self-link to every heading
+    pub fn with_heading_anchors(mut self, enabled: bool) -> Self {
+        self.heading_anchors = enabled;
+        self
     }
 
-    pub fn render_markdown_with_components(
-        &self,
-        markdown: &str,
-        tera: &Tera,
-        base_path: &str,
-    ) -> Result<String> {
-        let options = Options::all();
-        let parser = MdParser::new_ext(markdown, options);
-
-        let mut html_output = String::new();
-        html::push_html(&mut html_output, parser);
-
-        // Apply syntax highlighting first
-        let highlighted = self.highlight_code_blocks(&html_output);
-
-        // Then apply component templates
-        Self::post_process_components(&highlighted, tera, base_path)
+    pub fn render_markdown(&self, markdown: &str) -> String {
+        self.markdown_to_html(markdown).0
     }
 
-    fn post_process_components(html: &str, tera: &Tera, base_path: &str) -> Result<String> {
-        let mut result = html.to_string();
+    /// Render markdown with heading ids and syntax highlighting
+    fn markdown_to_html(&self, markdown: &str) -> (String, Vec<TocEntry>) {
+        let events: Vec<Event> = MdParser::new_ext(markdown, Options::all()).collect();
+        let headings = Self::collect_headings(&events);
 
-        let tag_patterns = vec![
-            "img", "code", "pre", "blockquote", "table", "a", "h1", "h2", "h3",
-            "h4", "h5", "h6", "p", "ul", "ol", "li", "strong", "em", "del"
-        ];
+        let mut heading_index = 0;
+        let mut with_ids = Vec::with_capacity(events.len());
 
-        for tag_name in tag_patterns {
-            let template_name = format!("components/{}.html", tag_name);
-
-            if tera.get_template(&template_name).is_err() {
-                continue;
+        for event in events {
+            match event {
+                Event::Start(Tag::Heading(level, _, classes)) => {
+                    let id = headings[heading_index].id.as_str();
+                    with_ids.push(Event::Start(Tag::Heading(level, Some(id), classes)));
+                }
+                Event::End(Tag::Heading(level, id, classes)) => {
+                    if self.heading_anchors {
+                        let anchor = format!(
+                            " <a class=\"heading-anchor\" href=\"#{}\" aria-hidden=\"true\">#</a>",
+                            tera::escape_html(&headings[heading_index].id)
+                        );
+                        with_ids.push(Event::Html(anchor.into()));
+                    }
+                    with_ids.push(Event::End(Tag::Heading(level, id, classes)));
+                    heading_index += 1;
+                }
+                other => with_ids.push(other),
             }
-
-            result = Self::replace_tag(&result, tag_name, tera, &template_name, base_path)?;
         }
 
-        Ok(result)
-    }
-
-    fn replace_tag(
-        html: &str,
-        tag_name: &str,
-        tera: &Tera,
-        template_name: &str,
-        base_path: &str,
-    ) -> Result<String> {
-        let mut result = String::new();
-        let mut chars = html.chars().peekable();
-
-        while let Some(ch) = chars.next() {
-            if ch == '<' {
-                let tag_start_pos = result.len();
-                result.push(ch);
-
-                let mut tag_content = String::new();
-                let mut in_quotes = false;
-                let mut quote_char = ' ';
-
-                while let Some(&next_ch) = chars.peek() {
-                    chars.next();
-                    result.push(next_ch);
+        let mut html_output = String::new();
+        html::push_html(&mut html_output, with_ids.into_iter());
 
-                    if next_ch == '"' || next_ch == '\'' {
-                        if in_quotes && next_ch == quote_char {
-                            in_quotes = false;
-                        } else if !in_quotes {
-                            in_quotes = true;
-                            quote_char = next_ch;
-                        }
-                    }
+        (
+            self.highlight_code_blocks(&html_output),
+            Self::nest_toc(headings),
+        )
+    }
 
-                    if next_ch == '>' && !in_quotes {
-                        tag_content = result[tag_start_pos..].to_string();
-                        break;
+    /// Headings in document order with unique ids.
+    /// Explicit `{#id}` attributes are kept; others are slugified from the
+    /// heading text, with `-1`, `-2`, ... appended to repeats.
+    fn collect_headings(events: &[Event]) -> Vec<TocEntry> {
+        let mut headings = Vec::new();
+        let mut used: HashSet<String> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Start(Tag::Heading(_, Some(id), _)) => Some(id.to_string()),
+                _ => None,
+            })
+            .collect();
+
+        let mut current: Option<(u32, Option<&str>, String)> = None;
+
+        for event in events {
+            match event {
+                Event::Start(Tag::Heading(level, id, _)) => {
+                    current = Some((*level as u32, *id, String::new()));
+                }
+                Event::Text(text) | Event::Code(text) => {
+                    if let Some((_, _, title)) = current.as_mut() {
+                        title.push_str(text);
                     }
                 }
+                Event::End(Tag::Heading(..)) => {
+                    let Some((level, explicit_id, title)) = current.take() else {
+                        continue;
+                    };
+                    let title = title.trim().to_string();
+
+                    let id = match explicit_id {
+                        Some(id) => id.to_string(),
+                        None => {
+                            let base = match slug::slugify(&title) {
+                                s if s.is_empty() => "section".to_string(),
+                                s => s,
+                            };
+                            let mut id = base.clone();
+                            let mut n = 1;
+                            while used.contains(&id) {
+                                id = format!("{}-{}", base, n);
+                                n += 1;
+                            }
+                            used.insert(id.clone());
+                            id
+                        }
+                    };
+
+                    headings.push(TocEntry {
+                        level,
+                        title,
+                        id,
+                        children: Vec::new(),
+                    });
+                }
+                _ => {}
+            }
+        }
 
-                if tag_content.starts_with(&format!("<{} ", tag_name))
-                    || tag_content == format!("<{}>", tag_name) {
-
-                    let attrs = Self::extract_attributes(&tag_content);
-                    let mut inner_content = String::new();
-
-                    if !tag_content.ends_with("/>") {
-                        let mut depth = 1;
-                        let close_tag = format!("</{}>", tag_name);
-
-                        while depth > 0 && chars.peek().is_some() {
-                            let ch = chars.next().unwrap();
+        headings
+    }
 
-                            if ch == '<' {
-                                let mut potential_tag = String::from('<');
-                                while let Some(&next_ch) = chars.peek() {
-                                    chars.next();
-                                    potential_tag.push(next_ch);
-                                    if next_ch == '>' {
-                                        break;
-                                    }
-                                }
+    /// Nest flat headings so each one holds the deeper headings that follow it
+    fn nest_toc(flat: Vec<TocEntry>) -> Vec<TocEntry> {
+        fn attach(stack: &mut [TocEntry], root: &mut Vec<TocEntry>, entry: TocEntry) {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(entry),
+                None => root.push(entry),
+            }
+        }
 
-                                if potential_tag == close_tag {
-                                    depth -= 1;
-                                    if depth == 0 {
-                                        break;
-                                    }
-                                } else if potential_tag.starts_with(&format!("<{} ", tag_name))
-                                    || potential_tag == format!("<{}>", tag_name) {
-                                    depth += 1;
-                                }
+        let mut root = Vec::new();
+        let mut stack: Vec<TocEntry> = Vec::new();
 
-                                if depth > 0 {
-                                    inner_content.push_str(&potential_tag);
-                                }
-                            } else {
-                                inner_content.push(ch);
-                            }
-                        }
-                    }
+        for entry in flat {
+            while stack.last().is_some_and(|top| top.level >= entry.level) {
+                let done = stack.pop().unwrap();
+                attach(&mut stack, &mut root, done);
+            }
+            stack.push(entry);
+        }
 
-                    let mut context = Context::new();
-                    for (key, value) in attrs {
-                        if Self::is_url_attribute(&key) {
-                            let resolved = Self::resolve_path(&value, base_path);
-                            context.insert(&key, &resolved);
-                        } else {
-                            context.insert(&key, &value);
-                        }
-                    }
+        while let Some(done) = stack.pop() {
+            attach(&mut stack, &mut root, done);
+        }
 
-                    if !inner_content.is_empty() {
-                        context.insert("content", &inner_content);
-                    }
+        root
+    }
 
-                    if let Ok(rendered) = tera.render(template_name, &context) {
-                        result.truncate(tag_start_pos);
-                        result.push_str(&rendered);
-                        continue;
-                    }
-                }
-            } else {
-                result.push(ch);
+    /// Render markdown to the plain text a reader would see, without markup.
+    ///
+    /// Raw HTML is dropped; block boundaries become newlines.
+    pub fn render_plain_text(markdown: &str) -> String {
+        let parser = MdParser::new_ext(markdown, Options::all());
+        let mut text = String::new();
+
+        for event in parser {
+            match event {
+                Event::Text(t) | Event::Code(t) => text.push_str(&t),
+                Event::SoftBreak => text.push(' '),
+                Event::HardBreak => text.push('\n'),
+                Event::End(
+                    Tag::Paragraph
+                    | Tag::Heading(..)
+                    | Tag::BlockQuote
+                    | Tag::CodeBlock(_)
+                    | Tag::Item
+                    | Tag::TableCell
+                    | Tag::TableRow
+                    | Tag::TableHead
+                    | Tag::FootnoteDefinition(_),
+                ) if !text.ends_with('\n') => text.push('\n'),
+                _ => {}
             }
         }
 
-        Ok(result)
+        text.trim().to_string()
     }
 
-    fn extract_attributes(tag: &str) -> HashMap<String, String> {
-        let mut attrs = HashMap::new();
+    pub fn render_markdown_with_components(
+        &self,
+        markdown: &str,
+        tera: &Tera,
+        base_path: &str,
+    ) -> Result<RenderedContent> {
+        // Headings get ids and code blocks are highlighted first
+        let (highlighted, toc) = self.markdown_to_html(markdown);
 
-        let tag = tag.trim_start_matches('<').trim_end_matches('>').trim_end_matches('/');
-        let parts: Vec<&str> = tag.splitn(2, ' ').collect();
+        // Then apply component templates
+        let mut rendered = Self::post_process_components(&highlighted, tera, base_path)?;
+        rendered.toc = toc;
+        Ok(rendered)
+    }
 
-        if parts.len() < 2 {
-            return attrs;
+    fn post_process_components(
+        html: &str,
+        tera: &Tera,
+        base_path: &str,
+    ) -> Result<RenderedContent> {
+        let component_tags = Self::component_tags(tera);
+
+        if component_tags.is_empty() {
+            return Ok(RenderedContent {
+                html: html.to_string(),
+                ..Default::default()
+            });
         }
 
-        let attr_string = parts[1];
-        let mut chars = attr_string.chars().peekable();
+        let nodes = parse_html(html);
+        let mut used = BTreeSet::new();
+        let mut result = String::with_capacity(html.len());
+        Self::render_components(
+            &nodes,
+            tera,
+            base_path,
+            &component_tags,
+            &mut used,
+            &mut result,
+        );
+
+        Ok(RenderedContent {
+            html: result,
+            components: used.into_iter().collect(),
+            ..Default::default()
+        })
+    }
 
-        while chars.peek().is_some() {
-            while chars.peek() == Some(&' ') {
-                chars.next();
-            }
+    /// Tags with a `components/<tag>.html` template in the theme
+    fn component_tags(tera: &Tera) -> HashSet<String> {
+        tera.get_template_names()
+            .filter_map(|name| name.strip_prefix("components/")?.strip_suffix(".html"))
+            .filter(|tag| !tag.contains('/'))
+            .map(|tag| tag.to_ascii_lowercase())
+            .collect()
+    }
 
-            let mut key = String::new();
-            while let Some(&ch) = chars.peek() {
-                if ch == '=' || ch == ' ' {
-                    break;
+    /// Write nodes to `out`, replacing component elements with their template.
+    ///
+    /// Children are processed before their parent, so a component's `content`
+    /// already has nested components applied. Template output is not processed
+    /// again, which lets `components/img.html` emit an `<img>` itself.
+    fn render_components(
+        nodes: &[Node],
+        tera: &Tera,
+        base_path: &str,
+        component_tags: &HashSet<String>,
+        used: &mut BTreeSet<String>,
+        out: &mut String,
+    ) {
+        for node in nodes {
+            let element = match node {
+                Node::Text(text) => {
+                    out.push_str(text);
+                    continue;
                 }
-                key.push(chars.next().unwrap());
-            }
-
-            if key.is_empty() {
-                break;
-            }
+                Node::Element(element) => element,
+            };
 
-            while chars.peek() == Some(&' ') {
-                chars.next();
+            let mut inner = String::new();
+            Self::render_components(
+                &element.children,
+                tera,
+                base_path,
+                component_tags,
+                used,
+                &mut inner,
+            );
+
+            if component_tags.contains(&element.name) {
+                let template_name = format!("components/{}.html", element.name);
+                let context = Self::component_context(element, &inner, base_path);
+
+                if let Ok(rendered) = tera.render(&template_name, &context) {
+                    out.push_str(&rendered);
+                    used.insert(template_name);
+                    continue;
+                }
             }
 
-            if chars.peek() != Some(&'=') {
-                attrs.insert(key, String::from("true"));
-                continue;
+            out.push_str(element.start_tag());
+            out.push_str(&inner);
+            if let Some(end_tag) = element.end_tag() {
+                out.push_str(end_tag);
             }
+        }
+    }
 
-            chars.next();
+    /// Attributes become variables (URL attributes resolved against the post),
+    /// `attributes` holds all of them for names Tera can't address, such as
+    /// `data-*`, and `content` holds the processed inner HTML
+    fn component_context(element: &Element, inner_html: &str, base_path: &str) -> Context {
+        let mut context = Context::new();
+        let mut attributes = BTreeMap::new();
 
-            while chars.peek() == Some(&' ') {
-                chars.next();
-            }
+        for (key, value) in &element.attributes {
+            let value = if Self::is_url_attribute(key) {
+                Self::resolve_path(value, base_path)
+            } else {
+                value.clone()
+            };
+            context.insert(key, &value);
+            attributes.insert(key.clone(), value);
+        }
 
-            let mut value = String::new();
-            if let Some(&quote) = chars.peek() {
-                if quote == '"' || quote == '\'' {
-                    chars.next();
-                    while let Some(&ch) = chars.peek() {
-                        if ch == quote {
-                            chars.next();
-                            break;
-                        }
-                        value.push(chars.next().unwrap());
-                    }
-                } else {
-                    while let Some(&ch) = chars.peek() {
-                        if ch == ' ' {
-                            break;
-                        }
-                        value.push(chars.next().unwrap());
-                    }
-                }
-            }
+        context.insert("attributes", &attributes);
 
-            attrs.insert(key, value);
+        if !inner_html.is_empty() {
+            context.insert("content", inner_html);
         }
 
-        attrs
+        context
     }
 
     fn is_url_attribute(attr: &str) -> bool {
@@ -273,8 +360,8 @@ impl Renderer {
             return trimmed.to_string();
         }
 
-        if trimmed.starts_with("./") {
-            return format!("/{}/{}", base_path.trim_matches('/'), &trimmed[2..]);
+        if let Some(relative) = trimmed.strip_prefix("./") {
+            return format!("/{}/{}", base_path.trim_matches('/'), relative);
         }
 
         if trimmed.starts_with("../") {
@@ -356,7 +443,9 @@ impl Renderer {
                                 depth -= 1;
                                 if depth == 0 {
                                     // Process the pre_content for code highlighting
-                                    if let Some(highlighted) = self.process_pre_content(&pre_content) {
+                                    if let Some(highlighted) =
+                                        self.process_pre_content(&pre_content)
+                                    {
                                         // Replace the accumulated content with highlighted version
                                         result.truncate(start_pos);
                                         result.push_str(&highlighted);
@@ -394,11 +483,9 @@ impl Renderer {
         // Extract language from class attribute
         let lang = if let Some(class_start) = content.find("class=\"language-") {
             let lang_start = class_start + "class=\"language-".len();
-            if let Some(quote_end) = content[lang_start..].find('"') {
-                Some(&content[lang_start..lang_start + quote_end])
-            } else {
-                None
-            }
+            content[lang_start..]
+                .find('"')
+                .map(|quote_end| &content[lang_start..lang_start + quote_end])
         } else {
             None
         };
@@ -438,15 +525,20 @@ impl Renderer {
             .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
 
         // Use ClassedHTMLGenerator for CSS class-based highlighting
-        let mut html_generator =
-            ClassedHTMLGenerator::new_with_class_style(syntax, &self.syntax_set, ClassStyle::Spaced);
+        let mut html_generator = ClassedHTMLGenerator::new_with_class_style(
+            syntax,
+            &self.syntax_set,
+            ClassStyle::Spaced,
+        );
 
         for line in LinesWithEndings::from(code) {
             html_generator.parse_html_for_line_which_includes_newline(line)?;
         }
 
-        Ok(format!("<pre class=\"syntax-highlight\"><code>{}</code></pre>",
-            html_generator.finalize()))
+        Ok(format!(
+            "<pre class=\"syntax-highlight\"><code>{}</code></pre>",
+            html_generator.finalize()
+        ))
     }
 
     /// Generate CSS for syntax highlighting themes
@@ -501,10 +593,26 @@ impl Renderer {
     }
 
     fn add_theme_variables(css: &mut String, theme: &Theme, indent: &str) {
-        css.push_str(&format!("{}--syntax-bg: {};\n", indent,
-            Self::color_to_css(&theme.settings.background.unwrap_or(syntect::highlighting::Color::WHITE))));
-        css.push_str(&format!("{}--syntax-fg: {};\n", indent,
-            Self::color_to_css(&theme.settings.foreground.unwrap_or(syntect::highlighting::Color::BLACK))));
+        css.push_str(&format!(
+            "{}--syntax-bg: {};\n",
+            indent,
+            Self::color_to_css(
+                &theme
+                    .settings
+                    .background
+                    .unwrap_or(syntect::highlighting::Color::WHITE)
+            )
+        ));
+        css.push_str(&format!(
+            "{}--syntax-fg: {};\n",
+            indent,
+            Self::color_to_css(
+                &theme
+                    .settings
+                    .foreground
+                    .unwrap_or(syntect::highlighting::Color::BLACK)
+            )
+        ));
     }
 
     fn color_to_css(color: &syntect::highlighting::Color) -> String {
@@ -514,8 +622,11 @@ impl Renderer {
     fn convert_css_to_variables(css: &str) -> String {
         // Replace hardcoded colors with CSS variables in the generated CSS
         // This is a simplified version - we'll use the variables defined above
-        css.replace("background-color:#", "background-color: var(--syntax-bg); /* #")
-            .replace("color:#", "color: var(--syntax-fg); /* #")
+        css.replace(
+            "background-color:#",
+            "background-color: var(--syntax-bg); /* #",
+        )
+        .replace("color:#", "color: var(--syntax-fg); /* #")
     }
 
     /// Write syntax highlighting CSS to file
@@ -542,7 +653,7 @@ mod tests {
         let md = "# Hello\n\nThis is **bold**.";
         let html = renderer.render_markdown(md);
 
-        assert!(html.contains("<h1>"));
+        assert!(html.contains("<h1 id=\"hello\">"));
         assert!(html.contains("Hello"));
         assert!(html.contains("<strong>bold</strong>"));
     }
@@ -570,4 +681,116 @@ mod tests {
         assert!(html.contains("<a href=\"https://example.com\">"));
         assert!(html.contains("Click here"));
     }
+
+    #[test]
+    fn test_render_plain_text() {
+        let md = "# Title\n\nSome **bold** and `code`.\n\n<div>raw</div>\n\n- one\n- two";
+        let text = Renderer::render_plain_text(md);
+
+        assert_eq!(text, "Title\nSome bold and code.\none\ntwo");
+    }
+
+    fn component_tera() -> Tera {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(vec![
+            (
+                "components/img.html",
+                "<figure><img src=\"{{ src }}\" alt=\"{{ alt }}\"></figure>",
+            ),
+            (
+                "components/details.html",
+                "<details class=\"box\"{% if open %} open{% endif %}>{{ content | safe }}</details>",
+            ),
+            (
+                "components/video.html",
+                "<video data-id=\"{{ attributes['data-id'] }}\" src=\"{{ src }}\"></video>",
+            ),
+        ])
+        .unwrap();
+        tera
+    }
+
+    #[test]
+    fn test_components_preserve_nesting_and_attributes() {
+        let tera = component_tera();
+        let html = "<details open><summary>More</summary><details><p><img src=\"./a.png\" alt=\"A &amp; B\"></p></details></details>";
+
+        let rendered = Renderer::post_process_components(html, &tera, "dev/post").unwrap();
+
+        assert_eq!(
+            rendered.html,
+            "<details class=\"box\" open><summary>More</summary><details class=\"box\"><p><figure><img src=\"&#x2F;dev&#x2F;post&#x2F;a.png\" alt=\"A &amp; B\"></figure></p></details></details>"
+        );
+        assert_eq!(
+            rendered.components,
+            vec!["components/details.html", "components/img.html"]
+        );
+    }
+
+    #[test]
+    fn test_components_discovered_from_templates() {
+        let tera = component_tera();
+        let html = "<p>Clip:</p>\n<VIDEO data-id=\"7\" src=\"clip.mp4\" controls></VIDEO>";
+
+        let rendered = Renderer::post_process_components(html, &tera, "dev/post").unwrap();
+
+        assert_eq!(
+            rendered.html,
+            "<p>Clip:</p>\n<video data-id=\"7\" src=\"&#x2F;dev&#x2F;post&#x2F;clip.mp4\"></video>"
+        );
+        assert_eq!(rendered.components, vec!["components/video.html"]);
+    }
+
+    #[test]
+    fn test_components_leave_other_markup_untouched() {
+        let tera = component_tera();
+        let html = "<div title=\"<img>\">text <!-- <img src=x> --> <em>a < b</em></div>";
+
+        let rendered = Renderer::post_process_components(html, &tera, "dev/post").unwrap();
+
+        assert_eq!(rendered.html, html);
+        assert!(rendered.components.is_empty());
+    }
+
+    #[test]
+    fn test_heading_ids_are_unique_and_korean_safe() {
+        let renderer = Renderer::new();
+        let md = "# 소개\n\n## Setup\n\n## Setup\n\n## Custom {#setup-1}\n\n## `code` & more!";
+        let html = renderer.render_markdown(md);
+
+        assert!(html.contains("<h1 id=\"소개\">소개</h1>"));
+        assert!(html.contains("<h2 id=\"setup\">Setup</h2>"));
+        assert!(html.contains("<h2 id=\"setup-2\">Setup</h2>"));
+        assert!(html.contains("<h2 id=\"setup-1\">Custom</h2>"));
+        assert!(html.contains("<h2 id=\"code-more\">"));
+    }
+
+    #[test]
+    fn test_heading_anchors() {
+        let renderer = Renderer::new().with_heading_anchors(true);
+        let html = renderer.render_markdown("## Hello World");
+
+        assert_eq!(
+            html.trim(),
+            "<h2 id=\"hello-world\">Hello World <a class=\"heading-anchor\" href=\"#hello-world\" aria-hidden=\"true\">#</a></h2>"
+        );
+    }
+
+    #[test]
+    fn test_toc_nesting() {
+        let renderer = Renderer::new();
+        let md = "## A\n\n### A.1\n\n#### A.1.a\n\n### A.2\n\n## B\n\n# Top";
+        let (_, toc) = renderer.markdown_to_html(md);
+
+        let titles = |entries: &[TocEntry]| -> Vec<String> {
+            entries.iter().map(|e| e.title.clone()).collect()
+        };
+
+        assert_eq!(titles(&toc), vec!["A", "B", "Top"]);
+        assert_eq!(titles(&toc[0].children), vec!["A.1", "A.2"]);
+        assert_eq!(titles(&toc[0].children[0].children), vec!["A.1.a"]);
+        assert_eq!(toc[0].children[0].children[0].id, "a1a");
+        assert_eq!(toc[0].children[0].children[0].level, 4);
+        assert!(toc[1].children.is_empty());
+    }
 }
@@ -0,0 +1,338 @@
+pub mod query;
+pub mod tokenizer;
+
+use crate::config::SsgConfig;
+use crate::metadata::MetadataCache;
+use crate::slug;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchIndex {
+    pub version: String,
+    pub posts: Vec<SearchEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub full_text: Option<FullTextIndex>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchEntry {
+    pub title: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub url: String,
+    /// Language code, so a search page can stay within the reader's language
+    #[serde(default)]
+    pub lang: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub date: String,
+    /// Plain text excerpt of the post body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<String>,
+    /// Words in the post body, counting each CJK character as a word
+    #[serde(default)]
+    pub word_count: usize,
+    /// Estimated minutes to read the post
+    #[serde(default)]
+    pub reading_time: usize,
+    /// Plain text of the post body (full-text mode only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// Number of body tokens (full-text mode only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<usize>,
+}
+
+/// Inverted index over post bodies
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FullTextIndex {
+    /// CJK n-gram size; queries must be tokenized the same way
+    pub ngram: usize,
+    /// Term to postings, empty when the terms are sharded
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub terms: BTreeMap<String, Vec<Posting>>,
+    /// Shard files; a term lives in `shards[fnv1a(term) % shards.len()]`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shards: Vec<String>,
+}
+
+/// Occurrences of a term in one post: `[post index, [char offsets into body]]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Posting(pub usize, pub Vec<usize>);
+
+/// Terms of one `search-index-<n>.json` file
+#[derive(Debug, Serialize, Deserialize)]
+struct IndexShard {
+    terms: BTreeMap<String, Vec<Posting>>,
+}
+
+impl FullTextIndex {
+    /// Build postings for a list of documents; the document index is its position
+    pub fn build(bodies: &[&str], ngram: usize) -> Self {
+        let mut terms: BTreeMap<String, Vec<Posting>> = BTreeMap::new();
+
+        for (doc, body) in bodies.iter().enumerate() {
+            let mut positions: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+            for token in tokenizer::tokenize(body, ngram) {
+                positions.entry(token.term).or_default().push(token.offset);
+            }
+            for (term, offsets) in positions {
+                terms.entry(term).or_default().push(Posting(doc, offsets));
+            }
+        }
+
+        Self {
+            ngram,
+            terms,
+            shards: Vec::new(),
+        }
+    }
+
+    /// Move the terms into `count` shards keyed by file name
+    fn into_shards(mut self, count: usize) -> (Self, Vec<(String, IndexShard)>) {
+        let mut shards: Vec<(String, IndexShard)> = (0..count)
+            .map(|i| {
+                (
+                    format!("search-index-{}.json", i),
+                    IndexShard {
+                        terms: BTreeMap::new(),
+                    },
+                )
+            })
+            .collect();
+
+        for (term, postings) in std::mem::take(&mut self.terms) {
+            let shard = tokenizer::fnv1a(&term) as usize % count;
+            shards[shard].1.terms.insert(term, postings);
+        }
+
+        self.shards = shards.iter().map(|(name, _)| name.clone()).collect();
+        (self, shards)
+    }
+}
+
+pub struct SearchIndexGenerator {
+    config: SsgConfig,
+}
+
+impl SearchIndexGenerator {
+    pub fn new(config: SsgConfig) -> Self {
+        Self { config }
+    }
+
+    /// Write `search-index.json`, and its shards when the terms are sharded.
+    /// Full-text bodies are the posts' rendered text recorded in `metadata`.
+    pub fn generate(&self, metadata: &MetadataCache) -> Result<()> {
+        println!("\n🔍 Generating search index...");
+
+        let search = &self.config.build.search;
+
+        let mut posts: Vec<SearchEntry> = metadata
+            .posts
+            .iter()
+            .map(|post| {
+                let prefix = metadata.lang_prefix(&post.lang);
+                let url = if self.config.build.encode_filenames {
+                    format!(
+                        "{}/{}/{}/",
+                        prefix,
+                        slug::encode_for_url(&post.category),
+                        slug::encode_for_url(&post.slug)
+                    )
+                } else {
+                    format!("{}/{}/{}/", prefix, post.category, post.slug)
+                };
+
+                let body = search
+                    .full_text
+                    .then(|| post.text.clone().unwrap_or_default());
+
+                SearchEntry {
+                    title: post.frontmatter.title.clone(),
+                    description: post.frontmatter.description.clone(),
+                    url,
+                    lang: post.lang.clone(),
+                    category: post.category.clone(),
+                    tags: post.frontmatter.tags.clone(),
+                    date: post.frontmatter.date.posted.format("%Y-%m-%d").to_string(),
+                    excerpt: post.excerpt.as_ref().map(|e| e.text.clone()),
+                    word_count: post.stats.word_count,
+                    reading_time: post.stats.reading_time,
+                    length: body
+                        .as_deref()
+                        .map(|b| tokenizer::tokenize(b, search.ngram).len()),
+                    body,
+                }
+            })
+            .collect();
+
+        posts.sort_by(|a, b| b.date.cmp(&a.date));
+
+        let output_dir = PathBuf::from(&self.config.build.output_dir);
+        Self::remove_shards(&output_dir)?;
+        let full_text = if search.full_text {
+            let texts: Vec<&str> = posts
+                .iter()
+                .map(|p| p.body.as_deref().unwrap_or_default())
+                .collect();
+            let index = FullTextIndex::build(&texts, search.ngram);
+            let term_count = index.terms.len();
+
+            if search.shards > 1 {
+                let (index, shards) = index.into_shards(search.shards);
+                for (name, shard) in &shards {
+                    fs::write(output_dir.join(name), serde_json::to_string(shard)?)?;
+                }
+                println!("   ✓ {} terms in {} shards", term_count, shards.len());
+                Some(index)
+            } else {
+                println!("   ✓ {} terms", term_count);
+                Some(index)
+            }
+        } else {
+            None
+        };
+
+        let index = SearchIndex {
+            version: "1.0".to_string(),
+            posts,
+            full_text,
+        };
+
+        let json = serde_json::to_string(&index)?;
+        let output_path = output_dir.join("search-index.json");
+
+        fs::write(&output_path, json)?;
+
+        println!("   ✓ {} posts indexed", index.posts.len());
+
+        Ok(())
+    }
+
+    /// Shard files of earlier builds, which may have had more shards
+    fn remove_shards(output_dir: &Path) -> Result<()> {
+        let Ok(entries) = fs::read_dir(output_dir) else {
+            return Ok(());
+        };
+        for entry in entries {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with("search-index-") && name.ends_with(".json") {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::MetadataCache;
+    use crate::reading::ReadingStats;
+    use crate::types::{Frontmatter, PostDate};
+    use chrono::Utc;
+
+    fn create_test_config() -> SsgConfig {
+        SsgConfig::default()
+    }
+
+    fn create_test_metadata() -> MetadataCache {
+        let mut metadata = MetadataCache::new();
+
+        let frontmatter = Frontmatter {
+            title: "Test Post".to_string(),
+            date: PostDate::new(Utc::now()),
+            tags: vec!["rust".to_string(), "test".to_string()],
+            featured_image: None,
+            description: Some("A test post".to_string()),
+            draft: false,
+            series: None,
+            series_order: None,
+            expires: None,
+            lang: None,
+            translation_key: None,
+        };
+
+        metadata.upsert_post(
+            "test-post".to_string(),
+            "dev".to_string(),
+            frontmatter,
+            ReadingStats::default(),
+        );
+
+        metadata
+    }
+
+    #[test]
+    fn test_search_entry_creation() {
+        let config = create_test_config();
+        let metadata = create_test_metadata();
+
+        SearchIndexGenerator::new(config);
+
+        let post = &metadata.posts[0];
+        let entry = SearchEntry {
+            title: post.frontmatter.title.clone(),
+            description: post.frontmatter.description.clone(),
+            url: format!("/{}/{}/", post.category, post.slug),
+            lang: post.lang.clone(),
+            category: post.category.clone(),
+            tags: post.frontmatter.tags.clone(),
+            date: post.frontmatter.date.posted.format("%Y-%m-%d").to_string(),
+            excerpt: None,
+            word_count: post.stats.word_count,
+            reading_time: post.stats.reading_time,
+            body: None,
+            length: None,
+        };
+
+        assert_eq!(entry.title, "Test Post");
+        assert_eq!(entry.url, "/dev/test-post/");
+        assert_eq!(entry.tags.len(), 2);
+    }
+
+    #[test]
+    fn test_full_text_index_positions() {
+        let index = FullTextIndex::build(&["rust is fast rust", "검색 엔진"], 2);
+
+        assert_eq!(index.terms["rust"], vec![Posting(0, vec![0, 13])]);
+        assert_eq!(index.terms["검색"], vec![Posting(1, vec![0])]);
+        assert_eq!(index.terms["엔진"], vec![Posting(1, vec![3])]);
+        assert!(!index.terms.contains_key("검"));
+    }
+
+    #[test]
+    fn test_full_text_index_shards() {
+        let index = FullTextIndex::build(&["alpha beta gamma delta", "beta"], 2);
+        let term_count = index.terms.len();
+
+        let (index, shards) = index.into_shards(3);
+
+        assert!(index.terms.is_empty());
+        assert_eq!(
+            index.shards,
+            vec![
+                "search-index-0.json",
+                "search-index-1.json",
+                "search-index-2.json"
+            ]
+        );
+        assert_eq!(
+            shards.iter().map(|(_, s)| s.terms.len()).sum::<usize>(),
+            term_count
+        );
+
+        let beta_shard = tokenizer::fnv1a("beta") as usize % 3;
+        assert_eq!(
+            shards[beta_shard].1.terms["beta"],
+            vec![Posting(0, vec![6]), Posting(1, vec![0])]
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use super::tokenizer::{self, Token};
+use super::{IndexShard, Posting, SearchEntry, SearchIndex};
+use crate::config::SearchBoosts;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// BM25 term frequency saturation
+const K1: f64 = 1.2;
+/// BM25 document length normalization
+const B: f64 = 0.75;
+/// Characters of context shown on each side of the first body match
+const SNIPPET_CONTEXT: usize = 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub category: String,
+    pub tags: Vec<String>,
+    pub date: String,
+    pub score: f64,
+    /// Body text around the first match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+}
+
+/// Term offsets and token count of one field of one post
+#[derive(Debug, Default)]
+struct FieldTerms {
+    terms: HashMap<String, Vec<usize>>,
+    length: usize,
+}
+
+impl FieldTerms {
+    fn new(tokens: Vec<Token>) -> Self {
+        let length = tokens.len();
+        let mut terms: HashMap<String, Vec<usize>> = HashMap::new();
+        for token in tokens {
+            terms.entry(token.term).or_default().push(token.offset);
+        }
+        Self { terms, length }
+    }
+
+    fn frequency(&self, term: &str) -> usize {
+        self.terms.get(term).map_or(0, Vec::len)
+    }
+}
+
+#[derive(Debug)]
+struct Document {
+    entry: SearchEntry,
+    title: FieldTerms,
+    tags: FieldTerms,
+    /// Post body, or the description or excerpt when the index has no full text
+    body: FieldTerms,
+    body_text: Option<String>,
+}
+
+/// Ranks posts in a search index with BM25 over title, tags and body
+#[derive(Debug)]
+pub struct SearchEngine {
+    documents: Vec<Document>,
+    /// Every term of every field, for prefix matches
+    vocabulary: BTreeSet<String>,
+    ngram: usize,
+    boosts: SearchBoosts,
+    avg_title: f64,
+    avg_tags: f64,
+    avg_body: f64,
+}
+
+impl SearchEngine {
+    /// Load `search-index.json` and its shards from a build output directory
+    pub fn load(output_dir: &Path) -> Result<Self> {
+        let path = output_dir.join("search-index.json");
+        let json = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read {} (run `ssg build` first)", path.display())
+        })?;
+        let mut index: SearchIndex = serde_json::from_str(&json)
+            .with_context(|| format!("Invalid search index: {}", path.display()))?;
+
+        if let Some(full_text) = &mut index.full_text {
+            for name in std::mem::take(&mut full_text.shards) {
+                let path = output_dir.join(&name);
+                let json = fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?;
+                let shard: IndexShard = serde_json::from_str(&json)
+                    .with_context(|| format!("Invalid search index shard: {}", path.display()))?;
+                full_text.terms.extend(shard.terms);
+            }
+        }
+
+        Ok(Self::new(index))
+    }
+
+    /// Bodies are matched through the index's postings. Only an index without
+    /// full text has its description or excerpt tokenized here instead.
+    pub fn new(index: SearchIndex) -> Self {
+        let ngram = index.full_text.as_ref().map_or(2, |f| f.ngram);
+
+        let mut postings: Option<Vec<HashMap<String, Vec<usize>>>> =
+            index.full_text.map(|full_text| {
+                let mut postings = vec![HashMap::new(); index.posts.len()];
+                for (term, list) in full_text.terms {
+                    for Posting(doc, offsets) in list {
+                        if let Some(terms) = postings.get_mut(doc) {
+                            terms.insert(term.clone(), offsets);
+                        }
+                    }
+                }
+                postings
+            });
+
+        let documents: Vec<Document> = index
+            .posts
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let body_text = entry
+                    .body
+                    .clone()
+                    .or_else(|| entry.description.clone())
+                    .or_else(|| entry.excerpt.clone());
+                let body = match postings.as_mut() {
+                    Some(postings) => {
+                        let terms = std::mem::take(&mut postings[i]);
+                        let length = entry
+                            .length
+                            .unwrap_or_else(|| terms.values().map(Vec::len).sum());
+                        FieldTerms { terms, length }
+                    }
+                    None => body_text
+                        .as_deref()
+                        .map(|text| FieldTerms::new(tokenizer::tokenize(text, ngram)))
+                        .unwrap_or_default(),
+                };
+
+                Document {
+                    title: FieldTerms::new(tokenizer::tokenize(&entry.title, ngram)),
+                    tags: FieldTerms::new(tokenizer::tokenize(&entry.tags.join(" "), ngram)),
+                    body,
+                    body_text,
+                    entry,
+                }
+            })
+            .collect();
+
+        let average = |field: fn(&Document) -> usize| {
+            if documents.is_empty() {
+                0.0
+            } else {
+                documents.iter().map(field).sum::<usize>() as f64 / documents.len() as f64
+            }
+        };
+
+        let vocabulary = documents
+            .iter()
+            .flat_map(|d| [&d.title, &d.tags, &d.body])
+            .flat_map(|field| field.terms.keys().cloned())
+            .collect();
+
+        Self {
+            avg_title: average(|d| d.title.length),
+            avg_tags: average(|d| d.tags.length),
+            avg_body: average(|d| d.body.length),
+            documents,
+            vocabulary,
+            ngram,
+            boosts: SearchBoosts::default(),
+        }
+    }
+
+    pub fn with_boosts(mut self, boosts: SearchBoosts) -> Self {
+        self.boosts = boosts;
+        self
+    }
+
+    /// Return up to `limit` posts matching the query, best first
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchResult> {
+        let terms: BTreeSet<String> = tokenizer::tokenize(query, self.ngram)
+            .into_iter()
+            .flat_map(|t| self.expand(t.term))
+            .collect();
+
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let idf: Vec<(&str, f64)> = terms
+            .iter()
+            .map(|term| (term.as_str(), self.idf(term)))
+            .collect();
+
+        let mut scored: Vec<(f64, &Document)> = self
+            .documents
+            .iter()
+            .filter_map(|doc| {
+                let score: f64 = idf
+                    .iter()
+                    .map(|(term, idf)| idf * self.term_score(doc, term))
+                    .sum();
+                (score > 0.0).then_some((score, doc))
+            })
+            .collect();
+
+        scored.sort_by(|a, b| {
+            b.0.total_cmp(&a.0)
+                .then_with(|| b.1.entry.date.cmp(&a.1.entry.date))
+        });
+
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(score, doc)| SearchResult {
+                title: doc.entry.title.clone(),
+                url: doc.entry.url.clone(),
+                category: doc.entry.category.clone(),
+                tags: doc.entry.tags.clone(),
+                date: doc.entry.date.clone(),
+                score,
+                snippet: Self::snippet(doc, &terms),
+            })
+            .collect()
+    }
+
+    /// A CJK query shorter than an n-gram, e.g. one syllable, matches the
+    /// indexed n-grams it starts
+    fn expand(&self, term: String) -> Vec<String> {
+        let chars = term.chars().count();
+        if chars >= self.ngram || !term.chars().all(tokenizer::is_cjk) {
+            return vec![term];
+        }
+
+        self.vocabulary
+            .range(term.clone()..)
+            .take_while(|indexed| indexed.starts_with(&term))
+            .cloned()
+            .collect()
+    }
+
+    fn idf(&self, term: &str) -> f64 {
+        let n = self.documents.len() as f64;
+        let df = self
+            .documents
+            .iter()
+            .filter(|d| {
+                d.title.frequency(term) + d.tags.frequency(term) + d.body.frequency(term) > 0
+            })
+            .count() as f64;
+
+        (1.0 + (n - df + 0.5) / (df + 0.5)).ln()
+    }
+
+    fn term_score(&self, doc: &Document, term: &str) -> f64 {
+        self.boosts.title * bm25(&doc.title, term, self.avg_title)
+            + self.boosts.tags * bm25(&doc.tags, term, self.avg_tags)
+            + self.boosts.body * bm25(&doc.body, term, self.avg_body)
+    }
+
+    fn snippet(doc: &Document, terms: &BTreeSet<String>) -> Option<String> {
+        let text = doc.body_text.as_deref()?;
+        let offset = terms
+            .iter()
+            .filter_map(|term| doc.body.terms.get(term))
+            .filter_map(|offsets| offsets.first())
+            .min()
+            .copied()?;
+
+        let chars: Vec<char> = text.chars().collect();
+        let start = offset.saturating_sub(SNIPPET_CONTEXT);
+        let end = (offset + SNIPPET_CONTEXT).min(chars.len());
+
+        let mut snippet: String = chars[start..end].iter().collect();
+        snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+        if start > 0 {
+            snippet.insert(0, '…');
+        }
+        if end < chars.len() {
+            snippet.push('…');
+        }
+
+        Some(snippet)
+    }
+}
+
+fn bm25(field: &FieldTerms, term: &str, avg_length: f64) -> f64 {
+    let tf = field.frequency(term) as f64;
+    if tf == 0.0 {
+        return 0.0;
+    }
+
+    let norm = if avg_length > 0.0 {
+        1.0 - B + B * field.length as f64 / avg_length
+    } else {
+        1.0
+    };
+
+    tf * (K1 + 1.0) / (tf + K1 * norm)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::FullTextIndex;
+
+    fn entry(title: &str, tags: &[&str], body: &str, date: &str) -> SearchEntry {
+        SearchEntry {
+            title: title.to_string(),
+            description: None,
+            url: format!("/dev/{}/", title.to_lowercase().replace(' ', "-")),
+            lang: "ko".to_string(),
+            category: "dev".to_string(),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            date: date.to_string(),
+            excerpt: None,
+            word_count: 0,
+            reading_time: 0,
+            body: Some(body.to_string()),
+            length: None,
+        }
+    }
+
+    fn engine(posts: Vec<SearchEntry>) -> SearchEngine {
+        SearchEngine::new(SearchIndex {
+            version: "1.0".to_string(),
+            posts,
+            full_text: None,
+        })
+    }
+
+    #[test]
+    fn test_title_match_outranks_body_match() {
+        let engine = engine(vec![
+            entry("Cooking", &[], "I wrote about rust once", "2024-01-02"),
+            entry("Rust Tips", &[], "Some tips", "2024-01-01"),
+        ]);
+
+        let results = engine.search("rust", 10);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "Rust Tips");
+        assert!(results[0].score > results[1].score);
+    }
+
+    #[test]
+    fn test_korean_query_matches_inflected_word() {
+        let engine = engine(vec![
+            entry(
+                "정적 사이트",
+                &["블로그"],
+                "검색을 지원합니다",
+                "2024-01-01",
+            ),
+            entry("Other", &[], "nothing here", "2024-01-01"),
+        ]);
+
+        let results = engine.search("검색", 10);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "정적 사이트");
+        assert_eq!(results[0].snippet.as_deref(), Some("검색을 지원합니다"));
+    }
+
+    #[test]
+    fn test_single_syllable_korean_query_matches_prefix() {
+        let engine = engine(vec![
+            entry(
+                "정적 사이트",
+                &["블로그"],
+                "검색을 지원합니다",
+                "2024-01-01",
+            ),
+            entry("Other", &[], "nothing here", "2024-01-01"),
+        ]);
+
+        let results = engine.search("검", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].snippet.as_deref(), Some("검색을 지원합니다"));
+
+        assert_eq!(engine.search("블", 10).len(), 1);
+        assert!(engine.search("잠", 10).is_empty());
+    }
+
+    #[test]
+    fn test_load_matches_bodies_through_shard_postings() {
+        let dir = tempfile::tempdir().unwrap();
+        let posts = vec![
+            entry("First", &[], "검색 엔진", "2024-01-02"),
+            entry("Second", &[], "static sites", "2024-01-01"),
+        ];
+        let bodies: Vec<&str> = posts.iter().map(|p| p.body.as_deref().unwrap()).collect();
+        let (full_text, shards) = FullTextIndex::build(&bodies, 2).into_shards(2);
+        for (name, shard) in &shards {
+            fs::write(dir.path().join(name), serde_json::to_string(shard).unwrap()).unwrap();
+        }
+        let index = SearchIndex {
+            version: "1.0".to_string(),
+            posts,
+            full_text: Some(full_text),
+        };
+        fs::write(
+            dir.path().join("search-index.json"),
+            serde_json::to_string(&index).unwrap(),
+        )
+        .unwrap();
+
+        let engine = SearchEngine::load(dir.path()).unwrap();
+
+        let results = engine.search("엔진", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "First");
+        assert_eq!(results[0].snippet.as_deref(), Some("검색 엔진"));
+        assert_eq!(engine.search("sites", 10)[0].title, "Second");
+    }
+
+    #[test]
+    fn test_tag_match_and_limit() {
+        let engine = engine(vec![
+            entry("A", &["web"], "", "2024-01-01"),
+            entry("B", &["web"], "", "2024-02-01"),
+            entry("C", &[], "", "2024-03-01"),
+        ]);
+
+        let results = engine.search("web", 1);
+
+        assert_eq!(results.len(), 1);
+        // Equal scores fall back to the newest post
+        assert_eq!(results[0].title, "B");
+        assert!(engine.search("", 10).is_empty());
+    }
+
+    #[test]
+    fn test_snippet_is_trimmed_around_match() {
+        let body = format!("{} needle {}", "a ".repeat(100), "b ".repeat(100));
+        let engine = engine(vec![entry("Post", &[], &body, "2024-01-01")]);
+
+        let snippet = engine.search("needle", 10)[0].snippet.clone().unwrap();
+
+        assert!(snippet.starts_with('…'));
+        assert!(snippet.ends_with('…'));
+        assert!(snippet.contains("needle"));
+    }
+}
@@ -0,0 +1,159 @@
+/// A normalized term and the character offset where it starts in the source text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub term: String,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Word,
+    Cjk,
+    Separator,
+}
+
+/// Split text into lowercase terms.
+///
+/// Runs of letters and digits become one term each. Korean, Chinese and
+/// Japanese text has no reliable word boundaries (Korean attaches particles
+/// to nouns), so CJK runs are split into overlapping character n-grams
+/// instead. A CJK run shorter than `ngram` is kept as a single term.
+pub fn tokenize(text: &str, ngram: usize) -> Vec<Token> {
+    let ngram = ngram.max(1);
+    let mut tokens = Vec::new();
+    let mut run: Vec<char> = Vec::new();
+    let mut run_start = 0;
+    let mut run_class = CharClass::Separator;
+
+    for (offset, c) in text.chars().enumerate() {
+        let class = classify(c);
+
+        if class != run_class {
+            flush_run(&run, run_start, run_class, ngram, &mut tokens);
+            run.clear();
+            run_start = offset;
+            run_class = class;
+        }
+
+        if class != CharClass::Separator {
+            run.push(c);
+        }
+    }
+
+    flush_run(&run, run_start, run_class, ngram, &mut tokens);
+
+    tokens
+}
+
+fn flush_run(run: &[char], start: usize, class: CharClass, ngram: usize, tokens: &mut Vec<Token>) {
+    match class {
+        CharClass::Separator => {}
+        CharClass::Word => tokens.push(Token {
+            term: run.iter().collect::<String>().to_lowercase(),
+            offset: start,
+        }),
+        CharClass::Cjk if run.len() <= ngram => tokens.push(Token {
+            term: run.iter().collect(),
+            offset: start,
+        }),
+        CharClass::Cjk => {
+            for (i, window) in run.windows(ngram).enumerate() {
+                tokens.push(Token {
+                    term: window.iter().collect(),
+                    offset: start + i,
+                });
+            }
+        }
+    }
+}
+
+fn classify(c: char) -> CharClass {
+    if is_cjk(c) {
+        CharClass::Cjk
+    } else if c.is_alphanumeric() {
+        CharClass::Word
+    } else {
+        CharClass::Separator
+    }
+}
+
+/// Hangul, kana and Han ideographs: scripts written without spaces between words
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x11FF     // Hangul Jamo
+        | 0x3040..=0x309F   // Hiragana
+        | 0x30A0..=0x30FF   // Katakana
+        | 0x3130..=0x318F   // Hangul Compatibility Jamo
+        | 0x3400..=0x4DBF   // CJK Unified Ideographs Extension A
+        | 0x4E00..=0x9FFF   // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF   // Hangul Syllables
+        | 0xF900..=0xFAFF   // CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F // CJK Extensions B-F and supplement
+    )
+}
+
+/// 32-bit FNV-1a hash over the UTF-8 bytes of a term.
+///
+/// Used to assign terms to index shards; clients compute the same hash to
+/// know which shard file to fetch.
+pub fn fnv1a(term: &str) -> u32 {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in term.as_bytes() {
+        hash ^= u32::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0193);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(text: &str) -> Vec<String> {
+        tokenize(text, 2).into_iter().map(|t| t.term).collect()
+    }
+
+    #[test]
+    fn test_tokenize_words() {
+        assert_eq!(
+            terms("Hello, Rust-lang 2024!"),
+            vec!["hello", "rust", "lang", "2024"]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_korean_bigrams() {
+        assert_eq!(terms("검색을 해요"), vec!["검색", "색을", "해요"]);
+        assert_eq!(terms("a 글"), vec!["a", "글"]);
+    }
+
+    #[test]
+    fn test_tokenize_mixed_scripts() {
+        assert_eq!(terms("Rust로 만든"), vec!["rust", "로", "만든"]);
+    }
+
+    #[test]
+    fn test_token_offsets_are_char_based() {
+        let tokens = tokenize("한글 rust", 2);
+        assert_eq!(
+            tokens[0],
+            Token {
+                term: "한글".to_string(),
+                offset: 0
+            }
+        );
+        assert_eq!(
+            tokens[1],
+            Token {
+                term: "rust".to_string(),
+                offset: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(""), 0x811c_9dc5);
+        assert_eq!(fnv1a("a"), 0xe40c_292c);
+    }
+}
@@ -0,0 +1,840 @@
+//! The dev server behind `ssg watch`.
+//!
+//! Serves the build output with the headers browsers rely on for media and
+//! caching (`HEAD`, byte ranges, `ETag`), answers search queries and keeps
+//! live reload streams open. Every connection is handled on its own thread,
+//! so an open event stream or a slow download never holds up other requests.
+//!
+//! While the last build is failing, the output of the last successful one is
+//! still served, with an error overlay over every page.
+
+use crate::config::{SearchBoosts, SsgConfig};
+use crate::diagnostics;
+use crate::livereload::{self, LiveReload};
+use crate::search::query::SearchEngine;
+use crate::slug;
+use anyhow::Result;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Dev server path answering search queries with JSON
+pub const SEARCH_ENDPOINT: &str = "/__search";
+
+/// Requests with a larger head are refused
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+
+/// How long a connection may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What the server needs from the site configuration
+#[derive(Debug, Clone)]
+struct Settings {
+    output_dir: PathBuf,
+    search_boosts: SearchBoosts,
+}
+
+/// Serves a site's build output. Clones share their settings, so the watcher
+/// can point a running server at a reloaded configuration.
+#[derive(Debug, Clone)]
+pub struct DevServer {
+    settings: Arc<RwLock<Settings>>,
+    /// Error overlay of the failing build, if any
+    overlay: Arc<RwLock<Option<String>>>,
+    /// Search index of the current build, loaded by the first query
+    search: Arc<RwLock<Option<Arc<SearchEngine>>>>,
+    live_reload: Option<LiveReload>,
+}
+
+impl DevServer {
+    pub fn new(config: &SsgConfig) -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(Settings::from_config(config))),
+            overlay: Arc::default(),
+            search: Arc::default(),
+            live_reload: None,
+        }
+    }
+
+    /// Accept live reload subscriptions and add the reload script to HTML
+    pub fn with_live_reload(mut self, live_reload: LiveReload) -> Self {
+        self.live_reload = Some(live_reload);
+        self
+    }
+
+    /// Pick up a changed configuration, e.g. a new output directory
+    pub fn update(&self, config: &SsgConfig) {
+        *self.settings.write().unwrap() = Settings::from_config(config);
+        self.refresh_search();
+    }
+
+    /// Have the next search query load the index of a new build
+    pub fn refresh_search(&self) {
+        *self.search.write().unwrap() = None;
+    }
+
+    /// Show a build error over every page, or clear it with `None`
+    pub fn set_error(&self, error: Option<&anyhow::Error>) {
+        *self.overlay.write().unwrap() = error.map(diagnostics::overlay);
+    }
+
+    /// Whether a build error is being shown
+    pub fn has_error(&self) -> bool {
+        self.overlay.read().unwrap().is_some()
+    }
+
+    /// Handle connections until the listener fails
+    pub fn serve(&self, listener: TcpListener) -> Result<()> {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let server = self.clone();
+                    std::thread::spawn(move || server.handle(stream));
+                }
+                Err(e) => eprintln!("Connection error: {}", e),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle(&self, mut stream: TcpStream) {
+        let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+
+        let request = match read_head(&mut stream) {
+            Ok(Some(head)) => Request::parse(&head),
+            Ok(None) => {
+                let response = Response::text(431, "Request header fields too large");
+                let _ = response.write_to(&mut stream, false);
+                return;
+            }
+            Err(_) => return,
+        };
+        let Some(request) = request else {
+            let _ = Response::text(400, "Bad request").write_to(&mut stream, false);
+            return;
+        };
+
+        if request.path == livereload::ENDPOINT {
+            if let Some(live_reload) = &self.live_reload {
+                let _ = stream.set_read_timeout(None);
+                live_reload.subscribe(stream);
+                return;
+            }
+        }
+
+        let response = self.respond(&request);
+        let _ = response.write_to(&mut stream, request.method == "HEAD");
+    }
+
+    fn respond(&self, request: &Request) -> Response {
+        if request.method != "GET" && request.method != "HEAD" {
+            return Response::text(405, "Method not allowed").with_header("Allow", "GET, HEAD");
+        }
+
+        let settings = self.settings.read().unwrap().clone();
+        if request.path == SEARCH_ENDPOINT {
+            return self.search(&settings, &request.query);
+        }
+
+        match resolve(&settings.output_dir, &request.path) {
+            Some(Resolved::File(path)) => self
+                .serve_file(request, &path)
+                .unwrap_or_else(|| self.not_found(&settings.output_dir, &request.path)),
+            Some(Resolved::Directory) => {
+                // Relative links in the page resolve against the directory
+                let location = match request.query.as_str() {
+                    "" => format!("{}/", request.raw_path),
+                    query => format!("{}/?{}", request.raw_path, query),
+                };
+                Response::text(302, "Found").with_header("Location", &location)
+            }
+            None => self.not_found(&settings.output_dir, &request.path),
+        }
+    }
+
+    fn serve_file(&self, request: &Request, path: &Path) -> Option<Response> {
+        let metadata = fs::metadata(path).ok()?;
+        let len = metadata.len();
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        let etag = format!("\"{:x}-{:x}\"", len, modified.as_nanos());
+        let content_type = content_type(path);
+        let is_html = content_type.starts_with("text/html");
+
+        // The overlay is not part of the file, so cached copies must not stand in
+        if is_html && self.has_error() {
+            return Some(self.html(200, fs::read(path).ok()?));
+        }
+
+        if request
+            .header("if-none-match")
+            .is_some_and(|header| etag_matches(header, &etag))
+        {
+            return Some(Response::new(304, Body::Empty).with_header("ETag", &etag));
+        }
+
+        if is_html {
+            let html = fs::read(path).ok()?;
+            return Some(self.html(200, html).with_header("ETag", &etag));
+        }
+
+        // A range only applies to the version of the file the client already has
+        let range = match request.header("if-range") {
+            Some(validator) if validator != etag => None,
+            _ => request.header("range"),
+        };
+        let response = match range.map_or(ByteRange::Full, |header| parse_range(header, len)) {
+            ByteRange::Full => Response::new(
+                200,
+                Body::File {
+                    path: path.to_path_buf(),
+                    offset: 0,
+                    len,
+                },
+            ),
+            ByteRange::Partial(start, end) => Response::new(
+                206,
+                Body::File {
+                    path: path.to_path_buf(),
+                    offset: start,
+                    len: end - start + 1,
+                },
+            )
+            .with_header("Content-Range", &format!("bytes {}-{}/{}", start, end, len)),
+            ByteRange::Unsatisfiable => Response::new(416, Body::Empty)
+                .with_header("Content-Range", &format!("bytes */{}", len)),
+        };
+
+        Some(
+            response
+                .with_header("Content-Type", content_type)
+                .with_header("Accept-Ranges", "bytes")
+                .with_header("ETag", &etag),
+        )
+    }
+
+    /// The closest `404.html`: the one under the path's first segment (every
+    /// language of a multilingual site has its own), then the site's own
+    fn not_found(&self, output_dir: &Path, path: &str) -> Response {
+        let first_segment = path.trim_start_matches('/').split('/').next().unwrap_or("");
+        let candidates = [
+            format!("/{}/404.html", first_segment),
+            "/404.html".to_string(),
+        ];
+
+        for candidate in &candidates {
+            if let Some(Resolved::File(page)) = resolve(output_dir, candidate) {
+                if let Ok(html) = fs::read(&page) {
+                    return self.html(404, html);
+                }
+            }
+        }
+
+        if self.has_error() {
+            // E.g. nothing has been built yet; the overlay needs a page to sit on
+            return self.html(404, b"<!DOCTYPE html>\n<html><body></body></html>".to_vec());
+        }
+        Response::text(404, "404 Not Found")
+    }
+
+    /// Answer `/__search?q=<query>[&limit=<n>]` with ranked results as JSON
+    fn search(&self, settings: &Settings, query: &str) -> Response {
+        let mut q = String::new();
+        let mut limit = 10;
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = slug::decode_from_url(&value.replace('+', " "));
+            match key {
+                "q" => q = value,
+                "limit" => limit = value.parse().unwrap_or(limit),
+                _ => {}
+            }
+        }
+
+        let (status, body) = match self.search_engine(settings) {
+            Ok(engine) => {
+                let results = engine.search(&q, limit);
+                (200, serde_json::json!({ "query": q, "results": results }))
+            }
+            Err(e) => (503, serde_json::json!({ "error": format!("{:#}", e) })),
+        };
+
+        Response::new(status, Body::Bytes(body.to_string().into_bytes()))
+            .with_header("Content-Type", "application/json; charset=utf-8")
+    }
+
+    /// The loaded search index, reading it from the output on first use.
+    /// A missing index isn't remembered, so it is picked up once built.
+    fn search_engine(&self, settings: &Settings) -> Result<Arc<SearchEngine>> {
+        if let Some(engine) = self.search.read().unwrap().as_ref() {
+            return Ok(Arc::clone(engine));
+        }
+
+        let mut search = self.search.write().unwrap();
+        if let Some(engine) = search.as_ref() {
+            return Ok(Arc::clone(engine));
+        }
+        let engine =
+            Arc::new(SearchEngine::load(&settings.output_dir)?.with_boosts(settings.search_boosts));
+        *search = Some(Arc::clone(&engine));
+        Ok(engine)
+    }
+
+    fn html(&self, status: u16, mut html: Vec<u8>) -> Response {
+        if let Some(overlay) = self.overlay.read().unwrap().as_deref() {
+            html = livereload::inject(&html, overlay);
+        }
+        // Pages only load the reload script when served here, never from the build
+        if self.live_reload.is_some() {
+            html = livereload::inject_script(&html);
+        }
+        Response::new(status, Body::Bytes(html))
+            .with_header("Content-Type", "text/html; charset=utf-8")
+    }
+}
+
+impl Settings {
+    fn from_config(config: &SsgConfig) -> Self {
+        Self {
+            output_dir: PathBuf::from(&config.build.output_dir),
+            search_boosts: config.build.search.boosts,
+        }
+    }
+}
+
+/// A parsed request line and headers
+#[derive(Debug)]
+struct Request {
+    method: String,
+    /// Path as sent, still percent-encoded
+    raw_path: String,
+    /// Decoded path
+    path: String,
+    query: String,
+    /// Header names are lowercased
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    fn parse(head: &[u8]) -> Option<Self> {
+        let head = std::str::from_utf8(head).ok()?;
+        let mut lines = head.split("\r\n");
+
+        let mut request_line = lines.next()?.split_whitespace();
+        let method = request_line.next()?.to_string();
+        let target = request_line.next()?;
+        if !target.starts_with('/') {
+            return None;
+        }
+        let (raw_path, query) = target.split_once('?').unwrap_or((target, ""));
+
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+
+        Some(Self {
+            method,
+            raw_path: raw_path.to_string(),
+            path: slug::decode_from_url(raw_path),
+            query: query.to_string(),
+            headers,
+        })
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Read up to the blank line ending the request head; `None` when it is too large
+fn read_head(stream: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut head = Vec::new();
+    let mut buffer = [0; 1024];
+
+    loop {
+        let n = stream.read(&mut buffer)?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        head.extend_from_slice(&buffer[..n]);
+
+        let end = head.windows(4).position(|w| w == b"\r\n\r\n");
+        if end.unwrap_or(head.len()) > MAX_HEAD_SIZE {
+            return Ok(None);
+        }
+        if let Some(end) = end {
+            head.truncate(end);
+            return Ok(Some(head));
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Resolved {
+    File(PathBuf),
+    /// A directory requested without its trailing slash
+    Directory,
+}
+
+/// Map a decoded URL path to a file in the output directory. Paths that
+/// would leave it, through `..` or a symlink, resolve to nothing.
+fn resolve(output_dir: &Path, path: &str) -> Option<Resolved> {
+    let mut file = output_dir.to_path_buf();
+    for segment in path.split('/') {
+        if segment.is_empty() || segment == "." {
+            continue;
+        }
+        let mut components = Path::new(segment).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(name)), None) if !segment.contains(['\\', '\0']) => {
+                file.push(name)
+            }
+            _ => return None,
+        }
+    }
+
+    if file.is_dir() {
+        if !path.ends_with('/') {
+            return Some(Resolved::Directory);
+        }
+        file.push("index.html");
+    }
+
+    let root = output_dir.canonicalize().ok()?;
+    let file = file.canonicalize().ok()?;
+    (file.starts_with(&root) && file.is_file()).then_some(Resolved::File(file))
+}
+
+#[derive(Debug, PartialEq)]
+enum ByteRange {
+    /// No usable range; send the whole file
+    Full,
+    /// First and last byte, inclusive
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// Parse a `Range` header against a file length. Only single `bytes` ranges
+/// are served partially; anything else gets the whole file.
+fn parse_range(header: &str, len: u64) -> ByteRange {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    match (start.parse::<u64>(), end.parse::<u64>()) {
+        // `-500`: the last 500 bytes
+        (Err(_), Ok(suffix)) if start.is_empty() => {
+            if suffix == 0 || len == 0 {
+                ByteRange::Unsatisfiable
+            } else {
+                ByteRange::Partial(len.saturating_sub(suffix), len - 1)
+            }
+        }
+        (Ok(start), _) if start >= len => ByteRange::Unsatisfiable,
+        // `500-`: from byte 500 to the end
+        (Ok(start), Err(_)) if end.is_empty() => ByteRange::Partial(start, len - 1),
+        (Ok(start), Ok(end)) if start <= end => ByteRange::Partial(start, end.min(len - 1)),
+        _ => ByteRange::Full,
+    }
+}
+
+/// Whether an `If-None-Match` header names the current version
+fn etag_matches(header: &str, etag: &str) -> bool {
+    header.trim() == "*"
+        || header
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == etag)
+}
+
+#[derive(Debug)]
+enum Body {
+    Empty,
+    Bytes(Vec<u8>),
+    /// Part of a file, streamed rather than read into memory
+    File {
+        path: PathBuf,
+        offset: u64,
+        len: u64,
+    },
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    headers: Vec<(&'static str, String)>,
+    body: Body,
+}
+
+impl Response {
+    fn new(status: u16, body: Body) -> Self {
+        Self {
+            status,
+            headers: vec![("Cache-Control", "no-cache".to_string())],
+            body,
+        }
+    }
+
+    fn text(status: u16, text: &str) -> Self {
+        Self::new(status, Body::Bytes(text.as_bytes().to_vec()))
+            .with_header("Content-Type", "text/plain; charset=utf-8")
+    }
+
+    fn with_header(mut self, name: &'static str, value: &str) -> Self {
+        self.headers.push((name, value.to_string()));
+        self
+    }
+
+    /// Send the response; `head_only` leaves out the body, as for `HEAD`
+    fn write_to(&self, out: &mut impl Write, head_only: bool) -> io::Result<()> {
+        let mut head = format!("HTTP/1.1 {} {}\r\n", self.status, reason(self.status));
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        let len = match &self.body {
+            Body::Empty => 0,
+            Body::Bytes(bytes) => bytes.len() as u64,
+            Body::File { len, .. } => *len,
+        };
+        if self.status != 304 {
+            head.push_str(&format!("Content-Length: {}\r\n", len));
+        }
+        head.push_str("Connection: close\r\n\r\n");
+        out.write_all(head.as_bytes())?;
+
+        if !head_only {
+            match &self.body {
+                Body::Empty => {}
+                Body::Bytes(bytes) => out.write_all(bytes)?,
+                Body::File { path, offset, len } => {
+                    let mut file = File::open(path)?;
+                    file.seek(SeekFrom::Start(*offset))?;
+                    io::copy(&mut file.take(*len), out)?;
+                }
+            }
+        }
+        out.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        206 => "Partial Content",
+        302 => "Found",
+        304 => "Not Modified",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        416 => "Range Not Satisfiable",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "",
+    }
+}
+
+/// MIME type for a file, by extension
+pub fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        // Documents
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json; charset=utf-8",
+        "webmanifest" => "application/manifest+json",
+        "xml" | "xsl" => "application/xml; charset=utf-8",
+        "rss" => "application/rss+xml; charset=utf-8",
+        "atom" => "application/atom+xml; charset=utf-8",
+        "txt" => "text/plain; charset=utf-8",
+        "md" | "markdown" => "text/markdown; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "yaml" | "yml" => "application/yaml; charset=utf-8",
+        "toml" => "application/toml; charset=utf-8",
+        "ics" => "text/calendar; charset=utf-8",
+        "vtt" => "text/vtt; charset=utf-8",
+        "srt" => "application/x-subrip",
+        "pdf" => "application/pdf",
+        "epub" => "application/epub+zip",
+        "wasm" => "application/wasm",
+
+        // Images
+        "png" => "image/png",
+        "apng" => "image/apng",
+        "jpg" | "jpeg" | "jfif" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "jxl" => "image/jxl",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+
+        // Fonts
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "eot" => "application/vnd.ms-fontobject",
+
+        // Audio
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "aac" => "audio/aac",
+        "oga" | "ogg" => "audio/ogg",
+        "opus" => "audio/opus",
+        "wav" => "audio/wav",
+        "flac" => "audio/flac",
+        "weba" => "audio/webm",
+        "mid" | "midi" => "audio/midi",
+
+        // Video
+        "mp4" | "m4v" => "video/mp4",
+        "webm" => "video/webm",
+        "ogv" => "video/ogg",
+        "mov" => "video/quicktime",
+        "mkv" => "video/x-matroska",
+        "avi" => "video/x-msvideo",
+        "mpeg" | "mpg" => "video/mpeg",
+        "ts" => "video/mp2t",
+        "m3u8" => "application/vnd.apple.mpegurl",
+
+        // Archives
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "7z" => "application/x-7z-compressed",
+
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn site() -> (TempDir, DevServer) {
+        let dir = TempDir::new().unwrap();
+        let output = dir.path().join("dist");
+        fs::create_dir_all(output.join("about")).unwrap();
+        fs::create_dir_all(output.join("en")).unwrap();
+        fs::write(output.join("about/index.html"), "<body>About</body>").unwrap();
+        fs::write(output.join("404.html"), "<body>Lost</body>").unwrap();
+        fs::write(output.join("en/404.html"), "<body>Not here</body>").unwrap();
+        fs::write(output.join("clip.mp4"), b"0123456789").unwrap();
+        fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+
+        let mut config = SsgConfig::default();
+        config.build.output_dir = output.to_string_lossy().to_string();
+        (dir, DevServer::new(&config))
+    }
+
+    fn request(server: &DevServer, head: &str) -> String {
+        let request = Request::parse(head.as_bytes()).unwrap();
+        let mut out = Vec::new();
+        server
+            .respond(&request)
+            .write_to(&mut out, request.method == "HEAD")
+            .unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn header<'a>(response: &'a str, name: &str) -> Option<&'a str> {
+        response
+            .lines()
+            .find_map(|line| line.strip_prefix(&format!("{}: ", name)))
+    }
+
+    #[test]
+    fn test_paths_stay_in_output_dir() {
+        let (dir, server) = site();
+        let output = dir.path().join("dist");
+
+        for path in [
+            "/../secret.txt",
+            "/about/../../secret.txt",
+            "/%2e%2e/secret.txt",
+            "/..%2fsecret.txt",
+        ] {
+            let response = request(&server, &format!("GET {} HTTP/1.1", path));
+            assert!(response.starts_with("HTTP/1.1 404"), "{}", path);
+            assert!(!response.contains("secret"), "{}", path);
+        }
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.path().join("secret.txt"), output.join("link.txt"))
+                .unwrap();
+            assert_eq!(resolve(&output, "/link.txt"), None);
+        }
+
+        assert_eq!(resolve(&output, "/about"), Some(Resolved::Directory));
+        assert!(matches!(
+            resolve(&output, "/about/"),
+            Some(Resolved::File(_))
+        ));
+        let response = request(&server, "GET /about?x=1 HTTP/1.1");
+        assert!(response.starts_with("HTTP/1.1 302"));
+        assert_eq!(header(&response, "Location"), Some("/about/?x=1"));
+    }
+
+    #[test]
+    fn test_byte_ranges() {
+        assert_eq!(parse_range("bytes=0-3", 10), ByteRange::Partial(0, 3));
+        assert_eq!(parse_range("bytes=5-", 10), ByteRange::Partial(5, 9));
+        assert_eq!(parse_range("bytes=-3", 10), ByteRange::Partial(7, 9));
+        assert_eq!(parse_range("bytes=8-100", 10), ByteRange::Partial(8, 9));
+        assert_eq!(parse_range("bytes=10-", 10), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 10), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-1,4-5", 10), ByteRange::Full);
+        assert_eq!(parse_range("items=0-1", 10), ByteRange::Full);
+
+        let (_dir, server) = site();
+        let response = request(&server, "GET /clip.mp4 HTTP/1.1\r\nRange: bytes=2-5");
+        assert!(response.starts_with("HTTP/1.1 206 Partial Content"));
+        assert_eq!(header(&response, "Content-Range"), Some("bytes 2-5/10"));
+        assert_eq!(header(&response, "Content-Type"), Some("video/mp4"));
+        assert!(response.ends_with("\r\n\r\n2345"));
+
+        let response = request(&server, "GET /clip.mp4 HTTP/1.1\r\nRange: bytes=20-");
+        assert!(response.starts_with("HTTP/1.1 416"));
+        assert_eq!(header(&response, "Content-Range"), Some("bytes */10"));
+    }
+
+    #[test]
+    fn test_head_and_etag() {
+        let (_dir, server) = site();
+
+        let response = request(&server, "HEAD /clip.mp4 HTTP/1.1");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert_eq!(header(&response, "Content-Length"), Some("10"));
+        assert!(response.ends_with("\r\n\r\n"));
+
+        let etag = header(&response, "ETag").unwrap();
+        let response = request(
+            &server,
+            &format!("GET /clip.mp4 HTTP/1.1\r\nIf-None-Match: W/\"x\", {}", etag),
+        );
+        assert!(response.starts_with("HTTP/1.1 304"));
+        assert!(response.ends_with("\r\n\r\n"));
+
+        let response = request(
+            &server,
+            "GET /clip.mp4 HTTP/1.1\r\nIf-None-Match: \"stale\"",
+        );
+        assert!(response.ends_with("0123456789"));
+
+        let response = request(&server, "POST /clip.mp4 HTTP/1.1");
+        assert!(response.starts_with("HTTP/1.1 405"));
+    }
+
+    #[test]
+    fn test_not_found_page() {
+        let (_dir, server) = site();
+
+        let response = request(&server, "GET /missing HTTP/1.1");
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+        assert_eq!(
+            header(&response, "Content-Type"),
+            Some("text/html; charset=utf-8")
+        );
+        assert!(response.ends_with("Lost</body>"));
+
+        let response = request(&server, "GET /en/missing/ HTTP/1.1");
+        assert!(response.ends_with("Not here</body>"));
+
+        let server = server.with_live_reload(LiveReload::new());
+        let response = request(&server, "GET /missing HTTP/1.1");
+        assert!(response.contains(livereload::ENDPOINT));
+    }
+
+    #[test]
+    fn test_error_overlay() {
+        let (_dir, server) = site();
+        let response = request(&server, "GET /about/ HTTP/1.1");
+        let etag = header(&response, "ETag").unwrap().to_string();
+
+        server.set_error(Some(&anyhow::anyhow!("Template <broken>")));
+        let response = request(
+            &server,
+            &format!("GET /about/ HTTP/1.1\r\nIf-None-Match: {}", etag),
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("About"));
+        assert!(response.contains("Template &lt;broken&gt;"));
+        assert_eq!(header(&response, "ETag"), None);
+
+        server.set_error(None);
+        let response = request(&server, "GET /about/ HTTP/1.1");
+        assert!(!response.contains("ssg-error-overlay"));
+    }
+
+    #[test]
+    fn test_search_index_loaded_once_per_build() {
+        let (dir, server) = site();
+        let index = dir.path().join("dist/search-index.json");
+        let write_index = |title: &str| {
+            let json = serde_json::json!({
+                "version": "1",
+                "posts": [{
+                    "title": title,
+                    "url": "/dev/post/",
+                    "category": "dev",
+                    "tags": [],
+                    "date": "2024-01-01",
+                }],
+            });
+            fs::write(&index, json.to_string()).unwrap();
+        };
+
+        let response = request(&server, "GET /__search?q=rust HTTP/1.1");
+        assert!(response.starts_with("HTTP/1.1 503"));
+
+        write_index("Rust notes");
+        let response = request(&server, "GET /__search?q=rust HTTP/1.1");
+        assert!(response.contains("Rust notes"));
+
+        // Queries keep using the loaded index until the next build
+        write_index("Rust handbook");
+        let response = request(&server, "GET /__search?q=rust HTTP/1.1");
+        assert!(response.contains("Rust notes"));
+
+        server.refresh_search();
+        let response = request(&server, "GET /__search?q=rust HTTP/1.1");
+        assert!(response.contains("Rust handbook"));
+    }
+
+    #[test]
+    fn test_read_head() {
+        let mut input: &[u8] = b"GET / HTTP/1.1\r\nHost: x\r\n\r\n";
+        let head = read_head(&mut input).unwrap().unwrap();
+        let request = Request::parse(&head).unwrap();
+        assert_eq!(request.header("host"), Some("x"));
+
+        let long = format!("GET / HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_HEAD_SIZE));
+        assert_eq!(read_head(&mut long.as_bytes()).unwrap(), None);
+    }
+}
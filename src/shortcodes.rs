@@ -1,6 +1,6 @@
 use anyhow::{anyhow, Result};
 use regex::Regex;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::OnceLock;
 
 /// Shortcode handler function type
@@ -143,6 +143,18 @@ impl ShortcodeRegistry {
         self.handlers.insert(name.to_string(), handler);
     }
 
+    /// Names of the registered shortcodes invoked in content
+    pub fn used_shortcodes(&self, content: &str) -> BTreeSet<String> {
+        static USED_RE: OnceLock<Regex> = OnceLock::new();
+        let re = USED_RE.get_or_init(|| Regex::new(r"\[(\w+)[^\]]*\]").unwrap());
+
+        re.captures_iter(content)
+            .map(|cap| cap.get(1).unwrap().as_str())
+            .filter(|name| self.handlers.contains_key(*name))
+            .map(|name| name.to_string())
+            .collect()
+    }
+
     /// Process all shortcodes in content
     pub fn process(&self, content: &str) -> Result<String> {
         let mut result = content.to_string();
@@ -264,7 +276,7 @@ fn parse_attributes(s: &str) -> HashMap<String, String> {
 }
 
 /// Escape HTML special characters
-fn escape_html(s: &str) -> String {
+pub(crate) fn escape_html(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
@@ -322,6 +334,18 @@ mod tests {
         assert_eq!(result, r#"[link text](https://example.com)"#);
     }
 
+    #[test]
+    fn test_used_shortcodes() {
+        let registry = ShortcodeRegistry::new();
+        let used = registry.used_shortcodes(
+            r#"[youtube id="x"] [unknown a="b"] [callout]hi[/callout] [link](https://example.com)"#,
+        );
+        assert_eq!(
+            used.into_iter().collect::<Vec<_>>(),
+            vec!["callout", "youtube"]
+        );
+    }
+
     #[test]
     fn test_escape_html() {
         assert_eq!(
@@ -0,0 +1,186 @@
+use crate::config::SsgConfig;
+use crate::pipeline::{BuildOptions, BuildPipeline, BuildReport};
+use crate::plugin::{Plugin, PluginManager};
+use crate::plugins::{builtin_plugins, external_plugins};
+use crate::shortcodes::{ShortcodeHandler, ShortcodeRegistry};
+use crate::watch::ChangeSet;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// A site ready to build: configuration, plugins and shortcodes
+pub struct Site {
+    pipeline: BuildPipeline,
+}
+
+impl Site {
+    pub fn builder(config: SsgConfig) -> SiteBuilder {
+        SiteBuilder {
+            config,
+            options: BuildOptions::default(),
+            plugins: Vec::new(),
+            shortcodes: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &SsgConfig {
+        self.pipeline.config()
+    }
+
+    pub fn plugin_names(&self) -> Vec<String> {
+        self.pipeline.plugin_names()
+    }
+
+    /// Build the whole site into `build.output_dir`
+    pub fn build(&mut self) -> Result<BuildReport> {
+        self.pipeline.run()
+    }
+
+    /// Rebuild only what `changes` affect, e.g. in watch mode; falls back to
+    /// [`Site::build`] when they may touch every output
+    pub fn rebuild(&mut self, changes: &ChangeSet) -> Result<BuildReport> {
+        self.pipeline.rebuild(changes)
+    }
+
+    /// Build one post against the metadata of the last full build
+    pub fn build_post(&mut self, path: &Path) -> Result<PathBuf> {
+        self.pipeline.build_single_post(path)
+    }
+}
+
+/// Collects plugins and shortcodes for a [`Site`]
+pub struct SiteBuilder {
+    config: SsgConfig,
+    options: BuildOptions,
+    plugins: Vec<Box<dyn Plugin>>,
+    shortcodes: Vec<(String, ShortcodeHandler)>,
+}
+
+impl SiteBuilder {
+    /// Skip posts unchanged since the last build (default: false)
+    pub fn with_incremental(mut self, enabled: bool) -> Self {
+        self.options.incremental = enabled;
+        self
+    }
+
+    /// Render posts on a worker pool (default: false); output is the same either way
+    pub fn with_parallel(mut self, enabled: bool) -> Self {
+        self.options.parallel = enabled;
+        self
+    }
+
+    /// Build scheduled and expired posts too (default: false), e.g. for previews
+    pub fn with_future(mut self, enabled: bool) -> Self {
+        self.options.future = enabled;
+        self
+    }
+
+    /// Build draft posts and pages too (default: false), e.g. for previews
+    pub fn with_drafts(mut self, enabled: bool) -> Self {
+        self.options.drafts = enabled;
+        self
+    }
+
+    /// Run a plugin after the built-in and external ones
+    pub fn with_plugin(mut self, plugin: impl Plugin + 'static) -> Self {
+        self.plugins.push(Box::new(plugin));
+        self
+    }
+
+    /// Register a shortcode, replacing a built-in or plugin shortcode of the same name
+    pub fn with_shortcode<F>(mut self, name: &str, handler: F) -> Self
+    where
+        F: Fn(&HashMap<String, String>, Option<&str>) -> Result<String> + Send + Sync + 'static,
+    {
+        self.shortcodes.push((name.to_string(), Box::new(handler)));
+        self
+    }
+
+    /// Initialize every plugin and load the theme
+    pub fn init(self) -> Result<Site> {
+        Ok(Site {
+            pipeline: self.into_pipeline()?,
+        })
+    }
+
+    pub(crate) fn into_pipeline(self) -> Result<BuildPipeline> {
+        let mut plugin_manager = PluginManager::new();
+        let plugins = builtin_plugins()
+            .into_iter()
+            .chain(external_plugins(&self.config)?)
+            .chain(self.plugins);
+        for plugin in plugins {
+            plugin_manager.register(plugin);
+        }
+        plugin_manager.init_all(&self.config)?;
+
+        let mut shortcode_registry = ShortcodeRegistry::new();
+        plugin_manager.register_shortcodes(&mut shortcode_registry);
+        for (name, handler) in self.shortcodes {
+            shortcode_registry.register(&name, handler);
+        }
+
+        BuildPipeline::new(
+            self.config,
+            self.options,
+            plugin_manager,
+            shortcode_registry,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugin::PluginContext;
+    use crate::types::Post;
+    use std::fs;
+
+    struct Signature;
+
+    impl Plugin for Signature {
+        fn name(&self) -> &str {
+            "signature"
+        }
+
+        fn on_post_rendered(
+            &self,
+            _post: &mut Post,
+            html: &mut String,
+            _ctx: &PluginContext,
+        ) -> Result<()> {
+            html.push_str("<p class=\"signature\">Signed</p>");
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_custom_plugin_and_shortcode() {
+        let root = tempfile::tempdir().unwrap();
+        let post_path = root.path().join("posts/dev/hello.md");
+        fs::create_dir_all(post_path.parent().unwrap()).unwrap();
+        fs::write(
+            &post_path,
+            "---\ntitle: \"Hello\"\ndate: 2024-01-01T00:00:00Z\n---\n\nBuilt in [year].\n",
+        )
+        .unwrap();
+
+        let mut config = SsgConfig::default();
+        config.build.content_dir = root.path().join("posts").to_string_lossy().to_string();
+        config.build.output_dir = root.path().join("dist").to_string_lossy().to_string();
+
+        let mut site = Site::builder(config)
+            .with_plugin(Signature)
+            .with_shortcode("year", |_, _| Ok("2024".to_string()))
+            .init()
+            .unwrap();
+
+        assert_eq!(site.plugin_names(), vec!["related_posts", "signature"]);
+
+        let output_path = site.build_post(&post_path).unwrap();
+        let html = fs::read_to_string(output_path).unwrap();
+
+        assert!(html.contains("Built in 2024."));
+        assert!(html.contains("<p class=\"signature\">Signed</p>"));
+    }
+}
@@ -0,0 +1,495 @@
+use crate::config::SsgConfig;
+use crate::feeds::FeedGenerator;
+use crate::metadata::{MetadataCache, PostMetadata};
+use crate::navigation::series_url;
+use crate::parser::Parser;
+use crate::slug;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// Maximum number of URLs a single sitemap file may list
+const MAX_URLS_PER_SITEMAP: usize = 50_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SitemapUrl {
+    /// Absolute, percent-encoded URL
+    pub loc: String,
+    pub lastmod: Option<DateTime<Utc>>,
+}
+
+pub struct SitemapGenerator;
+
+impl SitemapGenerator {
+    /// Write sitemap.xml, or a sitemap index plus numbered sitemaps for large sites
+    pub fn generate(
+        config: &SsgConfig,
+        metadata: &MetadataCache,
+        pages_dir: &Path,
+        output_dir: &Path,
+    ) -> Result<usize> {
+        let urls = Self::collect_urls(config, metadata, pages_dir);
+        let files = Self::render(&urls, &config.site.url, MAX_URLS_PER_SITEMAP);
+        Self::write(&files, output_dir)?;
+
+        Ok(urls.len())
+    }
+
+    /// Write rendered files, removing the `sitemap-N.xml` chunks of an earlier
+    /// build that no longer belong to the index
+    fn write(files: &[(String, String)], output_dir: &Path) -> Result<()> {
+        if let Ok(entries) = fs::read_dir(output_dir) {
+            for entry in entries {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                let is_chunk = name
+                    .strip_prefix("sitemap-")
+                    .and_then(|rest| rest.strip_suffix(".xml"))
+                    .is_some_and(|n| n.parse::<usize>().is_ok());
+                if is_chunk && !files.iter().any(|(filename, _)| *filename == name) {
+                    fs::remove_file(entry.path())?;
+                }
+            }
+        }
+
+        for (filename, xml) in files {
+            fs::write(output_dir.join(filename), xml)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn generate_robots(config: &SsgConfig, output_dir: &Path) -> Result<()> {
+        fs::write(output_dir.join("robots.txt"), Self::render_robots(config))?;
+        Ok(())
+    }
+
+    /// Every public URL the build writes: posts, pages, indices, tag and series pages.
+    /// Drafts and posts in hidden categories are left out, even from `--drafts`
+    /// previews, so crawlers are never pointed at them.
+    pub fn collect_urls(
+        config: &SsgConfig,
+        metadata: &MetadataCache,
+        pages_dir: &Path,
+    ) -> Vec<SitemapUrl> {
+        let languages = config.languages();
+        if languages.len() == 1 {
+            return Self::collect_language_urls(config, metadata, Some(pages_dir));
+        }
+
+        // Pages are not translated, so only the main language lists them
+        languages
+            .iter()
+            .flat_map(|language| {
+                Self::collect_language_urls(
+                    &config.localized(language),
+                    &metadata.for_language(&language.code),
+                    language.is_default.then_some(pages_dir),
+                )
+            })
+            .collect()
+    }
+
+    /// URLs of one language, with `config` localized to it
+    fn collect_language_urls(
+        config: &SsgConfig,
+        metadata: &MetadataCache,
+        pages_dir: Option<&Path>,
+    ) -> Vec<SitemapUrl> {
+        let site_url = config.site.url.trim_end_matches('/');
+        let url = |path: &str| format!("{}{}", site_url, path);
+        let per_page = config.build.posts_per_page.max(1);
+
+        let hidden: HashSet<&str> = metadata
+            .get_category_info()
+            .iter()
+            .filter(|c| c.hidden)
+            .map(|c| c.slug.as_str())
+            .collect();
+
+        let visible: Vec<&PostMetadata> = metadata
+            .posts
+            .iter()
+            .filter(|p| !p.frontmatter.draft && !hidden.contains(p.category.as_str()))
+            .collect();
+
+        let mut urls = vec![SitemapUrl {
+            loc: url("/"),
+            lastmod: Self::latest(&visible),
+        }];
+
+        for post in &visible {
+            urls.push(SitemapUrl {
+                loc: url(&format!(
+                    "/{}/{}/",
+                    slug::encode_for_url(&post.category),
+                    slug::encode_for_url(&post.slug)
+                )),
+                lastmod: Some(Self::lastmod(post)),
+            });
+        }
+
+        for page in pages_dir.map(Self::collect_pages).unwrap_or_default() {
+            urls.push(SitemapUrl {
+                loc: url(&format!("/{}/", slug::encode_for_url(&page))),
+                lastmod: None,
+            });
+        }
+
+        for category in metadata.get_category_info().iter().filter(|c| !c.hidden) {
+            let posts: Vec<&PostMetadata> = visible
+                .iter()
+                .filter(|p| p.category == category.slug)
+                .copied()
+                .collect();
+            let base = format!("/{}/", slug::encode_for_url(&category.slug));
+            Self::push_paginated(&mut urls, &url(&base), &posts, per_page);
+        }
+
+        let tags = metadata.get_tags();
+        if !tags.is_empty() {
+            urls.push(SitemapUrl {
+                loc: url("/tags/"),
+                lastmod: Self::latest(&visible),
+            });
+        }
+
+        for tag in tags {
+            let posts: Vec<&PostMetadata> = visible
+                .iter()
+                .filter(|p| p.frontmatter.tags.contains(&tag))
+                .copied()
+                .collect();
+            if posts.is_empty() {
+                continue;
+            }
+            let base = format!("/tag/{}/", slug::encode_for_url(&tag));
+            Self::push_paginated(&mut urls, &url(&base), &posts, per_page);
+        }
+
+        for series in metadata.get_series() {
+            let posts: Vec<&PostMetadata> = visible
+                .iter()
+                .filter(|p| p.frontmatter.series.as_deref() == Some(series.as_str()))
+                .copied()
+                .collect();
+            if posts.is_empty() {
+                continue;
+            }
+            urls.push(SitemapUrl {
+                loc: url(&series_url(&series)),
+                lastmod: Self::latest(&posts),
+            });
+        }
+
+        urls
+    }
+
+    /// Listing page plus `page/N/` URLs, matching IndexGenerator's pagination
+    fn push_paginated(
+        urls: &mut Vec<SitemapUrl>,
+        base_url: &str,
+        posts: &[&PostMetadata],
+        per_page: usize,
+    ) {
+        let total_pages = posts.len().div_ceil(per_page).max(1);
+        let lastmod = Self::latest(posts);
+
+        urls.push(SitemapUrl {
+            loc: base_url.to_string(),
+            lastmod,
+        });
+        for page_num in 2..=total_pages {
+            urls.push(SitemapUrl {
+                loc: format!("{}page/{}/", base_url, page_num),
+                lastmod,
+            });
+        }
+    }
+
+    /// Slugs of non-draft pages
+    fn collect_pages(pages_dir: &Path) -> Vec<String> {
+        if !pages_dir.exists() {
+            return Vec::new();
+        }
+
+        let mut pages: Vec<String> = WalkDir::new(pages_dir)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
+            .filter_map(|e| Parser::parse_page_file(e.path()).ok())
+            .filter(|page| !page.frontmatter.draft)
+            .map(|page| page.slug)
+            .collect();
+        pages.sort();
+        pages
+    }
+
+    fn lastmod(post: &PostMetadata) -> DateTime<Utc> {
+        let date = &post.frontmatter.date;
+        date.modified.unwrap_or(date.posted)
+    }
+
+    fn latest(posts: &[&PostMetadata]) -> Option<DateTime<Utc>> {
+        posts.iter().map(|p| Self::lastmod(p)).max()
+    }
+
+    /// Render `(filename, xml)` pairs: a single `sitemap.xml` urlset, or a
+    /// `sitemap.xml` index pointing at `sitemap-N.xml` chunks of `max_urls`
+    fn render(urls: &[SitemapUrl], site_url: &str, max_urls: usize) -> Vec<(String, String)> {
+        if urls.len() <= max_urls {
+            return vec![("sitemap.xml".to_string(), Self::render_urlset(urls))];
+        }
+
+        let site_url = site_url.trim_end_matches('/');
+        let mut files = Vec::new();
+        let mut index = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+        );
+
+        for (i, chunk) in urls.chunks(max_urls).enumerate() {
+            let filename = format!("sitemap-{}.xml", i + 1);
+
+            index.push_str("  <sitemap>\n");
+            index.push_str(&format!(
+                "    <loc>{}</loc>\n",
+                FeedGenerator::escape_xml(&format!("{}/{}", site_url, filename))
+            ));
+            if let Some(lastmod) = chunk.iter().filter_map(|u| u.lastmod).max() {
+                index.push_str(&format!(
+                    "    <lastmod>{}</lastmod>\n",
+                    lastmod.to_rfc3339()
+                ));
+            }
+            index.push_str("  </sitemap>\n");
+
+            files.push((filename, Self::render_urlset(chunk)));
+        }
+
+        index.push_str("</sitemapindex>\n");
+        files.insert(0, ("sitemap.xml".to_string(), index));
+        files
+    }
+
+    fn render_urlset(urls: &[SitemapUrl]) -> String {
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+        );
+
+        for url in urls {
+            xml.push_str("  <url>\n");
+            xml.push_str(&format!(
+                "    <loc>{}</loc>\n",
+                FeedGenerator::escape_xml(&url.loc)
+            ));
+            if let Some(lastmod) = url.lastmod {
+                xml.push_str(&format!(
+                    "    <lastmod>{}</lastmod>\n",
+                    lastmod.to_rfc3339()
+                ));
+            }
+            xml.push_str("  </url>\n");
+        }
+
+        xml.push_str("</urlset>\n");
+        xml
+    }
+
+    fn render_robots(config: &SsgConfig) -> String {
+        let mut robots = String::new();
+
+        for rule in &config.build.robots.rules {
+            robots.push_str(&format!("User-agent: {}\n", rule.user_agent));
+            for path in &rule.allow {
+                robots.push_str(&format!("Allow: {}\n", path));
+            }
+            for path in &rule.disallow {
+                robots.push_str(&format!("Disallow: {}\n", path));
+            }
+            if rule.allow.is_empty() && rule.disallow.is_empty() {
+                robots.push_str("Disallow:\n");
+            }
+            robots.push('\n');
+        }
+
+        if config.build.sitemap.enabled {
+            robots.push_str(&format!(
+                "Sitemap: {}/sitemap.xml\n",
+                config.site.url.trim_end_matches('/')
+            ));
+        }
+
+        robots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reading::ReadingStats;
+    use crate::types::{Category, Frontmatter, PostDate};
+    use chrono::TimeZone;
+
+    fn frontmatter(tags: &[&str], draft: bool) -> Frontmatter {
+        Frontmatter {
+            title: "Post".to_string(),
+            date: PostDate {
+                posted: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+                modified: None,
+            },
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            featured_image: None,
+            description: None,
+            draft,
+            series: None,
+            series_order: None,
+            expires: None,
+            lang: None,
+            translation_key: None,
+        }
+    }
+
+    fn category(slug: &str, hidden: bool) -> Category {
+        Category {
+            slug: slug.to_string(),
+            name: slug.to_string(),
+            description: String::new(),
+            index: 0,
+            hidden,
+            icon: None,
+            color: None,
+            cover_image: None,
+        }
+    }
+
+    #[test]
+    fn test_collect_urls_skips_drafts_and_hidden_categories() {
+        let mut config = SsgConfig::default();
+        config.site.url = "https://example.com/".to_string();
+        config.build.posts_per_page = 1;
+
+        let mut metadata = MetadataCache::new();
+        metadata.set_category_info(vec![category("dev", false), category("secret", true)]);
+        metadata.upsert_post(
+            "a".to_string(),
+            "dev".to_string(),
+            frontmatter(&["rust"], false),
+            ReadingStats::default(),
+        );
+        metadata.upsert_post(
+            "한글".to_string(),
+            "dev".to_string(),
+            frontmatter(&["rust"], false),
+            ReadingStats::default(),
+        );
+        metadata.upsert_post(
+            "b".to_string(),
+            "dev".to_string(),
+            frontmatter(&[], true),
+            ReadingStats::default(),
+        );
+        metadata.upsert_post(
+            "c".to_string(),
+            "secret".to_string(),
+            frontmatter(&["hidden"], false),
+            ReadingStats::default(),
+        );
+
+        let mut modified = frontmatter(&[], false);
+        modified.date.modified = Some(Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap());
+        metadata.upsert_post(
+            "d".to_string(),
+            "dev".to_string(),
+            modified,
+            ReadingStats::default(),
+        );
+
+        let urls = SitemapGenerator::collect_urls(&config, &metadata, Path::new("missing"));
+        let locs: Vec<&str> = urls.iter().map(|u| u.loc.as_str()).collect();
+
+        assert!(locs.contains(&"https://example.com/"));
+        assert!(locs.contains(&"https://example.com/dev/a/"));
+        assert!(locs.contains(&"https://example.com/dev/%ED%95%9C%EA%B8%80/"));
+        assert!(locs.contains(&"https://example.com/dev/page/3/"));
+        assert!(locs.contains(&"https://example.com/tag/rust/page/2/"));
+        assert!(!locs.iter().any(|l| l.contains("/b/")));
+        assert!(!locs
+            .iter()
+            .any(|l| l.contains("secret") || l.contains("hidden")));
+
+        let post = urls.iter().find(|u| u.loc.ends_with("/dev/d/")).unwrap();
+        assert_eq!(post.lastmod, modified_date());
+        assert_eq!(urls[0].lastmod, modified_date());
+    }
+
+    fn modified_date() -> Option<DateTime<Utc>> {
+        Some(Utc.with_ymd_and_hms(2024, 6, 1, 0, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn test_render_splits_into_sitemap_index() {
+        let urls: Vec<SitemapUrl> = (0..5)
+            .map(|i| SitemapUrl {
+                loc: format!("https://example.com/{}/?a=1&b=2", i),
+                lastmod: None,
+            })
+            .collect();
+
+        let single = SitemapGenerator::render(&urls, "https://example.com", 5);
+        assert_eq!(single.len(), 1);
+        assert!(single[0].1.contains("<urlset"));
+        assert!(single[0].1.contains("?a=1&amp;b=2"));
+
+        let split = SitemapGenerator::render(&urls, "https://example.com", 2);
+        let names: Vec<&str> = split.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "sitemap.xml",
+                "sitemap-1.xml",
+                "sitemap-2.xml",
+                "sitemap-3.xml"
+            ]
+        );
+        assert!(split[0].1.contains("<sitemapindex"));
+        assert!(split[0]
+            .1
+            .contains("<loc>https://example.com/sitemap-3.xml</loc>"));
+        assert_eq!(split[3].1.matches("<url>").count(), 1);
+
+        // Fewer chunks, or none at all, leave nothing stale behind
+        let output = tempfile::tempdir().unwrap();
+        SitemapGenerator::write(&split, output.path()).unwrap();
+        let fewer = SitemapGenerator::render(&urls, "https://example.com", 3);
+        SitemapGenerator::write(&fewer, output.path()).unwrap();
+        assert!(output.path().join("sitemap-2.xml").is_file());
+        assert!(!output.path().join("sitemap-3.xml").exists());
+        SitemapGenerator::write(&single, output.path()).unwrap();
+        assert!(!output.path().join("sitemap-1.xml").exists());
+        assert!(output.path().join("sitemap.xml").is_file());
+    }
+
+    #[test]
+    fn test_render_robots() {
+        let mut config = SsgConfig::default();
+        config.site.url = "https://example.com".to_string();
+
+        assert_eq!(
+            SitemapGenerator::render_robots(&config),
+            "User-agent: *\nDisallow:\n\nSitemap: https://example.com/sitemap.xml\n"
+        );
+
+        config.build.robots.rules[0].disallow = vec!["/private/".to_string()];
+        config.build.sitemap.enabled = false;
+
+        assert_eq!(
+            SitemapGenerator::render_robots(&config),
+            "User-agent: *\nDisallow: /private/\n\n"
+        );
+    }
+}
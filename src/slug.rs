@@ -1,4 +1,3 @@
-use blake3;
 use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 
 // Define characters that should NOT be percent-encoded
@@ -27,10 +26,32 @@ pub fn encode_for_url(input: &str) -> String {
 pub fn decode_from_url(input: &str) -> String {
     percent_encoding::percent_decode_str(input)
         .decode_utf8()
-        .unwrap_or_else(|_| std::borrow::Cow::Borrowed(input))
+        .unwrap_or(std::borrow::Cow::Borrowed(input))
         .to_string()
 }
 
+/// Turn free text such as a heading into a readable slug.
+/// Letters and digits in any script are kept (so Korean stays Korean),
+/// whitespace and separators become single hyphens, other punctuation is dropped.
+pub fn slugify(input: &str) -> String {
+    let mut slug = String::new();
+    let mut pending_hyphen = false;
+
+    for c in input.chars() {
+        if c.is_alphanumeric() {
+            if pending_hyphen && !slug.is_empty() {
+                slug.push('-');
+            }
+            pending_hyphen = false;
+            slug.extend(c.to_lowercase());
+        } else if c.is_whitespace() || c == '-' || c == '_' {
+            pending_hyphen = true;
+        }
+    }
+
+    slug
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,4 +82,13 @@ mod tests {
         assert!(encoded.len() <= 200);
         assert!(encoded.contains('-')); // Should have hash separator
     }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  Rust -- 2024 edition "), "rust-2024-edition");
+        assert_eq!(slugify("한글 제목: 소개"), "한글-제목-소개");
+        assert_eq!(slugify("C++ & Go?"), "c-go");
+        assert_eq!(slugify("!!!"), "");
+    }
 }
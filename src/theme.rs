@@ -1,11 +1,13 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::fs;
 use std::path::{Path, PathBuf};
+use tera::ast::Node;
 use tera::{Tera, Value};
 
 use crate::config::SsgConfig;
+use crate::diagnostics::SourceError;
 use crate::slug;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -44,7 +46,7 @@ pub struct ThemeEngine {
 
 impl ThemeEngine {
     pub fn new(ssg_config: &SsgConfig) -> Result<Self> {
-        let theme_dir = PathBuf::from("themes");
+        let theme_dir = PathBuf::from(&ssg_config.build.themes_dir);
         let theme_name = ssg_config.theme.name.clone();
 
         let active_theme = load_theme_metadata(&theme_dir, &theme_name)?;
@@ -75,19 +77,16 @@ impl ThemeEngine {
 
         let glob_pattern = format!("{}/**/*.html", primary_path.display());
         let mut tera = Tera::new(&glob_pattern)
+            .map_err(template_error)
             .context(format!("Failed to load templates from {:?}", primary_path))?;
 
         for path in self.template_paths.iter().skip(1) {
             if path.exists() {
                 let fallback_pattern = format!("{}/**/*.html", path.display());
-                match Tera::new(&fallback_pattern) {
-                    Ok(fallback_tera) => {
-                        tera.extend(&fallback_tera)?;
-                    }
-                    Err(_) => {
-                        continue;
-                    }
-                }
+                let fallback_tera = Tera::new(&fallback_pattern)
+                    .map_err(template_error)
+                    .context(format!("Failed to load templates from {:?}", path))?;
+                tera.extend(&fallback_tera)?;
             }
         }
 
@@ -118,6 +117,14 @@ impl ThemeEngine {
     }
 }
 
+/// Point at the template and position of a syntax error when Tera names them
+fn template_error(error: tera::Error) -> anyhow::Error {
+    match SourceError::template(&error) {
+        Some(source) => source.into(),
+        None => error.into(),
+    }
+}
+
 fn copy_dir_all(src: &Path, dst: &Path) -> Result<()> {
     fs::create_dir_all(dst)?;
 
@@ -165,7 +172,7 @@ fn load_theme_metadata(theme_dir: &Path, theme_name: &str) -> Result<ThemeMetada
         .context(format!("Failed to read {:?}", metadata_path))?;
 
     let mut metadata: ThemeMetadata =
-        serde_yaml::from_str(&content).context(format!("Failed to parse {:?}", metadata_path))?;
+        serde_yaml::from_str(&content).map_err(|e| SourceError::yaml(&metadata_path, &e))?;
 
     if metadata.name.is_empty() {
         metadata.name = theme_name.to_string();
@@ -288,6 +295,75 @@ fn validate_required_templates(tera: &Tera, theme: &ThemeMetadata) -> Result<()>
     Ok(())
 }
 
+/// Names of every template read when rendering `name`: the template itself,
+/// the templates it extends, and anything those include or import.
+pub fn template_dependencies(tera: &Tera, name: &str) -> BTreeSet<String> {
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![name.to_string()];
+
+    while let Some(current) = pending.pop() {
+        if !seen.insert(current.clone()) {
+            continue;
+        }
+
+        let Ok(template) = tera.get_template(&current) else {
+            continue;
+        };
+
+        pending.extend(template.parents.iter().cloned());
+        pending.extend(
+            template
+                .imported_macro_files
+                .iter()
+                .map(|(file, _)| file.clone()),
+        );
+        collect_includes(&template.ast, &mut pending);
+    }
+
+    seen.retain(|n| tera.get_template(n).is_ok());
+    seen
+}
+
+fn collect_includes(nodes: &[Node], out: &mut Vec<String>) {
+    for node in nodes {
+        match node {
+            Node::Include(_, names, _) => out.extend(names.iter().cloned()),
+            Node::Block(_, block, _) => collect_includes(&block.body, out),
+            Node::FilterSection(_, section, _) => collect_includes(&section.body, out),
+            Node::Forloop(_, forloop, _) => {
+                collect_includes(&forloop.body, out);
+                if let Some(empty) = &forloop.empty_body {
+                    collect_includes(empty, out);
+                }
+            }
+            Node::If(if_node, _) => {
+                for (_, _, body) in &if_node.conditions {
+                    collect_includes(body, out);
+                }
+                if let Some((_, body)) = &if_node.otherwise {
+                    collect_includes(body, out);
+                }
+            }
+            Node::MacroDefinition(_, definition, _) => collect_includes(&definition.body, out),
+            _ => {}
+        }
+    }
+}
+
+/// Hash the source file of every loaded template, keyed by template name
+pub fn hash_templates(tera: &Tera) -> HashMap<String, String> {
+    tera.get_template_names()
+        .filter_map(|name| {
+            let template = tera.get_template(name).ok()?;
+            let content = fs::read(template.path.as_ref()?).ok()?;
+            Some((
+                name.to_string(),
+                blake3::hash(&content).to_hex().to_string(),
+            ))
+        })
+        .collect()
+}
+
 fn urldecode_filter(value: &Value, _args: &HashMap<String, Value>) -> tera::Result<Value> {
     let s = tera::try_get_value!("urldecode", "value", String, value);
     let decoded = slug::decode_from_url(&s);
@@ -360,4 +436,24 @@ mod tests {
             &serde_yaml::Value::String("Helvetica".to_string())
         );
     }
+
+    #[test]
+    fn test_template_dependencies() {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(vec![
+            ("base.html", "{% block content %}{% endblock %}"),
+            ("partials/nav.html", "nav"),
+            (
+                "post.html",
+                r#"{% extends "base.html" %}{% block content %}{% if x %}{% include "partials/nav.html" %}{% endif %}{% endblock %}"#,
+            ),
+            ("tag.html", r#"{% extends "base.html" %}"#),
+        ])
+        .unwrap();
+
+        let deps: Vec<_> = template_dependencies(&tera, "post.html")
+            .into_iter()
+            .collect();
+        assert_eq!(deps, vec!["base.html", "partials/nav.html", "post.html"]);
+    }
 }
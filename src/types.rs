@@ -1,3 +1,5 @@
+use crate::excerpt::Excerpt;
+use crate::reading::ReadingStats;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Deserializer, Serialize};
 
@@ -55,6 +57,45 @@ pub struct Frontmatter {
     pub description: Option<String>,
     #[serde(default)]
     pub draft: bool,
+    /// Name of the series this post is a part of
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series: Option<String>,
+    /// Position within the series; parts without one follow, by date
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_order: Option<u32>,
+    /// The post is taken down from this time on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<DateTime<Utc>>,
+    /// Language code from `site.languages`; the main language when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// Shared by the translations of one post, linking them as alternates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation_key: Option<String>,
+}
+
+impl Frontmatter {
+    /// Whether the post is live at `now`
+    pub fn visibility(&self, now: DateTime<Utc>) -> Visibility {
+        if self.date.posted > now {
+            Visibility::Scheduled
+        } else if self.expires.is_some_and(|expires| expires <= now) {
+            Visibility::Expired
+        } else {
+            Visibility::Published
+        }
+    }
+}
+
+/// Publication state of a post relative to its `date.posted` and `expires`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Visibility {
+    Published,
+    /// `date.posted` is still in the future
+    Scheduled,
+    /// `expires` has passed
+    Expired,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -62,9 +103,30 @@ pub struct Post {
     pub slug: String,
     pub category: String,
     pub frontmatter: Frontmatter,
+    /// Drafts are only ever built with `--drafts`
+    pub is_draft: bool,
     pub content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub rendered_html: Option<String>,
+    /// Table of contents, filled in when the post is rendered
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub toc: Vec<TocEntry>,
+    /// Word count and reading time of the body
+    #[serde(default)]
+    pub stats: ReadingStats,
+    /// Leading part of the body, filled in when the post is rendered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excerpt: Option<Excerpt>,
+}
+
+/// A heading in the table of contents; `children` holds deeper headings under it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TocEntry {
+    pub level: u32,
+    pub title: String,
+    /// `id` attribute of the heading, for `#fragment` links
+    pub id: String,
+    pub children: Vec<TocEntry>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -121,4 +183,5 @@ pub struct Page {
     pub frontmatter: PageFrontmatter,
     pub content: String,
     pub rendered_html: Option<String>,
+    pub toc: Vec<TocEntry>,
 }
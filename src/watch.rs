@@ -0,0 +1,312 @@
+//! Turning file system events into the smallest rebuild that covers them.
+//!
+//! Watch mode collects a burst of events (editors often write a temporary
+//! file and rename it over the original), classifies every path and hands the
+//! resulting [`ChangeSet`] to [`crate::Site::rebuild`].
+
+use crate::config::{site_path, SsgConfig};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// The one site-level file config.yaml doesn't move
+const CONFIG_FILE: &str = "config.yaml";
+
+/// Directory a theme's static files live in
+const THEME_STATIC_DIR: &str = "static";
+
+/// What a changed file is to the site
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Post(PathBuf),
+    Page(PathBuf),
+    /// Theme templates, `theme.yaml` and translations
+    Template,
+    /// A file under `static/` or a theme's `static/`
+    Static(PathBuf),
+    /// An image or other file copied from the content directory
+    ContentAsset(PathBuf),
+    /// `.category.yaml`, or a directory added to or removed from the content directory
+    Category,
+    Config,
+}
+
+impl Change {
+    /// Classify a path relative to the site root. Build output, the build
+    /// cache and editor scratch files are not changes.
+    pub fn classify(path: &Path, config: &SsgConfig) -> Option<Self> {
+        let build = &config.build;
+        let name = path.file_name()?.to_string_lossy();
+        if is_scratch_file(&name)
+            || path.starts_with(site_path(&build.output_dir))
+            || path.starts_with(site_path(&build.cache_dir))
+        {
+            return None;
+        }
+
+        let is_markdown = path.extension().is_some_and(|ext| ext == "md");
+
+        if path == Path::new(CONFIG_FILE) {
+            Some(Self::Config)
+        } else if path.starts_with(site_path(&build.pages_dir)) {
+            is_markdown.then(|| Self::Page(path.to_path_buf()))
+        } else if path.starts_with(site_path(&build.content_dir)) {
+            if name == ".category.yaml" || path.extension().is_none() {
+                Some(Self::Category)
+            } else if is_markdown {
+                Some(Self::Post(path.to_path_buf()))
+            } else {
+                Some(Self::ContentAsset(path.to_path_buf()))
+            }
+        } else if path.starts_with(site_path(&build.static_dir)) {
+            Some(Self::Static(path.to_path_buf()))
+        } else if path.starts_with(site_path(&build.themes_dir)) {
+            if path.components().any(|c| c.as_os_str() == THEME_STATIC_DIR) {
+                Some(Self::Static(path.to_path_buf()))
+            } else {
+                Some(Self::Template)
+            }
+        } else if path.starts_with(site_path(&build.i18n_dir)) {
+            Some(Self::Template)
+        } else {
+            None
+        }
+    }
+}
+
+/// Source directories watch mode follows, as configured. Directories that
+/// don't exist yet, or sit inside another one, are left out.
+pub fn watched_dirs(config: &SsgConfig) -> Vec<PathBuf> {
+    let build = &config.build;
+    let mut dirs: Vec<PathBuf> = [
+        &build.content_dir,
+        &build.pages_dir,
+        &build.themes_dir,
+        &build.static_dir,
+        &build.i18n_dir,
+    ]
+    .into_iter()
+    .map(site_path)
+    .filter(|dir| dir.is_dir())
+    .collect();
+
+    dirs.sort();
+    dirs.dedup_by(|dir, parent| dir.starts_with(parent));
+    dirs
+}
+
+/// Swap files, backups and temporaries editors write next to the real file
+fn is_scratch_file(name: &str) -> bool {
+    (name.starts_with('.') && name != ".category.yaml")
+        || name.ends_with('~')
+        || name.ends_with(".swp")
+        || name.ends_with(".swx")
+        || name.ends_with(".tmp")
+        // Vim probes whether a directory is writable with this file
+        || name == "4913"
+}
+
+/// Every change in one burst of events, deduplicated
+#[derive(Debug, Clone, Default)]
+pub struct ChangeSet {
+    pub posts: BTreeSet<PathBuf>,
+    pub pages: BTreeSet<PathBuf>,
+    pub static_files: BTreeSet<PathBuf>,
+    pub content_assets: BTreeSet<PathBuf>,
+    pub templates: bool,
+    pub categories: bool,
+    pub config: bool,
+    /// Every path that classified as a change, in arrival order
+    pub paths: Vec<PathBuf>,
+}
+
+impl ChangeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a path relative to the site root; returns whether it was a change
+    pub fn add(&mut self, path: &Path, config: &SsgConfig) -> bool {
+        let Some(change) = Change::classify(path, config) else {
+            return false;
+        };
+
+        match change {
+            Change::Post(path) => {
+                self.posts.insert(path);
+            }
+            Change::Page(path) => {
+                self.pages.insert(path);
+            }
+            Change::Static(path) => {
+                self.static_files.insert(path);
+            }
+            Change::ContentAsset(path) => {
+                self.content_assets.insert(path);
+            }
+            Change::Template => self.templates = true,
+            Change::Category => self.categories = true,
+            Change::Config => self.config = true,
+        }
+        if !self.paths.iter().any(|p| p == path) {
+            self.paths.push(path.to_path_buf());
+        }
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Changes that affect every output, so only a full build covers them
+    pub fn is_structural(&self) -> bool {
+        self.templates || self.categories || self.config
+    }
+}
+
+/// Wait for a burst of events to settle: keep receiving until nothing has
+/// arrived for `quiet`, or `limit` has passed since `first`. Everything that
+/// queued up in the meantime, e.g. during the previous rebuild, comes along.
+pub fn debounce<T>(rx: &Receiver<T>, first: T, quiet: Duration, limit: Duration) -> Vec<T> {
+    let deadline = Instant::now() + limit;
+    let mut events = vec![first];
+
+    loop {
+        let timeout = quiet.min(deadline.saturating_duration_since(Instant::now()));
+        match rx.recv_timeout(timeout) {
+            Ok(event) => events.push(event),
+            Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_classify() {
+        let config = SsgConfig::default();
+        let classify = |path: &str| Change::classify(Path::new(path), &config);
+
+        assert_eq!(
+            classify("content/posts/dev/hello.md"),
+            Some(Change::Post(PathBuf::from("content/posts/dev/hello.md")))
+        );
+        assert_eq!(
+            classify("content/posts/dev/diagram.png"),
+            Some(Change::ContentAsset(PathBuf::from(
+                "content/posts/dev/diagram.png"
+            )))
+        );
+        assert_eq!(
+            classify("content/posts/dev/.category.yaml"),
+            Some(Change::Category)
+        );
+        assert_eq!(
+            classify("content/pages/about.md"),
+            Some(Change::Page(PathBuf::from("content/pages/about.md")))
+        );
+        assert_eq!(classify("themes/default/post.html"), Some(Change::Template));
+        assert_eq!(
+            classify("themes/default/i18n/en.yaml"),
+            Some(Change::Template)
+        );
+        assert_eq!(
+            classify("themes/default/static/css/main.css"),
+            Some(Change::Static(PathBuf::from(
+                "themes/default/static/css/main.css"
+            )))
+        );
+        assert_eq!(classify("config.yaml"), Some(Change::Config));
+
+        assert_eq!(classify("dist/index.html"), None);
+        assert_eq!(classify(".build-cache/cache.json"), None);
+        assert_eq!(classify("content/posts/dev/.hello.md.swp"), None);
+        assert_eq!(classify("content/posts/dev/hello.md~"), None);
+        assert_eq!(classify("content/posts/dev/4913"), None);
+    }
+
+    #[test]
+    fn test_classify_custom_layout() {
+        let mut config = SsgConfig::default();
+        config.build.content_dir = "./notes".to_string();
+        config.build.pages_dir = "site/pages".to_string();
+        config.build.static_dir = "site/public".to_string();
+        config.build.themes_dir = "look".to_string();
+        config.build.output_dir = "public".to_string();
+        config.build.cache_dir = "tmp/cache".to_string();
+        config.build.i18n_dir = "site/i18n".to_string();
+        let classify = |path: &str| Change::classify(Path::new(path), &config);
+
+        assert_eq!(
+            classify("notes/dev/hello.md"),
+            Some(Change::Post(PathBuf::from("notes/dev/hello.md")))
+        );
+        assert_eq!(
+            classify("site/pages/about.md"),
+            Some(Change::Page(PathBuf::from("site/pages/about.md")))
+        );
+        assert_eq!(
+            classify("site/public/robots.txt"),
+            Some(Change::Static(PathBuf::from("site/public/robots.txt")))
+        );
+        assert_eq!(classify("look/default/post.html"), Some(Change::Template));
+        assert_eq!(classify("site/i18n/ko.yaml"), Some(Change::Template));
+
+        assert_eq!(classify("content/posts/dev/hello.md"), None);
+        assert_eq!(classify("i18n/ko.yaml"), None);
+        assert_eq!(classify("themes/default/post.html"), None);
+        assert_eq!(classify("public/index.html"), None);
+        assert_eq!(classify("tmp/cache/cache.json"), None);
+    }
+
+    #[test]
+    fn test_change_set() {
+        let config = SsgConfig::default();
+        let mut changes = ChangeSet::new();
+
+        assert!(changes.add(Path::new("content/posts/dev/hello.md"), &config));
+        assert!(changes.add(Path::new("content/posts/dev/hello.md"), &config));
+        assert!(!changes.add(Path::new("content/posts/dev/.hello.md.swp"), &config));
+        assert!(changes.add(Path::new("static/site.css"), &config));
+
+        assert_eq!(changes.posts.len(), 1);
+        assert_eq!(changes.paths.len(), 2);
+        assert!(!changes.is_structural());
+
+        changes.add(Path::new("themes/default/base.html"), &config);
+        assert!(changes.is_structural());
+    }
+
+    #[test]
+    fn test_debounce_coalesces_bursts() {
+        let (tx, rx) = channel();
+        for i in 1..4 {
+            tx.send(i).unwrap();
+        }
+
+        let quiet = Duration::from_millis(20);
+        let events = debounce(&rx, 0, quiet, Duration::from_secs(1));
+        assert_eq!(events, vec![0, 1, 2, 3]);
+
+        // A steady stream of events is cut off at the limit
+        let sender = std::thread::spawn(move || {
+            for i in 0..100 {
+                if tx.send(i).is_err() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+        });
+        let start = Instant::now();
+        debounce(&rx, 0, quiet, Duration::from_millis(100));
+        assert!(start.elapsed() < Duration::from_millis(300));
+        drop(rx);
+        sender.join().unwrap();
+    }
+}
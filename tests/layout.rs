@@ -0,0 +1,99 @@
+//! Building a site whose directories are all somewhere other than the defaults
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use walkdir::WalkDir;
+
+fn copy_dir(src: &Path, dst: &Path) {
+    for entry in WalkDir::new(src).into_iter().filter_map(|e| e.ok()) {
+        let target = dst.join(entry.path().strip_prefix(src).unwrap());
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target).unwrap();
+        } else {
+            fs::copy(entry.path(), &target).unwrap();
+        }
+    }
+}
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_build_with_custom_layout() {
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path();
+
+    write(
+        &root.join("writing/dev/hello.md"),
+        "---\ntitle: \"Hello\"\ndate: 2024-01-01T00:00:00Z\n---\n\nHello from a custom layout.\n",
+    );
+    write(&root.join("writing/dev/diagram.svg"), "<svg></svg>");
+    write(
+        &root.join("site/about.md"),
+        "---\ntitle: \"About\"\n---\n\nAbout this site.\n",
+    );
+    write(&root.join("assets/robots-extra.txt"), "extra");
+    write(
+        &root.join("translations/ko.yaml"),
+        "recent_posts: Latest Writing\n",
+    );
+    copy_dir(
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("themes/default"),
+        &root.join("looks/default"),
+    );
+    write(
+        &root.join("config.yaml"),
+        "build:\n  content_dir: writing\n  pages_dir: site\n  static_dir: assets\n  \
+         themes_dir: looks\n  cache_dir: state\n  output_dir: public\n  \
+         i18n_dir: translations\n",
+    );
+
+    // Paths in config.yaml resolve against the site root, as `ssg` is run from there
+    let build = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_ssg"))
+            .args(["build", "--incremental"])
+            .current_dir(root)
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(
+            output.status.success(),
+            "{}{}",
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        stdout
+    };
+    let report = build();
+    assert!(report.contains("Built: 1\n"), "{}", report);
+    assert!(report.contains("Pages: 1\n"), "{}", report);
+
+    let public = root.join("public");
+    let post = fs::read_to_string(public.join("dev/hello/index.html")).unwrap();
+    assert!(post.contains("Hello from a custom layout."));
+    assert!(public.join("dev/diagram.svg").is_file());
+    let page = fs::read_to_string(public.join("about/index.html")).unwrap();
+    assert!(page.contains("About this site."));
+    assert_eq!(
+        fs::read_to_string(public.join("robots-extra.txt")).unwrap(),
+        "extra"
+    );
+    assert!(public.join("css").is_dir());
+    assert!(public.join("feed.xml").is_file());
+    let index = fs::read_to_string(public.join("index.html")).unwrap();
+    assert!(index.contains("Latest Writing"));
+
+    // The build cache goes where it is configured, and is used next time
+    assert!(root.join("state").read_dir().unwrap().next().is_some());
+    let report = build();
+    assert!(report.contains("Built: 0\n"), "{}", report);
+    assert!(report.contains("Skipped: 1\n"), "{}", report);
+
+    // Nothing falls back to the default locations
+    for default in ["content", "dist", "static", "themes", ".build-cache"] {
+        assert!(!root.join(default).exists(), "{} was created", default);
+    }
+}